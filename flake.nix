@@ -19,11 +19,17 @@
       version = "0.1.0";
       src = fs.toSource {
         root = ./.;
-        fileset = fs.unions [./style.css ./logo.png ./favicon.png];
+        fileset = fs.unions [./style.css ./themes/dark.css ./logo.png ./favicon.png];
       };
       installPhase = ''
         mkdir -p $out/share/doc/stagix
-        cp $src/* $out/share/doc/stagix/
+        cp $src/logo.png $src/favicon.png $out/share/doc/stagix/
+        {
+          cat $src/style.css
+          printf '\n@media (prefers-color-scheme: dark) {\n'
+          cat $src/themes/dark.css
+          printf '}\n'
+        } > $out/share/doc/stagix/style.css
       '';
     };
   in {
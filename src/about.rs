@@ -0,0 +1,141 @@
+use build_html::{Container, HtmlContainer as _, escape_html};
+use gix::{ObjectId, Repository};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use tracing::debug;
+
+/// The about page, rendering the readme, or the changelog or contributing guide for their pages,
+/// at the root of the tree of `head` as markdown if it is a markdown file, or as preformatted text
+/// otherwise.
+///
+/// Markdown with mermaid diagrams loads `mermaid_script`, if given, to draw them.
+pub(crate) fn get_about(
+    repo: &Repository,
+    head: ObjectId,
+    readme: &str,
+    mermaid_script: Option<&str>,
+) -> anyhow::Result<Container> {
+    debug!(repo=?repo.path(), ?readme, "get about");
+    let mut container =
+        Container::new(build_html::ContainerType::Div).with_attributes([("id", "about")]);
+    let tree = repo.find_commit(head)?.tree()?;
+    let Some(entry) = tree.find_entry(readme) else {
+        return Ok(container);
+    };
+    let blob = entry.object()?;
+    let text = String::from_utf8_lossy(&blob.data);
+    if readme.ends_with(".md") {
+        let html = render_markdown(&text);
+        if let Some(script) = mermaid_script
+            && html.contains(MERMAID_PRE)
+        {
+            container.add_raw(format!(
+                "{html}<script src=\"{}\"></script>",
+                escape_html(script)
+            ));
+        } else {
+            container.add_raw(html);
+        }
+    } else {
+        container.add_preformatted(escape_html(&text));
+    }
+    Ok(container)
+}
+
+/// The start of a mermaid diagram, left as its source for the mermaid script to draw.
+const MERMAID_PRE: &str = "<pre class=\"mermaid\">";
+
+/// Render markdown to html, escaping any raw html in it.
+///
+/// Code blocks of `mermaid` are kept as `<pre class="mermaid">` rather than `<pre><code>`, for a
+/// mermaid script to draw. Links and images to urls that aren't [safe](is_safe_url) are left out,
+/// keeping their text.
+pub(crate) fn render_markdown(text: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let mut in_mermaid = false;
+    // whether each link or image being rendered was left out
+    let mut dropped = Vec::new();
+    let parser = Parser::new_ext(text, options).filter_map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Some(Event::Text(html)),
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))
+            if info.split_whitespace().next() == Some("mermaid") =>
+        {
+            in_mermaid = true;
+            Some(Event::Html(MERMAID_PRE.into()))
+        }
+        Event::End(TagEnd::CodeBlock) if in_mermaid => {
+            in_mermaid = false;
+            Some(Event::Html("</pre>".into()))
+        }
+        Event::Start(Tag::Link { ref dest_url, .. } | Tag::Image { ref dest_url, .. }) => {
+            let safe = is_safe_url(dest_url);
+            dropped.push(!safe);
+            safe.then_some(event)
+        }
+        Event::End(TagEnd::Link | TagEnd::Image) => {
+            let dropped = dropped.pop().unwrap_or_default();
+            (!dropped).then_some(event)
+        }
+        event => Some(event),
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+/// Whether `url` is safe to link to from a page, being relative or of a scheme that can't run
+/// scripts: `http`, `https` or `mailto`.
+pub(crate) fn is_safe_url(url: &str) -> bool {
+    // browsers ignore whitespace and control characters in the scheme, as in `java\tscript:`
+    let url = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect::<String>();
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => {
+            matches!(
+                url[..i].to_ascii_lowercase().as_str(),
+                "http" | "https" | "mailto"
+            )
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_urls() {
+        for url in [
+            "https://example.com",
+            "HTTP://example.com",
+            "mailto:jane@example.com",
+            "docs/setup.md",
+            "../README.md",
+            "#usage",
+            "?a=b:c",
+            "dir/a:b",
+        ] {
+            assert!(is_safe_url(url), "{url:?}");
+        }
+    }
+
+    #[test]
+    fn unsafe_urls() {
+        for url in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "java\tscript:alert(1)",
+            " javascript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "vbscript:msgbox",
+            "file:///etc/passwd",
+        ] {
+            assert!(!is_safe_url(url), "{url:?}");
+        }
+    }
+}
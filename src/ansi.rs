@@ -0,0 +1,167 @@
+use crate::html::Escaped;
+
+/// The 16 standard terminal colours, normal then bright.
+const PALETTE: [&str; 16] = [
+    "#000000", "#aa0000", "#00aa00", "#aa5500", "#0000aa", "#aa00aa", "#00aaaa", "#aaaaaa",
+    "#555555", "#ff5555", "#55ff55", "#ffff55", "#5555ff", "#ff55ff", "#55ffff", "#ffffff",
+];
+
+/// Whether `text` has ANSI escape sequences, such as a captured terminal log.
+pub(crate) fn has_ansi(text: &str) -> bool {
+    text.contains("\x1b[")
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    /// Index into the palette.
+    foreground: Option<usize>,
+    background: Option<usize>,
+}
+
+impl Style {
+    fn css(&self) -> String {
+        let mut css = String::new();
+        if self.bold {
+            css.push_str("font-weight: bold;");
+        }
+        if let Some(fg) = self.foreground {
+            css.push_str(&format!("color: {};", PALETTE[fg]));
+        }
+        if let Some(bg) = self.background {
+            css.push_str(&format!("background-color: {};", PALETTE[bg]));
+        }
+        css
+    }
+
+    /// Apply the parameters of a select graphic rendition sequence, like `1;31`.
+    fn apply(&mut self, params: &str) {
+        let mut params = params.split(';').map(|p| p.parse::<usize>().unwrap_or(0));
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.foreground = Some(param - 30),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(param - 40),
+                49 => self.background = None,
+                90..=97 => self.foreground = Some(param - 90 + 8),
+                100..=107 => self.background = Some(param - 100 + 8),
+                // 256 colours, only those in the palette being shown
+                38 | 48 => {
+                    let colour = match params.next() {
+                        Some(5) => params.next().filter(|&c| c < PALETTE.len()),
+                        // rgb colours take three more parameters
+                        Some(2) => {
+                            params.nth(2);
+                            None
+                        }
+                        _ => None,
+                    };
+                    if param == 38 {
+                        self.foreground = colour;
+                    } else {
+                        self.background = colour;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Renders lines with ANSI escape sequences as html, colours and bold becoming styled spans and
+/// any other sequences being stripped.
+///
+/// The style carries over from one line to the next, as in a terminal.
+#[derive(Debug, Default)]
+pub(crate) struct AnsiRenderer {
+    style: Style,
+}
+
+impl AnsiRenderer {
+    pub(crate) fn line_html(&mut self, line: &str) -> Escaped {
+        let mut html = String::new();
+        let mut open = self.open_span(&mut html);
+        let mut rest = line;
+        while let Some(start) = rest.find('\x1b') {
+            html.push_str(Escaped::new(&rest[..start]).as_str());
+            rest = &rest[start + 1..];
+            let Some(sequence) = rest.strip_prefix('[') else {
+                // a lone escape, or a sequence other than CSI, is dropped
+                continue;
+            };
+            let Some(end) = sequence.find(|c: char| ('\x40'..='\x7e').contains(&c)) else {
+                rest = "";
+                break;
+            };
+            if sequence[end..].starts_with('m') {
+                if open {
+                    html.push_str("</span>");
+                }
+                self.style.apply(&sequence[..end]);
+                open = self.open_span(&mut html);
+            }
+            rest = &sequence[end + 1..];
+        }
+        html.push_str(Escaped::new(rest).as_str());
+        if open {
+            html.push_str("</span>");
+        }
+        Escaped::markup(html)
+    }
+
+    /// Open a span for the current style if it isn't the default, returning whether it did.
+    fn open_span(&self, html: &mut String) -> bool {
+        if self.style == Style::default() {
+            return false;
+        }
+        html.push_str(&format!("<span style=\"{}\">", self.style.css()));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colours_become_spans() {
+        let mut renderer = AnsiRenderer::default();
+        assert_eq!(
+            renderer.line_html("\x1b[1;31merror\x1b[0m: a < b").as_str(),
+            "<span style=\"font-weight: bold;color: #aa0000;\">error</span>: a &lt; b"
+        );
+    }
+
+    #[test]
+    fn style_carries_over_lines() {
+        let mut renderer = AnsiRenderer::default();
+        assert_eq!(
+            renderer.line_html("\x1b[32mok").as_str(),
+            "<span style=\"color: #00aa00;\">ok</span>"
+        );
+        assert_eq!(
+            renderer.line_html("still").as_str(),
+            "<span style=\"color: #00aa00;\">still</span>"
+        );
+    }
+
+    #[test]
+    fn other_sequences_are_stripped() {
+        let mut renderer = AnsiRenderer::default();
+        assert_eq!(renderer.line_html("\x1b[2Kdone\x1b").as_str(), "done");
+        assert_eq!(renderer.line_html("\x1b[38;2;1;2;3mrgb").as_str(), "rgb");
+        assert_eq!(
+            renderer.line_html("\x1b[38;5;9mbright").as_str(),
+            "<span style=\"color: #ff5555;\">bright</span>"
+        );
+    }
+
+    #[test]
+    fn detects_escapes() {
+        assert!(has_ansi("\x1b[0m"));
+        assert!(!has_ansi("plain [0m"));
+    }
+}
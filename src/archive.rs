@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use gix::bstr::ByteSlice as _;
+use gix::objs::tree::EntryKind;
+use gix::traverse::tree::Recorder;
+use gix::{ObjectId, Repository};
+use tracing::debug;
+
+use crate::attributes::Linguist;
+use crate::safe_path::is_safe_tree_path;
+
+/// A `.tar.gz` snapshot of the tree at a ref.
+pub(crate) struct Snapshot {
+    /// The ref the snapshot was taken from, such as a tag name or `HEAD`.
+    pub(crate) refname: String,
+    pub(crate) commit: ObjectId,
+    pub(crate) path: PathBuf,
+    /// The archive, or `None` if the previous build already wrote it for the same commit.
+    pub(crate) data: Option<Vec<u8>>,
+}
+
+/// Snapshots of the tree of every tag, and of `head` as `HEAD` if it is set, only archiving those
+/// that `built` says weren't written at the same path and commit by the previous build.
+pub(crate) fn get_snapshots(
+    repo: &Repository,
+    repo_name: &str,
+    head: Option<ObjectId>,
+    built: impl Fn(&Path, ObjectId) -> bool,
+) -> anyhow::Result<Vec<Snapshot>> {
+    debug!(repo=?repo.path(), ?head, "get snapshots");
+    let mut refs = Vec::new();
+    for tag in repo.references()?.tags()? {
+        let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+        // tags of trees and blobs aren't snapshots of the history
+        let Ok(commit) = tag.peel_to_commit() else {
+            continue;
+        };
+        refs.push((tag.name().shorten().to_string(), commit));
+    }
+    if let Some(head) = head {
+        refs.push(("HEAD".to_owned(), repo.find_commit(head)?));
+    }
+
+    let mut snapshots = Vec::new();
+    for (refname, commit) in refs {
+        let prefix = format!("{}-{}", repo_name, refname.replace('/', "-"));
+        let path = PathBuf::from("archives").join(format!("{prefix}.tar.gz"));
+        // the same commit always gives the same archive
+        let data = if built(&path, commit.id) {
+            None
+        } else {
+            let mtime = commit.time()?.seconds.max(0) as u64;
+            Some(tree_archive(repo, &commit.tree()?, &prefix, mtime)?)
+        };
+        snapshots.push(Snapshot {
+            refname,
+            commit: commit.id,
+            path,
+            data,
+        });
+    }
+    Ok(snapshots)
+}
+
+/// Gzipped tarball of `tree` with every entry under `prefix`, stamped with `mtime` so the same
+/// tree always gives the same archive.
+fn tree_archive(
+    repo: &Repository,
+    tree: &gix::Tree<'_>,
+    prefix: &str,
+    mtime: u64,
+) -> anyhow::Result<Vec<u8>> {
+    let mut recorder = Recorder::default();
+    tree.traverse().depthfirst(&mut recorder)?;
+    let linguist = Linguist::load(tree)?;
+
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for entry in recorder.records {
+        if linguist.is_export_ignored(&entry.filepath.to_str_lossy())
+            || !is_safe_tree_path(&entry.filepath)
+        {
+            continue;
+        }
+        let path = format!("{}/{}", prefix, entry.filepath);
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(mtime);
+        match entry.mode.kind() {
+            EntryKind::Blob | EntryKind::BlobExecutable => {
+                let blob = repo.find_object(entry.oid)?;
+                let mode = if entry.mode.kind() == EntryKind::BlobExecutable {
+                    0o755
+                } else {
+                    0o644
+                };
+                header.set_mode(mode);
+                header.set_size(blob.data.len() as u64);
+                tar.append_data(&mut header, path, blob.data.as_slice())?;
+            }
+            EntryKind::Link => {
+                let target = repo.find_object(entry.oid)?;
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_mode(0o777);
+                header.set_size(0);
+                tar.append_link(&mut header, path, &*String::from_utf8_lossy(&target.data))?;
+            }
+            EntryKind::Tree => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+                tar.append_data(&mut header, format!("{path}/"), std::io::empty())?;
+            }
+            EntryKind::Commit => continue,
+        }
+    }
+    Ok(tar.into_inner()?.finish()?)
+}
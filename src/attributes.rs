@@ -0,0 +1,184 @@
+use gix::Tree;
+use gix::bstr::ByteSlice as _;
+use gix::glob::wildmatch::Mode;
+use tracing::debug;
+
+/// Linguist overrides and `export-ignore` declared in the root `.gitattributes` of a tree.
+#[derive(Debug, Default)]
+pub(crate) struct Linguist {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    language: Option<String>,
+    generated: Option<bool>,
+    vendored: Option<bool>,
+    export_ignore: Option<bool>,
+}
+
+/// The linguist attributes that apply to a single path.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct LinguistAttributes {
+    /// Language declared with `linguist-language`, overriding detection by extension.
+    pub(crate) language: Option<String>,
+    pub(crate) generated: bool,
+    pub(crate) vendored: bool,
+}
+
+impl Linguist {
+    pub(crate) fn load(tree: &Tree<'_>) -> anyhow::Result<Self> {
+        let Some(entry) = tree.lookup_entry_by_path(".gitattributes")? else {
+            debug!("no .gitattributes found");
+            return Ok(Self::default());
+        };
+        let object = entry.object()?;
+        Ok(Self::parse(&object.data.to_str_lossy()))
+    }
+
+    pub(crate) fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(pattern) = parts.next() else {
+                continue;
+            };
+            let mut rule = Rule {
+                pattern: pattern.to_owned(),
+                language: None,
+                generated: None,
+                vendored: None,
+                export_ignore: None,
+            };
+            for attr in parts {
+                let (name, value) = match attr.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (attr, None),
+                };
+                let (name, set) = match (name.strip_prefix('-'), name.strip_prefix('!')) {
+                    (Some(name), _) | (_, Some(name)) => (name, false),
+                    _ => (name, value.is_none_or(|v| v != "false")),
+                };
+                match name {
+                    "linguist-language" => rule.language = value.map(str::to_owned),
+                    "linguist-generated" => rule.generated = Some(set),
+                    "linguist-vendored" => rule.vendored = Some(set),
+                    "export-ignore" => rule.export_ignore = Some(set),
+                    _ => {}
+                }
+            }
+            if rule.language.is_some()
+                || rule.generated.is_some()
+                || rule.vendored.is_some()
+                || rule.export_ignore.is_some()
+            {
+                rules.push(rule);
+            }
+        }
+        Self { rules }
+    }
+
+    /// Attributes for `path`, later matching lines overriding earlier ones like git does.
+    pub(crate) fn lookup(&self, path: &str) -> LinguistAttributes {
+        let mut attributes = LinguistAttributes::default();
+        for rule in &self.rules {
+            if !matches(&rule.pattern, path) {
+                continue;
+            }
+            if let Some(language) = &rule.language {
+                attributes.language = Some(language.clone());
+            }
+            if let Some(generated) = rule.generated {
+                attributes.generated = generated;
+            }
+            if let Some(vendored) = rule.vendored {
+                attributes.vendored = vendored;
+            }
+        }
+        attributes
+    }
+
+    /// Whether `path` is left out of file pages and archives with `export-ignore`, like `git
+    /// archive` does, set on it or on any directory it is in.
+    pub(crate) fn is_export_ignored(&self, path: &str) -> bool {
+        let mut ignored = false;
+        let prefixes = path
+            .match_indices('/')
+            .map(|(i, _)| &path[..i])
+            .chain(std::iter::once(path));
+        for prefix in prefixes {
+            for rule in &self.rules {
+                if let Some(export_ignore) = rule.export_ignore
+                    && matches(&rule.pattern, prefix)
+                {
+                    ignored = export_ignore;
+                }
+            }
+            if ignored {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Match a gitattributes pattern: patterns without a slash match the file name at any depth,
+/// otherwise the full path from the root.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, value) = if pattern.trim_end_matches('/').contains('/') {
+        (pattern.trim_start_matches('/'), path)
+    } else {
+        (pattern, path.rsplit('/').next().unwrap_or(path))
+    };
+    gix::glob::wildmatch(
+        pattern.as_bytes().as_bstr(),
+        value.as_bytes().as_bstr(),
+        Mode::NO_MATCH_SLASH_LITERAL,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATTRIBUTES: &str = "\
+# generated and vendored code
+*.min.js linguist-generated
+vendor/** linguist-vendored
+*.h linguist-language=C
+vendor/keep/** -linguist-vendored
+
+docs export-ignore
+*.txt export-ignore
+keep.txt -export-ignore
+";
+
+    #[test]
+    fn lookup_linguist_attributes() {
+        let linguist = Linguist::parse(ATTRIBUTES);
+        assert!(linguist.lookup("web/app.min.js").generated);
+        assert!(!linguist.lookup("web/app.js").generated);
+        assert!(linguist.lookup("vendor/lib/a.c").vendored);
+        assert!(!linguist.lookup("vendor/keep/b.c").vendored);
+        assert!(!linguist.lookup("src/vendor/a.c").vendored);
+        assert_eq!(
+            linguist.lookup("include/x.h").language.as_deref(),
+            Some("C")
+        );
+    }
+
+    #[test]
+    fn export_ignored_paths() {
+        let linguist = Linguist::parse(ATTRIBUTES);
+        assert!(linguist.is_export_ignored("docs"));
+        assert!(linguist.is_export_ignored("docs/guide/index.md"));
+        assert!(linguist.is_export_ignored("notes/todo.txt"));
+        assert!(!linguist.is_export_ignored("keep.txt"));
+        assert!(!linguist.is_export_ignored("src/docs.rs"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{BTreeMap, HashMap};
+
+use build_html::{
+    Container, Html as _, HtmlContainer as _, HtmlElement, Table, TableCell, TableRow,
+};
+use gix::actor::SignatureRef;
+use gix::date::time::CustomFormat;
+use gix::date::time::format::ISO8601;
+use gix::mailmap::Snapshot;
+
+use crate::avatar::avatar_html;
+use crate::html::{Escaped, cell};
+use crate::size::format_size;
+
+/// Per author totals, gathered while walking the log.
+#[derive(Debug)]
+struct AuthorStats {
+    name: String,
+    commits: usize,
+    /// Commits crediting them with a `Co-authored-by` trailer.
+    co_authored: usize,
+    lines_added: usize,
+    lines_removed: usize,
+    /// Earliest and latest commit times in seconds.
+    first: i64,
+    last: i64,
+}
+
+/// Commit statistics for every author, identified by their mailmap resolved email, along with
+/// totals and commits per month.
+pub(crate) struct Authors {
+    mailmap: Snapshot,
+    /// Url of the authors' avatars, with `{md5}` for the hash of their email.
+    avatar_url_template: Option<String>,
+    by_email: HashMap<String, AuthorStats>,
+    /// Commits per `YYYY-MM` month of the author time.
+    by_month: BTreeMap<String, usize>,
+    commits: usize,
+    files_changed: usize,
+    lines_added: usize,
+    lines_removed: usize,
+    /// Whether the walk stopped before the start of the history.
+    truncated: bool,
+}
+
+impl Authors {
+    pub(crate) fn new(mailmap: Snapshot, avatar_url_template: Option<&str>) -> Self {
+        Self {
+            mailmap,
+            avatar_url_template: avatar_url_template.map(str::to_owned),
+            by_email: HashMap::new(),
+            by_month: BTreeMap::new(),
+            commits: 0,
+            files_changed: 0,
+            lines_added: 0,
+            lines_removed: 0,
+            truncated: false,
+        }
+    }
+
+    /// Note that the log was cut short, so the statistics only cover part of the history.
+    pub(crate) fn truncate(&mut self) {
+        self.truncated = true;
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        author: SignatureRef<'_>,
+        files_changed: usize,
+        lines_added: usize,
+        lines_removed: usize,
+    ) -> anyhow::Result<()> {
+        let time = author.time()?;
+        let seconds = time.seconds;
+        *self
+            .by_month
+            .entry(time.format(CustomFormat::new("%Y-%m"))?)
+            .or_default() += 1;
+        self.commits += 1;
+        self.files_changed += files_changed;
+        self.lines_added += lines_added;
+        self.lines_removed += lines_removed;
+        let stats = self.stats(author, seconds);
+        stats.commits += 1;
+        stats.lines_added += lines_added;
+        stats.lines_removed += lines_removed;
+        Ok(())
+    }
+
+    /// Credit `co_author` with a commit they were named in by a `Co-authored-by` trailer.
+    pub(crate) fn record_co_author(&mut self, co_author: SignatureRef<'_>) -> anyhow::Result<()> {
+        let seconds = co_author.time()?.seconds;
+        self.stats(co_author, seconds).co_authored += 1;
+        Ok(())
+    }
+
+    /// The stats of the mailmap resolved `signature`, extending their active period to include
+    /// `seconds`.
+    fn stats(&mut self, signature: SignatureRef<'_>, seconds: i64) -> &mut AuthorStats {
+        let resolved = self.mailmap.resolve(signature);
+        let stats = self
+            .by_email
+            .entry(resolved.email.to_string().to_lowercase())
+            .or_insert_with(|| AuthorStats {
+                name: resolved.name.to_string(),
+                commits: 0,
+                co_authored: 0,
+                lines_added: 0,
+                lines_removed: 0,
+                first: seconds,
+                last: seconds,
+            });
+        stats.first = stats.first.min(seconds);
+        stats.last = stats.last.max(seconds);
+        stats
+    }
+
+    /// The totals, commits per month and the table of authors, for `stats.html`, with the size of
+    /// the repo's objects on disk if known.
+    pub(crate) fn to_stats_container(&self, disk_size: Option<u64>) -> anyhow::Result<Container> {
+        let mut container = Container::new(build_html::ContainerType::Div);
+        if self.truncated {
+            container.add_paragraph("Statistics only cover the commits shown in the log.");
+        }
+
+        container.add_header(2, "Totals");
+        let mut totals = Table::new().with_attributes([("id", "totals")]);
+        let mut rows = vec![
+            ("Commits", self.commits.to_string()),
+            ("Authors", self.by_email.len().to_string()),
+            ("Files changed", self.files_changed.to_string()),
+            ("Lines added", self.lines_added.to_string()),
+            ("Lines removed", self.lines_removed.to_string()),
+        ];
+        if let Some(disk_size) = disk_size {
+            rows.push(("Size on disk", format_size(disk_size)));
+        }
+        for (label, value) in rows {
+            totals.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(TableCell::default().with_raw(label))
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(value),
+                    ),
+            );
+        }
+        container.add_table(totals);
+
+        container.add_header(2, "Activity");
+        let busiest = self.by_month.values().copied().max().unwrap_or(1);
+        let mut activity = Table::new()
+            .with_attributes([("id", "activity")])
+            .with_header_row(["Month", "Commits", ""]);
+        for (month, &commits) in self.by_month.iter().rev() {
+            let bar = HtmlElement::new(build_html::HtmlTag::Div)
+                .with_attribute("class", "bar")
+                .with_attribute("style", format!("width: {}em", commits * 20 / busiest))
+                .to_html_string();
+            activity.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(TableCell::default().with_raw(month))
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(commits),
+                    )
+                    .with_cell(TableCell::default().with_raw(bar)),
+            );
+        }
+        container.add_table(activity);
+
+        container.add_header(2, "Authors");
+        container.add_table(self.authors_table()?);
+        Ok(container)
+    }
+
+    fn authors_table(&self) -> anyhow::Result<Table> {
+        let mut authors = self.by_email.iter().collect::<Vec<_>>();
+        authors
+            .sort_by(|(_, a), (_, b)| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+
+        let mut table = Table::new()
+            .with_attributes([("id", "authors")])
+            .with_header_row([
+                "Author",
+                "Commits",
+                "Co-authored",
+                "+",
+                "-",
+                "First commit",
+                "Last commit",
+            ]);
+        for (email, author) in authors {
+            let mut name = Escaped::default();
+            if let Some(template) = &self.avatar_url_template {
+                name.push(&avatar_html(template, email));
+            }
+            name.push(&Escaped::new(&author.name));
+            table.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(cell(&name))
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(author.commits),
+                    )
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(author.co_authored),
+                    )
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(format!("+{}", author.lines_added)),
+                    )
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(format!("-{}", author.lines_removed)),
+                    )
+                    .with_cell(TableCell::default().with_raw(format_seconds(author.first)?))
+                    .with_cell(TableCell::default().with_raw(format_seconds(author.last)?)),
+            );
+        }
+        Ok(table)
+    }
+}
+
+fn format_seconds(seconds: i64) -> anyhow::Result<String> {
+    Ok(gix::date::Time::new(seconds, 0).format(ISO8601)?)
+}
@@ -0,0 +1,37 @@
+use md5::{Digest as _, Md5};
+
+use crate::html::Escaped;
+
+/// A small avatar image for `email`, from `template` with `{md5}` replaced by the md5 of the
+/// trimmed and lowercased email, as gravatar and libravatar expect.
+pub(crate) fn avatar_html(template: &str, email: &str) -> Escaped {
+    let hash = Md5::digest(email.trim().to_lowercase().as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    let src = template.replace("{md5}", &hash);
+    Escaped::markup(format!(
+        "<img class=\"avatar\" src=\"{}\" alt=\"\" width=\"16\" height=\"16\" loading=\"lazy\">",
+        Escaped::new(src)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn avatar_hashes_normalised_email() {
+        let html = avatar_html(
+            "https://example.com/avatar/{md5}?s=32",
+            " Jane@Example.COM ",
+        );
+        // md5 of jane@example.com
+        assert!(
+            html.as_str().contains(
+                "src=\"https://example.com/avatar/9e26471d35a78862c17e467d87cddedf?s=32\""
+            ),
+            "{html}"
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use clap::Parser;
+use stagix::SitesArgs;
+
+fn main() -> anyhow::Result<()> {
+    let args = SitesArgs::parse();
+
+    tracing_subscriber::fmt::init();
+
+    args.run()
+}
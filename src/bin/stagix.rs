@@ -0,0 +1,10 @@
+use clap::Parser;
+use stagix::Cli;
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    tracing_subscriber::fmt::init();
+
+    cli.run()
+}
@@ -0,0 +1,157 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use build_html::{
+    Container, Html as _, HtmlContainer as _, HtmlElement, Table, TableCell, TableRow, escape_html,
+};
+use gix::bstr::{BStr, ByteSlice as _};
+use gix::date::time::format::ISO8601;
+use gix::traverse::tree::Recorder;
+use gix::{ObjectId, Repository};
+use tracing::{debug, warn};
+
+use crate::progress::Progress;
+use crate::{TimeZone, Topology, to_root_path};
+
+/// Details of a commit shown against the lines it last modified.
+struct BlameCommit {
+    short_id: String,
+    author: String,
+    time: String,
+    /// Whether the commit has its own page to link to.
+    has_page: bool,
+}
+
+/// A blame page for every text file in the head tree, at `blame/<path>.html`.
+pub(crate) fn get_blames(
+    repo: &Repository,
+    head_id: ObjectId,
+    log_length: Option<usize>,
+    topology: Topology,
+    time_zone: TimeZone,
+) -> anyhow::Result<Vec<(PathBuf, Container)>> {
+    debug!(repo=?repo.path(), "get blames");
+    let mut recorder = Recorder::default();
+    repo.find_commit(head_id)?
+        .tree()?
+        .traverse()
+        .depthfirst(&mut recorder)?;
+
+    let paged_commits = topology
+        .walk(repo, head_id)?
+        .take(log_length.unwrap_or(usize::MAX))
+        .map(|rev| Ok(rev?.id))
+        .collect::<anyhow::Result<HashSet<_>>>()?;
+    let mut commits = HashMap::new();
+
+    let num_files = recorder
+        .records
+        .iter()
+        .filter(|entry| entry.mode.is_blob())
+        .count();
+    let mut progress = Progress::new("blamed", "files", Some(num_files));
+    let mut pages = Vec::new();
+    for entry in recorder.records {
+        if !entry.mode.is_blob() {
+            continue;
+        }
+        progress.inc();
+        if str::from_utf8(&repo.find_object(entry.oid)?.data).is_err() {
+            continue;
+        }
+        let path = PathBuf::from("blame").join(format!("{}.html", entry.filepath));
+        let outcome = match repo.blame_file(
+            entry.filepath.as_bstr(),
+            head_id,
+            gix::repository::blame_file::Options::default(),
+        ) {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                warn!(path=?entry.filepath, %error, "Failed to blame file");
+                continue;
+            }
+        };
+
+        let to_repo_root = to_root_path(&path);
+        let mut table = Table::new()
+            .with_attributes([("id", "blame")])
+            .with_header_row(["Commit", "Author", "Date", "Line", ""]);
+        for (blame_entry, lines) in outcome.entries_with_lines() {
+            if let Entry::Vacant(entry) = commits.entry(blame_entry.commit_id) {
+                entry.insert(blame_commit(
+                    repo,
+                    blame_entry.commit_id,
+                    &paged_commits,
+                    time_zone,
+                )?);
+            }
+            let commit = &commits[&blame_entry.commit_id];
+            for (j, line) in lines.iter().enumerate() {
+                let line_number = blame_entry.start_in_blamed_file as usize + j + 1;
+                // only label the first line of each hunk
+                let (id, author, time) = if j == 0 {
+                    let id = if commit.has_page {
+                        HtmlElement::new(build_html::HtmlTag::Link)
+                            .with_attribute(
+                                "href",
+                                format!("{}commits/{}.html", to_repo_root, blame_entry.commit_id),
+                            )
+                            .with_raw(&commit.short_id)
+                            .to_html_string()
+                    } else {
+                        commit.short_id.clone()
+                    };
+                    (id, escape_html(&commit.author), commit.time.clone())
+                } else {
+                    Default::default()
+                };
+                table.add_custom_body_row(
+                    TableRow::new()
+                        .with_cell(TableCell::default().with_raw(id))
+                        .with_cell(TableCell::default().with_raw(author))
+                        .with_cell(TableCell::default().with_raw(time))
+                        .with_cell(
+                            TableCell::default()
+                                .with_attributes([("class", "num")])
+                                .with_raw(line_number),
+                        )
+                        .with_cell(
+                            TableCell::default()
+                                .with_raw(format!("<pre>{}</pre>", escape_line(line.as_bstr()))),
+                        ),
+                );
+            }
+        }
+
+        let content = Container::new(build_html::ContainerType::Div)
+            .with_attributes([("id", "content")])
+            .with_paragraph(escape_html(&entry.filepath.to_string()))
+            .with_html(HtmlElement::new(build_html::HtmlTag::HorizontalRule))
+            .with_table(table);
+        pages.push((path, content));
+    }
+    Ok(pages)
+}
+
+fn blame_commit(
+    repo: &Repository,
+    id: ObjectId,
+    paged_commits: &HashSet<ObjectId>,
+    time_zone: TimeZone,
+) -> anyhow::Result<BlameCommit> {
+    let commit = repo.find_commit(id)?;
+    let author = commit.author()?;
+    Ok(BlameCommit {
+        short_id: id.to_hex_with_len(8).to_string(),
+        author: author.name.to_string(),
+        time: time_zone.apply(author.time()?).format(ISO8601)?,
+        has_page: paged_commits.contains(&id),
+    })
+}
+
+fn escape_line(line: &BStr) -> String {
+    let line = line.to_str_lossy();
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+    escape_html(line.strip_suffix('\r').unwrap_or(line))
+}
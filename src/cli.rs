@@ -0,0 +1,662 @@
+use std::fs::{create_dir_all, read_to_string};
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use tracing::info;
+
+use crate::sites::discover_repos;
+#[cfg(target_os = "linux")]
+use crate::watch_repo;
+use crate::{
+    CommitRefs, DateFormat, EmailDisplay, FileListing, Format, IndexColumn, IndexOptions,
+    IndexSort, InlineAssets, LogArchive, MergeDiff, Output, PageOptions, PagesOptions, RepoOptions,
+    ServerConfig, SiteConfig, Templates, Theme, TimeZone, Topology, Validation, build_index_page,
+    build_pages_dirs, build_repo_pages, build_site, build_sites, parse_size, verify_repo_pages,
+};
+
+/// Generate static html pages for git repos.
+#[derive(Debug, Parser)]
+pub struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Build an index page listing the repos, as `stagix-index` does.
+    Index(IndexArgs),
+    /// Build the pages of a single repo, as `stagix-repo` does.
+    Repo(RepoArgs),
+    /// Check that the pages of a repo are up to date without writing anything, exiting with an
+    /// error if any page is missing or stale.
+    Verify(VerifyArgs),
+    /// Publish the docs of each repo, as `stagix-pages` does.
+    Pages(PagesArgs),
+    /// Build several sites from a toml file, as `stagix-sites` does.
+    Sites(SitesArgs),
+    /// Build the index, the pages of every repo and their published docs, for all the repos in
+    /// a directory.
+    All(AllArgs),
+}
+
+impl Cli {
+    pub fn run(self) -> anyhow::Result<()> {
+        match self.command {
+            Command::Index(args) => args.run(),
+            Command::Repo(args) => args.run(),
+            Command::Verify(args) => args.run(),
+            Command::Pages(args) => args.run(),
+            Command::Sites(args) => args.run(),
+            Command::All(args) => args.run(),
+        }
+    }
+}
+
+/// Options for the layout of the pages, shared by every command that writes html.
+#[derive(Debug, clap::Args)]
+struct PageArgs {
+    /// Check generated pages for well-formedness problems, failing the build if strict.
+    #[clap(long, value_enum)]
+    validate: Option<Validation>,
+
+    /// Embed the stylesheet, logo and favicon into every page so each is a standalone file.
+    #[clap(long)]
+    self_contained: bool,
+
+    /// URL the output is served from, used for canonical links, Open Graph metadata and
+    /// sitemaps.
+    #[clap(long)]
+    base_url: Option<String>,
+
+    /// Path to an html fragment inserted at the top of every page.
+    #[clap(long)]
+    header_include: Option<PathBuf>,
+    /// Path to an html fragment inserted at the bottom of every page, after the main content.
+    #[clap(long, alias = "footer-file")]
+    footer_include: Option<PathBuf>,
+    /// Directory of `header.html`, `nav.html` and `footer.html` templates replacing the built-in
+    /// page layout.
+    #[clap(long)]
+    templates_dir: Option<PathBuf>,
+    /// How commit and tag times are shown on the log, refs and index pages.
+    #[clap(long, value_enum, default_value_t = DateFormat::Iso)]
+    date_format: DateFormat,
+    /// Offset to show times in: `original` for that recorded with each commit or tag, `utc`, or
+    /// a fixed offset such as `+01:00`.
+    #[clap(long, default_value = "original")]
+    time_zone: TimeZone,
+}
+
+impl PageArgs {
+    /// The page options these give, embedding the assets if the pages are self contained.
+    fn page_options(
+        self,
+        stylesheet: Option<&Path>,
+        logo: Option<&Path>,
+        favicon: Option<&Path>,
+        theme: Option<Theme>,
+    ) -> anyhow::Result<PageOptions> {
+        let inline_assets = if self.self_contained {
+            let assets = InlineAssets::load(stylesheet, logo, favicon)?;
+            Some(assets.or_theme(theme.unwrap_or_default()))
+        } else {
+            None
+        };
+        Ok(PageOptions {
+            validate: self.validate,
+            inline_assets,
+            base_url: self.base_url,
+            header_include: self.header_include.map(read_to_string).transpose()?,
+            footer_include: self.footer_include.map(read_to_string).transpose()?,
+            templates: self
+                .templates_dir
+                .as_deref()
+                .map(Templates::load)
+                .transpose()?,
+            date_format: self.date_format,
+            time_zone: self.time_zone,
+            ..Default::default()
+        })
+    }
+}
+
+/// Options for the table of repos on the index page.
+#[derive(Debug, clap::Args)]
+struct IndexTableArgs {
+    /// URL to use as the base for repos links.
+    #[clap(long)]
+    repos_url: Option<String>,
+    /// URL to use as the base for pages links.
+    #[clap(long)]
+    pages_url: Option<String>,
+    /// Columns to show in the index table, in order.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    columns: Vec<IndexColumn>,
+    /// Order of the repos in the index table, within each category.
+    #[clap(long, value_enum, default_value_t = IndexSort::LastCommit)]
+    sort: IndexSort,
+    /// Reverse the order of the repos in the index table.
+    #[clap(long)]
+    reverse: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct IndexArgs {
+    #[clap()]
+    repos: Vec<PathBuf>,
+    /// Directory to write the `index.html` file to, if unset the page is written to stdout.
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+    /// Path to css stylesheet that will be copied next to the `index.html`, requires --out-dir
+    #[clap(long, requires = "out_dir")]
+    stylesheet: Option<PathBuf>,
+    /// Path to png logo that will be copied next to the `index.html`, requires --out-dir
+    #[clap(long, requires = "out_dir")]
+    logo: Option<PathBuf>,
+    /// Path to png favicon that will be copied next to the `index.html`, requires --out-dir
+    #[clap(long, requires = "out_dir")]
+    favicon: Option<PathBuf>,
+    /// Built-in theme to use for any of the stylesheet, logo and favicon not given explicitly.
+    #[clap(long, value_enum)]
+    theme: Option<Theme>,
+    #[clap(flatten)]
+    table: IndexTableArgs,
+    /// Formats to also write the index in, alongside the `index.html`, requires --out-dir
+    #[clap(
+        long = "format",
+        value_enum,
+        value_delimiter = ',',
+        requires = "out_dir"
+    )]
+    formats: Vec<Format>,
+
+    #[clap(flatten)]
+    page: PageArgs,
+
+    /// Number of repos to process concurrently.
+    #[clap(short, long, default_value_t = 1)]
+    jobs: usize,
+}
+
+impl IndexArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        let page = self.page.page_options(
+            self.stylesheet.as_deref(),
+            self.logo.as_deref(),
+            self.favicon.as_deref(),
+            self.theme,
+        )?;
+        build_index_page(
+            self.repos,
+            IndexOptions {
+                out_dir: self.out_dir,
+                stylesheet: self.stylesheet,
+                logo: self.logo,
+                favicon: self.favicon,
+                repos_url: self.table.repos_url,
+                pages_url: self.table.pages_url,
+                theme: self.theme,
+                page,
+                columns: self.table.columns,
+                sort: self.table.sort,
+                reverse: self.table.reverse,
+                formats: self.formats,
+                jobs: self.jobs,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct RepoArgs {
+    #[clap()]
+    repo: PathBuf,
+    #[clap(long, default_value = ".")]
+    out_dir: PathBuf,
+    /// Archive to write the site into instead of --out-dir, one of `.tar`, `.tar.gz`, `.tgz` or
+    /// `.zip`.
+    #[clap(long, conflicts_with = "out_dir")]
+    out: Option<PathBuf>,
+    /// Build into a `.<name>.staging` directory next to --out-dir and swap it in once complete,
+    /// so a failed build leaves the previous output untouched. The parent of --out-dir must be
+    /// writable and --out-dir can't be the current directory.
+    #[clap(long, conflicts_with = "out")]
+    atomic: bool,
+    /// Number of commits to limit log history to, uses all commits if not set.
+    #[clap(short, long)]
+    log_length: Option<usize>,
+    /// Also split the entire history into a log page per period, linked from the log.
+    #[clap(long, value_enum)]
+    log_archive: Option<LogArchive>,
+    /// Number of commits per page of the log, with the log split over `log.html`, `log/2.html`
+    /// and onwards, and `log/1.html` redirecting to `log.html`.
+    #[clap(long)]
+    log_page_size: Option<usize>,
+    /// Also write a blame page for every text file, which is slow for long histories.
+    #[clap(long)]
+    blame: bool,
+    /// Write `.tar.gz` snapshots of every tag to `archives/`, linked from the refs page.
+    #[clap(long)]
+    snapshots: bool,
+    /// Also write a snapshot of HEAD.
+    #[clap(long, requires = "snapshots")]
+    snapshot_head: bool,
+    /// Export the repo to `<name>.git/` in the output so it can be cloned over plain HTTP.
+    #[clap(long)]
+    dumb_http: bool,
+    /// Branches to also generate pages for under `branches/<name>/`, or `all`.
+    #[clap(long, value_delimiter = ',')]
+    branches: Vec<String>,
+    /// Also generate files pages for the tree of every tag under `tags/<name>/`.
+    #[clap(long)]
+    tag_trees: bool,
+    /// Also write a compare page of the commits and changes from the latest tag to HEAD, linked
+    /// from the refs page.
+    #[clap(long)]
+    compare_head: bool,
+    /// Refs whose history gets commit pages under `commits/`, so commits only reachable from
+    /// other branches or tags have pages too.
+    #[clap(long, value_enum, default_value_t = CommitRefs::Head)]
+    commit_refs: CommitRefs,
+    /// Formats to also write the repo's data in, alongside the html pages.
+    #[clap(long = "format", value_enum, value_delimiter = ',')]
+    formats: Vec<Format>,
+    /// Branch to build the log and files from, instead of `HEAD`.
+    #[clap(long)]
+    default_branch: Option<String>,
+
+    /// Base URLs for cloning from, each giving a clone URL of `<base>/<name>.git` unless the repo
+    /// has a `url` file.
+    #[clap(long, value_delimiter = ',')]
+    clone_base_urls: Vec<String>,
+
+    #[clap(flatten)]
+    page: PageArgs,
+
+    /// Width of tab characters in file views and diffs.
+    #[clap(long)]
+    tab_width: Option<usize>,
+    /// Highlight trailing whitespace and carriage returns in file views and diffs.
+    #[clap(long)]
+    show_whitespace: bool,
+    /// Percentage of similarity for a deleted and added file to be shown as a rename or copy in
+    /// diffs.
+    #[clap(long, default_value_t = 50, value_parser = clap::value_parser!(u8).range(0..=100))]
+    rename_similarity: u8,
+    /// Show before and after images for changed images in diffs, embedded into the page.
+    #[clap(long)]
+    image_diffs: bool,
+    /// Number of changed files to show on a commit page, with the rest only in the raw diff.
+    #[clap(long)]
+    max_diff_files: Option<usize>,
+    /// Number of diff lines to show on a commit page, with the rest only in the raw diff.
+    #[clap(long)]
+    max_diff_lines: Option<usize>,
+    /// An ssh `allowed_signers` file to verify ssh signatures of commits and tags against, as
+    /// set by `gpg.ssh.allowedSignersFile` for git.
+    #[clap(long)]
+    allowed_signers: Option<PathBuf>,
+    /// How the diff of a merge commit is shown on its page.
+    #[clap(long, value_enum, default_value_t = MergeDiff::FirstParent)]
+    merge_diff: MergeDiff,
+    /// Which commits are in the log and get commit pages, `all` including those only reachable
+    /// through merged branches and drawing the commit graph in the log.
+    #[clap(long, value_enum, default_value_t = Topology::FirstParent)]
+    topology: Topology,
+    /// Show the committer dates in the log rather than the author dates, without changing the
+    /// order commits are listed in.
+    #[clap(long)]
+    show_committer_dates: bool,
+    /// List the commits in the log newest first by committer date rather than in the order of
+    /// the history, as after a rebase keeping the original dates, without the commit graph.
+    #[clap(long)]
+    committer_date_order: bool,
+    /// Url of author avatars to show in the log and authors tables, with `{md5}` replaced by the
+    /// md5 of each author's email, such as `https://seccdn.libravatar.org/avatar/{md5}`.
+    #[clap(long)]
+    avatar_url_template: Option<String>,
+    /// How the emails of authors, committers and taggers are shown on commit and tag pages, in
+    /// the feed, patches and data formats.
+    #[clap(long, value_enum, default_value_t = EmailDisplay::Full)]
+    emails: EmailDisplay,
+    /// Write a `search.html` page and `search.json` index of every file, commit and ref.
+    #[clap(long)]
+    search: bool,
+    /// Also write the files of `HEAD` and every tag under `blob/<commit>/`, as permalinks that
+    /// keep pointing at the same lines when `HEAD` moves.
+    #[clap(long)]
+    blob_pages: bool,
+    /// Also write an index page for every directory under `files/`, with breadcrumbs up to the
+    /// root, alongside the flat listing of `files.html`.
+    #[clap(long)]
+    dir_pages: bool,
+    /// How `files.html` lists the files, as a flat table, a tree of collapsible directories or
+    /// both.
+    #[clap(long, value_enum, default_value_t = FileListing::Flat)]
+    file_listing: FileListing,
+    /// Size above which a file's page only links to the raw file, such as `5MiB`.
+    #[clap(long, value_parser = parse_size)]
+    max_blob_size: Option<u64>,
+    /// Url of a mermaid script, such as a local copy of `mermaid.min.js`, for drawing the
+    /// ```mermaid blocks of the readme and other markdown pages.
+    #[clap(long)]
+    mermaid_script: Option<String>,
+
+    /// Write each page as a bare html fragment, without the document head or header, for
+    /// embedding into another site's layout.
+    #[clap(
+        long,
+        conflicts_with_all = ["self_contained", "header_include", "footer_include", "templates_dir"]
+    )]
+    fragment: bool,
+
+    /// Also write a web server config snippet setting cache headers for the generated pages.
+    #[clap(long, value_enum)]
+    server_config: Option<ServerConfig>,
+    /// Where to write the --server-config snippet, by default next to the output as
+    /// `<out>.<file name>` so it isn't served along with the pages.
+    #[clap(long, requires = "server_config")]
+    server_config_out: Option<PathBuf>,
+
+    /// Keep running after building, building again whenever the repo's refs change.
+    #[cfg(target_os = "linux")]
+    #[clap(long)]
+    watch: bool,
+    /// Built-in theme to embed for any of the stylesheet, logo and favicon not given explicitly,
+    /// requires --self-contained
+    #[clap(long, value_enum, requires = "self_contained")]
+    theme: Option<Theme>,
+    /// Path to css stylesheet to embed, requires --self-contained
+    #[clap(long, requires = "self_contained")]
+    stylesheet: Option<PathBuf>,
+    /// Path to png logo to embed, requires --self-contained
+    #[clap(long, requires = "self_contained")]
+    logo: Option<PathBuf>,
+    /// Path to png favicon to embed, requires --self-contained
+    #[clap(long, requires = "self_contained")]
+    favicon: Option<PathBuf>,
+}
+
+impl RepoArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        #[cfg(target_os = "linux")]
+        let watch = self.watch;
+        let (repo, options) = self.options()?;
+        #[cfg(target_os = "linux")]
+        if watch {
+            return watch_repo(&repo, &options);
+        }
+        build_repo_pages(&repo, &options)
+    }
+
+    /// The repo to build and the options to build it with.
+    fn options(self) -> anyhow::Result<(PathBuf, RepoOptions)> {
+        let options = RepoOptions {
+            output: match self.out {
+                Some(archive) => Output::Archive(archive),
+                None => Output::Dir(self.out_dir),
+            },
+            atomic: self.atomic,
+            log_length: self.log_length,
+            log_archive: self.log_archive,
+            log_page_size: self.log_page_size,
+            blame: self.blame,
+            snapshots: self.snapshots,
+            snapshot_head: self.snapshot_head,
+            dumb_http: self.dumb_http,
+            branches: self.branches,
+            tag_trees: self.tag_trees,
+            compare_head: self.compare_head,
+            commit_refs: self.commit_refs,
+            formats: self.formats,
+            default_branch: self.default_branch,
+            clone_base_urls: self.clone_base_urls,
+            page: PageOptions {
+                tab_width: self.tab_width,
+                show_whitespace: self.show_whitespace,
+                rename_similarity: Some(self.rename_similarity),
+                image_diffs: self.image_diffs,
+                max_diff_files: self.max_diff_files,
+                max_diff_lines: self.max_diff_lines,
+                allowed_signers: self.allowed_signers,
+                merge_diff: self.merge_diff,
+                topology: self.topology,
+                show_committer_dates: self.show_committer_dates,
+                committer_date_order: self.committer_date_order,
+                avatar_url_template: self.avatar_url_template,
+                emails: self.emails,
+                search: self.search,
+                blob_pages: self.blob_pages,
+                dir_pages: self.dir_pages,
+                file_listing: self.file_listing,
+                max_blob_size: self.max_blob_size,
+                mermaid_script: self.mermaid_script,
+                fragment: self.fragment,
+                ..self.page.page_options(
+                    self.stylesheet.as_deref(),
+                    self.logo.as_deref(),
+                    self.favicon.as_deref(),
+                    self.theme,
+                )?
+            },
+            server_config: self.server_config,
+            server_config_path: self.server_config_out,
+        };
+        Ok((self.repo, options))
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct VerifyArgs {
+    /// The repo and the options its pages were built with in --out-dir.
+    #[clap(flatten)]
+    repo: RepoArgs,
+}
+
+impl VerifyArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        #[cfg(target_os = "linux")]
+        if self.repo.watch {
+            anyhow::bail!("--watch can't be used when verifying");
+        }
+        let (repo, options) = self.repo.options()?;
+        let stale = verify_repo_pages(&repo, &options)?;
+        if !stale.is_empty() {
+            for path in &stale {
+                tracing::warn!(?path, "page is out of date");
+            }
+            anyhow::bail!("{} pages are out of date", stale.len());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct PagesArgs {
+    #[clap()]
+    repos: Vec<PathBuf>,
+    /// Directory to copy each repo's docs into, under a directory of the repo's name.
+    #[clap(long)]
+    out_dir: PathBuf,
+    /// Directory holding a manifest of each repo's copied files between runs, and the files being
+    /// written before they are moved into place. This must be on the same filesytem as the
+    /// out_dir.
+    #[clap(long)]
+    working_dir: PathBuf,
+
+    /// Whether or not to create an index page, the same as stagix-index.
+    #[clap(long)]
+    index: bool,
+
+    // index options
+    /// Path to css stylesheet that will be copied next to the `index.html`
+    #[clap(long)]
+    stylesheet: Option<PathBuf>,
+    /// Path to png logo that will be copied next to the `index.html`
+    #[clap(long)]
+    logo: Option<PathBuf>,
+    /// Path to png favicon that will be copied next to the `index.html`
+    #[clap(long)]
+    favicon: Option<PathBuf>,
+    /// Built-in theme to use for any of the stylesheet, logo and favicon not given explicitly.
+    #[clap(long, value_enum)]
+    theme: Option<Theme>,
+    #[clap(flatten)]
+    table: IndexTableArgs,
+
+    #[clap(flatten)]
+    page: PageArgs,
+
+    /// Number of repos to process concurrently.
+    #[clap(short, long, default_value_t = 1)]
+    jobs: usize,
+    /// Command wrapping each repo's pages command, with its arguments separated by spaces, such
+    /// as `"bwrap --ro-bind / / --bind . . --unshare-net"`.
+    #[clap(long, value_delimiter = ' ')]
+    sandbox: Vec<String>,
+}
+
+impl PagesArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        let page = self.page.page_options(
+            self.stylesheet.as_deref(),
+            self.logo.as_deref(),
+            self.favicon.as_deref(),
+            self.theme,
+        )?;
+        build_pages_dirs(
+            self.repos,
+            PagesOptions {
+                out_dir: self.out_dir.clone(),
+                working_dir: self.working_dir,
+                index: self.index.then_some(IndexOptions {
+                    out_dir: Some(self.out_dir),
+                    stylesheet: self.stylesheet,
+                    logo: self.logo,
+                    favicon: self.favicon,
+                    repos_url: self.table.repos_url,
+                    pages_url: self.table.pages_url,
+                    theme: self.theme,
+                    page,
+                    columns: self.table.columns,
+                    sort: self.table.sort,
+                    reverse: self.table.reverse,
+                    formats: Vec::new(),
+                    jobs: self.jobs,
+                }),
+                jobs: self.jobs,
+                sandbox: self.sandbox,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct SitesArgs {
+    /// Path to a toml file describing the sites to build, each as a `[[site]]` table.
+    #[clap()]
+    sites: PathBuf,
+}
+
+impl SitesArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        build_sites(&self.sites)
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct AllArgs {
+    /// Directory holding the repos, each directory in it that is a git repo being built.
+    #[clap(long)]
+    repos_dir: PathBuf,
+    /// Directory to write the site to, with the index at the root, each repo's pages under its
+    /// name and the published docs under `pages/`.
+    #[clap(long)]
+    out_dir: PathBuf,
+    /// Directory kept between runs for publishing the docs, on the same filesystem as the
+    /// out_dir, `.<out dir>.pages` next to it if not given.
+    #[clap(long)]
+    working_dir: Option<PathBuf>,
+    /// URL the site is served from, used for canonical links, Open Graph metadata and sitemaps.
+    #[clap(long)]
+    base_url: Option<String>,
+    /// Built-in theme to use for any of the stylesheet, logo and favicon not given explicitly.
+    #[clap(long, value_enum)]
+    theme: Option<Theme>,
+    /// Path to css stylesheet copied next to the `index.html` and shared by every page.
+    #[clap(long)]
+    stylesheet: Option<PathBuf>,
+    /// Path to png logo copied next to the `index.html` and shared by every page.
+    #[clap(long)]
+    logo: Option<PathBuf>,
+    /// Path to png favicon copied next to the `index.html` and shared by every page.
+    #[clap(long)]
+    favicon: Option<PathBuf>,
+    /// Base URLs for cloning from, each giving a clone URL of `<base>/<name>.git` unless the repo
+    /// has a `url` file.
+    #[clap(long, value_delimiter = ',')]
+    clone_base_urls: Vec<String>,
+    /// Number of commits to limit each repo's log history to, uses all commits if not set.
+    #[clap(short, long)]
+    log_length: Option<usize>,
+    /// Path to an html fragment inserted at the bottom of every page.
+    #[clap(long, alias = "footer-file")]
+    footer_include: Option<PathBuf>,
+    /// Columns to show in the index table, in order.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    columns: Vec<IndexColumn>,
+    /// Order of the repos in the index table, within each category.
+    #[clap(long, value_enum)]
+    sort: Option<IndexSort>,
+    /// Number of repos to publish the docs of concurrently.
+    #[clap(short, long, default_value_t = 1)]
+    jobs: usize,
+    /// Command wrapping each repo's pages command, with its arguments separated by spaces.
+    #[clap(long, value_delimiter = ' ')]
+    sandbox: Vec<String>,
+}
+
+impl AllArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        let repos = discover_repos(&self.repos_dir)?;
+        info!(num_repos = repos.len(), repos_dir = ?self.repos_dir, "building everything");
+        create_dir_all(&self.out_dir)?;
+        let out_dir = self.out_dir.canonicalize()?;
+        let working_dir = match self.working_dir {
+            Some(working_dir) => working_dir,
+            None => {
+                let Some(name) = out_dir.file_name() else {
+                    anyhow::bail!("out dir {:?} has no name", out_dir);
+                };
+                out_dir.with_file_name(format!(".{}.pages", name.to_string_lossy()))
+            }
+        };
+        // the docs are linked from the index relative to it, like the repos
+        let pages_url = "pages";
+        build_site(&SiteConfig {
+            repos: repos.clone(),
+            out_dir: out_dir.clone(),
+            base_url: self.base_url,
+            theme: self.theme,
+            stylesheet: self.stylesheet,
+            logo: self.logo,
+            favicon: self.favicon,
+            clone_base_urls: self.clone_base_urls,
+            log_length: self.log_length,
+            pages_url: Some(pages_url.to_owned()),
+            footer_file: self.footer_include,
+            index_columns: self.columns,
+            index_sort: self.sort,
+        })?;
+        build_pages_dirs(
+            repos,
+            PagesOptions {
+                out_dir: out_dir.join(pages_url),
+                working_dir,
+                index: None,
+                jobs: self.jobs,
+                sandbox: self.sandbox,
+            },
+        )
+    }
+}
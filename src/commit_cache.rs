@@ -0,0 +1,393 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::{Meta, OutputSink, RepoOptions};
+
+/// Name of the cache file written at the root of a repo's output.
+pub(crate) const COMMIT_CACHE_FILE: &str = ".stagix-commits";
+
+/// Commit pages written by a previous build, so they needn't be rendered again, as paths without
+/// an extension, such as `commits/<id>` or `branches/<name>/commits/<id>`, along with the
+/// `blob/<id>` directories of permalink files pages.
+///
+/// The first line holds a hash of the options, metadata and files affecting how commit pages are
+/// rendered, the cache being dropped whenever they change. Each page is followed by the paths of
+/// the files pages its diff links to, indented by a tab, as those move with the tip.
+#[derive(Debug, Default)]
+pub(crate) struct CommitCache {
+    key: String,
+    pages: HashMap<PathBuf, BTreeSet<String>>,
+}
+
+impl CommitCache {
+    /// Load the cache from the previous build in `sink`, keeping only commits whose page and diff
+    /// still exist.
+    pub(crate) fn load(
+        sink: &dyn OutputSink,
+        options: &RepoOptions,
+        meta: &Meta,
+    ) -> anyhow::Result<Self> {
+        // everything shown in the header and nav, but not the head or modification time, which
+        // change with every push
+        let Meta {
+            description,
+            urls,
+            name,
+            owner,
+            pages,
+            domain,
+            readme,
+            license,
+            changelog,
+            contributing,
+            mailinglist,
+            mod_time: _,
+            log_length,
+            feed_length,
+            category,
+            head: _,
+        } = meta;
+        let mut hasher = gix::hash::hasher(gix::hash::Kind::Sha1);
+        hasher.update(
+            format!(
+                "{:?} {:?} {:?} {:?}",
+                options.log_length, options.commit_refs, options.clone_base_urls, options.page,
+            )
+            .as_bytes(),
+        );
+        hasher.update(
+            format!(
+                "{description:?} {urls:?} {name:?} {owner:?} {pages:?} {domain:?} {readme:?} \
+                 {license:?} {changelog:?} {contributing:?} {mailinglist:?} {log_length:?} \
+                 {feed_length:?} {category:?}"
+            )
+            .as_bytes(),
+        );
+        // signatures verify differently as the signers change
+        if let Some(allowed_signers) = &options.page.allowed_signers {
+            let contents = std::fs::read(allowed_signers).unwrap_or_default();
+            hasher.update(&contents);
+        }
+        if let Some(templates) = &options.page.templates {
+            for source in templates.sources() {
+                hasher.update(source.as_bytes());
+            }
+        }
+        let key = hasher.try_finalize()?.to_string();
+
+        let mut pages = HashMap::new();
+        if let Some(contents) = sink.read(Path::new(COMMIT_CACHE_FILE)) {
+            let contents = String::from_utf8_lossy(&contents);
+            // not `lines`, as paths can end in a carriage return
+            let mut lines = contents.split_terminator('\n');
+            if lines.next() == Some(key.as_str()) {
+                let mut page: Option<(PathBuf, BTreeSet<String>)> = None;
+                for line in lines {
+                    match (line.strip_prefix('\t'), &mut page) {
+                        (Some(file), Some((_, linked))) => {
+                            linked.insert(file.to_owned());
+                        }
+                        (Some(_), None) => {}
+                        (None, _) => {
+                            pages.extend(page.replace((PathBuf::from(line), BTreeSet::new())))
+                        }
+                    }
+                }
+                pages.extend(page);
+                pages.retain(|page: &PathBuf, _| {
+                    if page.starts_with("blob") {
+                        sink.modified(&page.join("files.html")).is_some()
+                    } else {
+                        sink.modified(&page.with_extension("html")).is_some()
+                            && sink.modified(&page.with_extension("diff")).is_some()
+                    }
+                });
+            } else {
+                debug!("options changed, dropping commit cache");
+            }
+        }
+        debug!(cached = pages.len(), "loaded commit cache");
+        Ok(Self { key, pages })
+    }
+
+    /// Whether the page of commit `id` in `commits_dir` is cached, with every files page it links
+    /// to still among `files`.
+    pub(crate) fn contains(&self, commits_dir: &Path, id: &str, files: &HashSet<String>) -> bool {
+        self.pages
+            .get(&commits_dir.join(id))
+            .is_some_and(|linked| linked.iter().all(|file| files.contains(file)))
+    }
+
+    /// Cache the page of commit `id` in `commits_dir`, linking to the files pages of `linked`.
+    pub(crate) fn insert(&mut self, commits_dir: &Path, id: &str, linked: BTreeSet<String>) {
+        // a path with a newline can't be written on a line of its own
+        if linked.iter().any(|file| file.contains('\n')) {
+            return;
+        }
+        self.pages.insert(commits_dir.join(id), linked);
+    }
+
+    /// Drop `page`, returning whether it was cached.
+    pub(crate) fn remove_page(&mut self, page: &Path) -> bool {
+        self.pages.remove(page).is_some()
+    }
+
+    /// Drop the pages of commit `id` in every commits directory, its permalink files pages
+    /// showing nothing that moves with refs.
+    pub(crate) fn remove(&mut self, id: &str) {
+        self.pages
+            .retain(|page, _| page.starts_with("blob") || page.file_name() != Some(id.as_ref()));
+    }
+
+    /// Whether the permalink files pages in `blob_dir` are cached.
+    pub(crate) fn contains_blobs(&self, blob_dir: &Path) -> bool {
+        self.pages.contains_key(blob_dir)
+    }
+
+    pub(crate) fn insert_blobs(&mut self, blob_dir: PathBuf) {
+        self.pages.insert(blob_dir, BTreeSet::new());
+    }
+
+    /// Whether `path`, a page, diff or patch of a commit or a permalink files page, belongs to a
+    /// cached page.
+    pub(crate) fn contains_file(&self, path: &Path) -> bool {
+        if path.starts_with("blob") {
+            let blob_dir = path.components().take(2).collect::<PathBuf>();
+            return self.pages.contains_key(&blob_dir);
+        }
+        self.pages.contains_key(&path.with_extension(""))
+    }
+
+    pub(crate) fn to_file(&self) -> String {
+        let mut pages = self
+            .pages
+            .iter()
+            .map(|(page, linked)| (page.to_string_lossy(), linked))
+            .collect::<Vec<_>>();
+        pages.sort_unstable();
+        let mut out = format!("{}\n", self.key);
+        for (page, linked) in pages {
+            out.push_str(&page);
+            out.push('\n');
+            for file in linked {
+                out.push('\t');
+                out.push_str(file);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::{CommitRefs, Output, PageOptions, Templates};
+
+    /// Output kept in memory between builds.
+    #[derive(Default)]
+    struct MemorySink {
+        files: HashMap<PathBuf, Vec<u8>>,
+    }
+
+    impl OutputSink for MemorySink {
+        fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+            self.files.insert(path.to_owned(), contents.to_owned());
+            Ok(())
+        }
+
+        fn modified(&self, path: &Path) -> Option<SystemTime> {
+            self.files.contains_key(path).then_some(UNIX_EPOCH)
+        }
+
+        fn read(&self, path: &Path) -> Option<Vec<u8>> {
+            self.files.get(path).cloned()
+        }
+
+        fn finish(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn options() -> RepoOptions {
+        RepoOptions {
+            output: Output::Dir(PathBuf::from(".")),
+            atomic: false,
+            log_length: None,
+            log_archive: None,
+            log_page_size: None,
+            blame: false,
+            snapshots: false,
+            snapshot_head: false,
+            dumb_http: false,
+            branches: Vec::new(),
+            tag_trees: false,
+            compare_head: false,
+            commit_refs: CommitRefs::Head,
+            formats: Vec::new(),
+            default_branch: None,
+            clone_base_urls: Vec::new(),
+            page: PageOptions::default(),
+            server_config: None,
+            server_config_path: None,
+        }
+    }
+
+    fn meta() -> Meta {
+        Meta {
+            description: "a repo".to_owned(),
+            urls: Vec::new(),
+            name: "repo".to_owned(),
+            owner: String::new(),
+            pages: None,
+            domain: None,
+            readme: None,
+            license: None,
+            changelog: None,
+            contributing: None,
+            mailinglist: None,
+            mod_time: UNIX_EPOCH,
+            log_length: None,
+            feed_length: None,
+            category: None,
+            head: None,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("stagix-commit-cache-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write the page of commit `a` to `sink` as a build would, linking to `src/lib.rs`.
+    fn build(sink: &mut MemorySink, options: &RepoOptions) -> CommitCache {
+        let mut cache = CommitCache::load(sink, options, &meta()).unwrap();
+        if !cache.contains(Path::new("commits"), "a", &files(&["src/lib.rs"])) {
+            sink.write(Path::new("commits/a.html"), b"").unwrap();
+            sink.write(Path::new("commits/a.diff"), b"").unwrap();
+            cache.insert(
+                Path::new("commits"),
+                "a",
+                BTreeSet::from(["src/lib.rs".to_owned()]),
+            );
+        }
+        sink.write(Path::new(COMMIT_CACHE_FILE), cache.to_file().as_bytes())
+            .unwrap();
+        cache
+    }
+
+    fn files(paths: &[&str]) -> HashSet<String> {
+        paths.iter().map(|&path| path.to_owned()).collect()
+    }
+
+    fn is_cached(sink: &MemorySink, options: &RepoOptions) -> bool {
+        CommitCache::load(sink, options, &meta()).unwrap().contains(
+            Path::new("commits"),
+            "a",
+            &files(&["src/lib.rs"]),
+        )
+    }
+
+    #[test]
+    fn unchanged_build_reuses_pages() {
+        let mut sink = MemorySink::default();
+        let options = options();
+        build(&mut sink, &options);
+        assert!(is_cached(&sink, &options));
+        build(&mut sink, &options);
+        assert!(is_cached(&sink, &options));
+    }
+
+    #[test]
+    fn changed_option_drops_pages() {
+        let mut sink = MemorySink::default();
+        let mut options = options();
+        build(&mut sink, &options);
+        options.page.show_whitespace = true;
+        assert!(!is_cached(&sink, &options));
+    }
+
+    #[test]
+    fn changed_template_drops_pages() {
+        let dir = temp_dir("templates");
+        std::fs::write(dir.join("footer.html"), "<p>old</p>").unwrap();
+        let mut sink = MemorySink::default();
+        let mut options = options();
+        options.page.templates = Some(Templates::load(&dir).unwrap());
+        build(&mut sink, &options);
+        assert!(is_cached(&sink, &options));
+        std::fs::write(dir.join("footer.html"), "<p>new</p>").unwrap();
+        options.page.templates = Some(Templates::load(&dir).unwrap());
+        assert!(!is_cached(&sink, &options));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn changed_signers_drop_pages() {
+        let dir = temp_dir("signers");
+        let allowed_signers = dir.join("allowed_signers");
+        std::fs::write(&allowed_signers, "a@example.com ssh-ed25519 AAAA\n").unwrap();
+        let mut sink = MemorySink::default();
+        let mut options = options();
+        options.page.allowed_signers = Some(allowed_signers.clone());
+        build(&mut sink, &options);
+        assert!(is_cached(&sink, &options));
+        std::fs::write(&allowed_signers, "b@example.com ssh-ed25519 AAAA\n").unwrap();
+        assert!(!is_cached(&sink, &options));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_page_drops_it() {
+        let mut sink = MemorySink::default();
+        let options = options();
+        build(&mut sink, &options);
+        sink.files.remove(Path::new("commits/a.diff"));
+        assert!(!is_cached(&sink, &options));
+    }
+
+    #[test]
+    fn page_linking_a_removed_file_is_stale() {
+        let mut sink = MemorySink::default();
+        let options = options();
+        build(&mut sink, &options);
+        let cache = CommitCache::load(&sink, &options, &meta()).unwrap();
+        assert!(cache.contains(Path::new("commits"), "a", &files(&["src/lib.rs", "b"])));
+        assert!(!cache.contains(Path::new("commits"), "a", &files(&["b"])));
+    }
+
+    #[test]
+    fn linked_files_round_trip() {
+        let mut sink = MemorySink::default();
+        let options = options();
+        let mut cache = CommitCache::load(&sink, &options, &meta()).unwrap();
+        let linked = BTreeSet::from(["a b\r".to_owned(), "\tc".to_owned()]);
+        cache.insert(Path::new("commits"), "a", linked.clone());
+        cache.insert_blobs(PathBuf::from("blob/b"));
+        sink.write(Path::new(COMMIT_CACHE_FILE), cache.to_file().as_bytes())
+            .unwrap();
+        for path in ["commits/a.html", "commits/a.diff", "blob/b/files.html"] {
+            sink.write(Path::new(path), b"").unwrap();
+        }
+        let cache = CommitCache::load(&sink, &options, &meta()).unwrap();
+        assert_eq!(cache.pages[Path::new("commits/a")], linked);
+        assert!(cache.contains_blobs(Path::new("blob/b")));
+    }
+
+    #[test]
+    fn paths_with_newlines_are_not_cached() {
+        let mut cache = CommitCache::default();
+        cache.insert(
+            Path::new("commits"),
+            "a",
+            BTreeSet::from(["a\nb".to_owned()]),
+        );
+        assert!(!cache.contains(Path::new("commits"), "a", &files(&["a\nb"])));
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+
+use gix::{ObjectId, Repository};
+use serde::Deserialize;
+
+/// Refs whose history gets commit pages under `commits/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitRefs {
+    /// Only the history of `HEAD`.
+    #[default]
+    Head,
+    /// `HEAD` and every local branch.
+    Branches,
+    /// `HEAD`, every local branch and every tag.
+    All,
+}
+
+impl CommitRefs {
+    pub(crate) fn includes_branches(self) -> bool {
+        matches!(self, CommitRefs::Branches | CommitRefs::All)
+    }
+
+    pub(crate) fn includes_tags(self) -> bool {
+        self == CommitRefs::All
+    }
+
+    /// The commits to walk from, `head` first, without duplicates.
+    pub(crate) fn tips(self, repo: &Repository, head: ObjectId) -> anyhow::Result<Vec<ObjectId>> {
+        let mut tips = vec![head];
+        let refs = repo.references()?;
+        if self.includes_branches() {
+            for branch in refs.local_branches()? {
+                let mut branch = branch.map_err(|e| anyhow::anyhow!(e))?;
+                tips.push(branch.peel_to_commit()?.id);
+            }
+        }
+        if self.includes_tags() {
+            for tag in refs.tags()? {
+                let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+                // tags of trees or blobs have no history
+                if let Ok(commit) = tag.peel_to_commit() {
+                    tips.push(commit.id);
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        tips.retain(|id| seen.insert(*id));
+        Ok(tips)
+    }
+}
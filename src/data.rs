@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+
+use gix::bstr::ByteSlice as _;
+use gix::date::time::format::ISO8601_STRICT;
+use gix::objs::tree::EntryKind;
+use gix::traverse::tree::Recorder;
+use gix::{ObjectId, Repository};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::attributes::Linguist;
+use crate::safe_path::retain_safe_paths;
+use crate::{EmailDisplay, Meta, PageOptions, TimeZone, Topology, get_last_commits, raw_tree_diff};
+
+/// Which commits [`collect_log`] and [`collect_files`] look at.
+#[derive(Debug, Default, Clone)]
+pub struct CollectOptions {
+    /// Number of commits of the log to collect, all of them if unset.
+    pub log_length: Option<usize>,
+    pub topology: Topology,
+    /// How the author emails of the commits are given.
+    pub emails: EmailDisplay,
+}
+
+/// The repo as a whole, as listed on the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoSummary {
+    pub name: String,
+    pub description: String,
+    pub owner: String,
+    pub category: Option<String>,
+    pub clone_urls: Vec<String>,
+    /// Id of the commit at `HEAD`, empty if there are no commits.
+    pub head: String,
+    /// Committer time of `HEAD`, empty if there are no commits.
+    pub last_commit: String,
+}
+
+/// A commit of the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub author_name: String,
+    /// Empty when emails are hidden by [`CollectOptions::emails`].
+    pub author_email: String,
+    /// Author time.
+    pub time: String,
+    pub title: String,
+    pub body: Option<String>,
+    /// Changes against the first parent.
+    pub files_changed: u64,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Branch,
+    Tag,
+}
+
+/// A local branch or tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefInfo {
+    pub name: String,
+    pub kind: RefKind,
+    /// Id of the commit the ref points at.
+    pub commit: String,
+    /// Id of the tag object of an annotated tag.
+    pub tag: Option<String>,
+    /// Author time of that commit.
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    File,
+    Executable,
+    Submodule,
+}
+
+/// A file of the tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub kind: FileKind,
+    /// Size in bytes, zero for submodules.
+    pub size: usize,
+    /// Id of the blob, or of the commit of a submodule.
+    pub id: String,
+    /// Id of the last commit to change the file.
+    pub last_commit: Option<String>,
+}
+
+/// Summarise the repo, with `meta` loaded by [`Meta::load`].
+pub fn collect_summary(repo: &Repository, meta: &Meta) -> anyhow::Result<RepoSummary> {
+    // both left empty for a repo without commits
+    let (head, last_commit) = match meta.head {
+        Some(head) => {
+            let head = repo.find_commit(head)?;
+            (head.id.to_string(), head.time()?.format(ISO8601_STRICT)?)
+        }
+        None => (String::new(), String::new()),
+    };
+    Ok(RepoSummary {
+        name: meta.name.clone(),
+        description: meta.description.clone(),
+        owner: meta.owner.clone(),
+        category: meta.category.clone(),
+        clone_urls: meta.urls.clone(),
+        head,
+        last_commit,
+    })
+}
+
+/// The commits of the log of `tip`, walked as the log page is.
+pub fn collect_log(
+    repo: &Repository,
+    tip: ObjectId,
+    options: &CollectOptions,
+) -> anyhow::Result<Vec<CommitInfo>> {
+    debug!(repo=?repo.path(), %tip, ?options, "collect log");
+    let mut commits = Vec::new();
+    for rev in options
+        .topology
+        .walk(repo, tip)?
+        .take(options.log_length.unwrap_or(usize::MAX))
+    {
+        let mut commit = collect_commit(repo, rev?.id)?;
+        commit.author_email = options
+            .emails
+            .text(&commit.author_email)
+            .unwrap_or_default();
+        commits.push(commit);
+    }
+    Ok(commits)
+}
+
+/// A single commit, with its changes against its first parent.
+pub fn collect_commit(repo: &Repository, id: ObjectId) -> anyhow::Result<CommitInfo> {
+    let commit = repo.find_commit(id)?;
+    let parents = commit.parent_ids().map(|p| p.detach()).collect::<Vec<_>>();
+    let parent_tree = match parents.first() {
+        Some(parent) => repo.find_commit(*parent)?.tree()?,
+        None => repo.empty_tree(),
+    };
+    let stats = parent_tree.changes()?.stats(&commit.tree()?)?;
+    let author = commit.author()?;
+    let message = commit.message()?;
+    Ok(CommitInfo {
+        id: id.to_string(),
+        parents: parents.iter().map(ToString::to_string).collect(),
+        author_name: author.name.to_string(),
+        author_email: author.email.to_string(),
+        time: author.time()?.format(ISO8601_STRICT)?,
+        title: message.title.trim().to_str_lossy().into_owned(),
+        body: message
+            .body
+            .map(|body| body.trim().to_str_lossy().into_owned()),
+        files_changed: stats.files_changed,
+        lines_added: stats.lines_added,
+        lines_removed: stats.lines_removed,
+    })
+}
+
+/// The raw unified diff of the commit `id` against its first parent.
+pub fn collect_diff(
+    repo: &Repository,
+    id: ObjectId,
+    options: &PageOptions,
+) -> anyhow::Result<String> {
+    let commit = repo.find_commit(id)?;
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent_ids().next() {
+        Some(parent) => repo.find_commit(parent)?.tree()?,
+        None => repo.empty_tree(),
+    };
+    raw_tree_diff(repo, &parent_tree, &tree, options)
+}
+
+/// Every local branch and tag pointing at a commit, tags of trees and blobs being skipped.
+pub fn collect_refs(repo: &Repository) -> anyhow::Result<Vec<RefInfo>> {
+    let mut refs = Vec::new();
+    let references = repo.references()?;
+    for (kind, iter) in [
+        (RefKind::Branch, references.local_branches()?),
+        (RefKind::Tag, references.tags()?),
+    ] {
+        for reference in iter {
+            let mut reference = reference.map_err(|e| anyhow::anyhow!(e))?;
+            let target = reference.try_id().map(|id| id.detach());
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
+            refs.push(RefInfo {
+                name: reference.name().shorten().to_string(),
+                kind,
+                commit: commit.id.to_string(),
+                tag: target
+                    .filter(|target| *target != commit.id)
+                    .map(|target| target.to_string()),
+                time: commit.author()?.time()?.format(ISO8601_STRICT)?,
+            });
+        }
+    }
+    Ok(refs)
+}
+
+/// Every file and submodule in the tree of `tip`, along with the last commit to change it.
+pub fn collect_files(
+    repo: &Repository,
+    tip: ObjectId,
+    options: &CollectOptions,
+) -> anyhow::Result<Vec<FileEntry>> {
+    debug!(repo=?repo.path(), %tip, "collect files");
+    let tree = repo.find_commit(tip)?.tree()?;
+    let mut recorder = Recorder::default();
+    tree.traverse().depthfirst(&mut recorder)?;
+    let linguist = Linguist::load(&tree)?;
+    recorder
+        .records
+        .retain(|entry| !linguist.is_export_ignored(&entry.filepath.to_str_lossy()));
+    retain_safe_paths(&mut recorder.records);
+    let paths = recorder
+        .records
+        .iter()
+        .filter(|entry| entry.mode.is_blob() || entry.mode.is_commit())
+        .map(|entry| entry.filepath.to_string())
+        .collect::<HashSet<_>>();
+    // the data keeps the offsets times were recorded with
+    let last_commits =
+        get_last_commits(repo, tip, paths, None, options.topology, TimeZone::Original)?;
+    let mut files = Vec::new();
+    for entry in recorder.records {
+        let kind = match entry.mode.kind() {
+            EntryKind::Blob => FileKind::File,
+            EntryKind::BlobExecutable => FileKind::Executable,
+            EntryKind::Commit => FileKind::Submodule,
+            EntryKind::Tree | EntryKind::Link => continue,
+        };
+        let size = if kind == FileKind::Submodule {
+            0
+        } else {
+            repo.find_header(entry.oid)?.size() as usize
+        };
+        let path = entry.filepath.to_string();
+        files.push(FileEntry {
+            last_commit: last_commits.get(&path).map(|last| last.id.clone()),
+            path,
+            kind,
+            size,
+            id: entry.oid.to_string(),
+        });
+    }
+    Ok(files)
+}
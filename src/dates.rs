@@ -0,0 +1,183 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gix::date::Time;
+use gix::date::time::format::{ISO8601, ISO8601_STRICT};
+use serde::Deserialize;
+
+use crate::html::Escaped;
+
+/// How commit and tag times are shown on the log, refs and index pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateFormat {
+    /// The exact time, like `2025-01-31 12:00:00 +0000`.
+    #[default]
+    Iso,
+    /// How long before the pages were generated, like `3 days ago`, with the exact time on
+    /// hover.
+    Relative,
+    /// The exact time followed by the relative one.
+    Both,
+}
+
+impl DateFormat {
+    /// `time` as a `<time>` element in this format and the zone, its `datetime` being the exact
+    /// time.
+    pub(crate) fn html(self, time: Time, zone: TimeZone) -> anyhow::Result<Escaped> {
+        let time = zone.apply(time);
+        let datetime = time.format(ISO8601_STRICT)?;
+        let iso = time.format(ISO8601)?;
+        Ok(Escaped::markup(match self {
+            DateFormat::Iso => format!("<time datetime=\"{datetime}\">{iso}</time>"),
+            DateFormat::Relative => format!(
+                "<time datetime=\"{datetime}\" title=\"{iso}\">{}</time>",
+                relative(time.seconds, now())
+            ),
+            DateFormat::Both => format!(
+                "<time datetime=\"{datetime}\">{iso} ({})</time>",
+                relative(time.seconds, now())
+            ),
+        }))
+    }
+}
+
+/// The offset times are shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeZone {
+    /// The offset recorded with the time, that of whoever made the commit or tag.
+    #[default]
+    Original,
+    /// UTC, `+0000`.
+    Utc,
+    /// A fixed offset in seconds east of UTC.
+    Fixed(i32),
+}
+
+impl TimeZone {
+    pub(crate) fn apply(self, time: Time) -> Time {
+        let offset = match self {
+            TimeZone::Original => return time,
+            TimeZone::Utc => 0,
+            TimeZone::Fixed(offset) => offset,
+        };
+        Time {
+            seconds: time.seconds,
+            offset,
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = String;
+
+    /// Parse `original`, `utc` or an offset such as `+01:00`, `-0530` or `+02`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "original" => return Ok(TimeZone::Original),
+            "utc" | "z" => return Ok(TimeZone::Utc),
+            _ => {}
+        }
+        let invalid = || format!("invalid time zone {s:?}, expected original, utc or +HH:MM");
+        let (sign, digits) = match s.split_at_checked(1) {
+            Some(("+", rest)) => (1, rest),
+            Some(("-", rest)) => (-1, rest),
+            _ => return Err(invalid()),
+        };
+        let digits = digits.replace(':', "");
+        if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let (hours, minutes) = digits.split_at(2);
+        let hours: i32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i32 = if minutes.is_empty() {
+            0
+        } else {
+            minutes.parse().map_err(|_| invalid())?
+        };
+        if hours > 14 || minutes > 59 {
+            return Err(invalid());
+        }
+        Ok(TimeZone::Fixed(sign * (hours * 60 + minutes) * 60))
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// How long before `now` the time `seconds` was, in the largest whole unit.
+fn relative(seconds: i64, now: i64) -> String {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let elapsed = now - seconds;
+    if elapsed < 0 {
+        return "in the future".to_owned();
+    }
+    match UNITS.iter().find(|(unit, _)| elapsed >= *unit) {
+        Some((unit, name)) => {
+            let count = elapsed / unit;
+            let plural = if count == 1 { "" } else { "s" };
+            format!("{count} {name}{plural} ago")
+        }
+        None => "just now".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_zones() {
+        for (s, zone) in [
+            ("original", TimeZone::Original),
+            ("UTC", TimeZone::Utc),
+            ("z", TimeZone::Utc),
+            ("+01:00", TimeZone::Fixed(3600)),
+            ("-0530", TimeZone::Fixed(-19800)),
+            ("+02", TimeZone::Fixed(7200)),
+            ("+14:00", TimeZone::Fixed(50400)),
+        ] {
+            assert_eq!(s.parse::<TimeZone>(), Ok(zone), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_time_zones() {
+        for s in [
+            "", "local", "01:00", "+1", "+123", "+15:00", "+01:60", "+ab:cd",
+        ] {
+            assert!(s.parse::<TimeZone>().is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn apply_keeps_the_instant() {
+        let time = Time::new(1_700_000_000, 3600);
+        assert_eq!(TimeZone::Original.apply(time), time);
+        assert_eq!(TimeZone::Utc.apply(time), Time::new(1_700_000_000, 0));
+        assert_eq!(
+            TimeZone::Fixed(-19800).apply(time),
+            Time::new(1_700_000_000, -19800)
+        );
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = 1_700_000_000;
+        assert_eq!(relative(now - 30, now), "just now");
+        assert_eq!(relative(now - 60, now), "1 minute ago");
+        assert_eq!(relative(now - 2 * 60 * 60, now), "2 hours ago");
+        assert_eq!(relative(now - 400 * 24 * 60 * 60, now), "1 year ago");
+        assert_eq!(relative(now + 10, now), "in the future");
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use build_html::{Container, HtmlContainer as _, HtmlElement, Table, TableCell, TableRow};
+use gix::bstr::ByteSlice as _;
+use gix::objs::tree::EntryKind;
+use gix::traverse::tree::Recorder;
+use gix::{ObjectId, Repository};
+use tracing::{debug, warn};
+
+use crate::attributes::Linguist;
+use crate::html::{Escaped, cell, link, row};
+use crate::safe_path::retain_safe_paths;
+use crate::size::format_size;
+
+/// An index page of a single directory of the tree.
+pub(crate) struct DirPage {
+    /// Path of the directory in the tree, empty for the root.
+    pub(crate) dir: String,
+    pub(crate) container: Container,
+}
+
+impl DirPage {
+    /// Path of the page, relative to the files pages.
+    pub(crate) fn path(&self) -> PathBuf {
+        dir_page_path(&self.dir)
+    }
+}
+
+/// Path of the index page of `dir` relative to the files pages, `index.html` in the directory.
+pub(crate) fn dir_page_path(dir: &str) -> PathBuf {
+    PathBuf::from(dir).join("index.html")
+}
+
+enum Entry {
+    Dir,
+    File { size: u64, executable: bool },
+    Submodule(ObjectId),
+}
+
+/// An index page for every directory in the tree of `tip`, listing its subdirectories and files
+/// with a breadcrumb back up to the root named `name`.
+///
+/// Directories holding a file named `index` get no page, as it would be written over that file's
+/// page.
+pub(crate) fn get_dir_pages(
+    repo: &Repository,
+    tip: ObjectId,
+    name: &str,
+) -> anyhow::Result<Vec<DirPage>> {
+    debug!(repo=?repo.path(), %tip, "get dir pages");
+    let tree = repo.find_commit(tip)?.tree()?;
+    let mut recorder = Recorder::default();
+    tree.traverse().breadthfirst(&mut recorder)?;
+    let linguist = Linguist::load(&tree)?;
+    recorder
+        .records
+        .retain(|entry| !linguist.is_export_ignored(&entry.filepath.to_str_lossy()));
+    retain_safe_paths(&mut recorder.records);
+
+    let mut dirs: BTreeMap<String, BTreeMap<String, Entry>> = BTreeMap::new();
+    dirs.insert(String::new(), BTreeMap::new());
+    for record in recorder.records {
+        let path = record.filepath.to_str_lossy();
+        let (parent, file_name) = path.rsplit_once('/').unwrap_or(("", path.as_ref()));
+        let entry = match record.mode.kind() {
+            EntryKind::Tree => {
+                dirs.entry(path.to_string()).or_default();
+                Entry::Dir
+            }
+            EntryKind::Blob | EntryKind::BlobExecutable => Entry::File {
+                size: repo.find_header(record.oid)?.size(),
+                executable: record.mode.kind() == EntryKind::BlobExecutable,
+            },
+            EntryKind::Commit => Entry::Submodule(record.oid),
+            // symlinks have no page to link to
+            EntryKind::Link => continue,
+        };
+        dirs.entry(parent.to_owned())
+            .or_default()
+            .insert(file_name.to_owned(), entry);
+    }
+
+    let mut pages = Vec::new();
+    for (dir, entries) in dirs {
+        if matches!(entries.get("index"), Some(Entry::File { .. })) {
+            warn!(
+                dir,
+                "directory has a file named index, not writing its index page"
+            );
+            continue;
+        }
+        let depth = if dir.is_empty() {
+            0
+        } else {
+            dir.split('/').count()
+        };
+        let mut container = Container::new(build_html::ContainerType::Div);
+        container.add_html(breadcrumb(name, &dir, depth));
+
+        let mut table = Table::new()
+            .with_attributes([("id", "files")])
+            .with_header_row(["Mode", "Name", "Size"]);
+        if depth > 0 {
+            let up = link(&Escaped::new("../index.html"), &Escaped::new(".."));
+            table.add_custom_body_row(row([&Escaped::new("d---------"), &up, &Escaped::default()]));
+        }
+        // directories first, like most file browsers
+        let (subdirs, files): (Vec<_>, Vec<_>) = entries
+            .iter()
+            .partition(|(_, entry)| matches!(entry, Entry::Dir));
+        for (file_name, entry) in subdirs.into_iter().chain(files) {
+            let (mode, name, size) = match entry {
+                Entry::Dir => (
+                    "d---------",
+                    link(
+                        &Escaped::new(format!("{file_name}/index.html")),
+                        &Escaped::new(format!("{file_name}/")),
+                    ),
+                    String::new(),
+                ),
+                Entry::File { size, executable } => (
+                    if *executable {
+                        "-rwxr-xr-x"
+                    } else {
+                        "-rw-r--r--"
+                    },
+                    link(
+                        &Escaped::new(format!("{file_name}.html")),
+                        &Escaped::new(file_name),
+                    ),
+                    format_size(*size),
+                ),
+                Entry::Submodule(id) => ("m---------", Escaped::new(file_name), format!("@ {id}")),
+            };
+            table.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(TableCell::default().with_raw(mode))
+                    .with_cell(cell(&name))
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(size),
+                    ),
+            );
+        }
+        container.add_table(table);
+        pages.push(DirPage { dir, container });
+    }
+    Ok(pages)
+}
+
+/// Links to each directory from the root down to `dir`, which is `depth` directories deep.
+fn breadcrumb(name: &str, dir: &str, depth: usize) -> HtmlElement {
+    let mut crumbs =
+        HtmlElement::new(build_html::HtmlTag::ParagraphText).with_attribute("class", "breadcrumb");
+    let components = std::iter::once(name).chain(dir.split('/').filter(|c| !c.is_empty()));
+    for (i, component) in components.enumerate() {
+        if i > 0 {
+            crumbs.add_raw(" / ");
+        }
+        if i == depth {
+            crumbs.add_raw(Escaped::new(component));
+        } else {
+            crumbs.add_raw(link(
+                &Escaped::new(format!("{}index.html", "../".repeat(depth - i))),
+                &Escaped::new(component),
+            ));
+        }
+    }
+    crumbs
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::fs::read_dir;
+use std::path::Path;
+
+use gix::Repository;
+use tracing::debug;
+
+use crate::manifest::ManifestSink;
+use crate::{Manifest, OutputSink};
+
+/// Export the repo into `dir` of the output as a bare repository that git can clone over the
+/// dumb HTTP protocol, the same as `git update-server-info` then copying the repo.
+///
+/// Objects and packs are named by their contents, so those the `previous` build already copied
+/// are left as they are.
+pub(crate) fn export_dumb_http(
+    repo: &Repository,
+    dir: &Path,
+    previous: Option<&Manifest>,
+    sink: &mut ManifestSink<'_>,
+) -> anyhow::Result<()> {
+    debug!(repo=?repo.path(), ?dir, "exporting for dumb http");
+
+    let head = match repo.head_name()? {
+        Some(name) => format!("ref: {}\n", name.as_bstr()),
+        None => format!("{}\n", repo.head_id()?),
+    };
+    sink.write(&dir.join("HEAD"), head.as_bytes())?;
+
+    let mut refs = Vec::new();
+    for reference in repo.references()?.all()? {
+        let mut reference = reference.map_err(|e| anyhow::anyhow!(e))?;
+        let Some(id) = reference.try_id() else {
+            continue;
+        };
+        let name = reference.name().as_bstr().to_string();
+        let peeled = reference.peel_to_id()?;
+        let mut lines = format!("{id}\t{name}\n");
+        if peeled != id {
+            lines.push_str(&format!("{peeled}\t{name}^{{}}\n"));
+        }
+        refs.push((name, lines));
+    }
+    refs.sort();
+    let info_refs = refs.into_iter().map(|(_, lines)| lines).collect::<String>();
+    sink.write(&dir.join("info/refs"), info_refs.as_bytes())?;
+
+    let copied = previous
+        .iter()
+        .flat_map(|previous| &previous.files)
+        .filter(|entry| entry.path.starts_with(dir.join("objects")))
+        .map(|entry| (entry.path.as_path(), entry))
+        .collect::<HashMap<_, _>>();
+    let objects = repo.common_dir().join("objects");
+    let mut packs = String::new();
+    for entry in read_dir(&objects)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_loose_dir = name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit());
+        if !is_loose_dir && name != "pack" {
+            continue;
+        }
+        for object in read_dir(entry.path())? {
+            let object = object?;
+            let object_name = object.file_name().to_string_lossy().into_owned();
+            if name == "pack" {
+                if object_name.ends_with(".pack") {
+                    packs.push_str(&format!("P {object_name}\n"));
+                } else if !object_name.ends_with(".idx") {
+                    continue;
+                }
+            }
+            let path = dir.join("objects").join(&name).join(&object_name);
+            if let Some(&entry) = copied.get(path.as_path())
+                && sink.modified(&path).is_some()
+            {
+                sink.manifest.files.push(entry.clone());
+                continue;
+            }
+            let contents = std::fs::read(object.path())?;
+            sink.write(&path, &contents)?;
+        }
+    }
+    packs.push('\n');
+    sink.write(&dir.join("objects/info/packs"), packs.as_bytes())?;
+    Ok(())
+}
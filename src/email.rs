@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+use crate::html::Escaped;
+
+/// How the emails of authors, committers and taggers are shown, to make them harder to scrape.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmailDisplay {
+    /// The email as it is.
+    #[default]
+    Full,
+    /// Leave out the email entirely, showing only the name.
+    Hide,
+    /// Only the part before the `@`, like `jeff@…`.
+    TruncateDomain,
+    /// Spelled out in text, like `jeff at example dot com`, and as character references in html,
+    /// which browsers show as normal but naive scrapers don't match.
+    Encode,
+}
+
+impl EmailDisplay {
+    /// `email` as plain text, or `None` if hidden.
+    pub(crate) fn text(self, email: &str) -> Option<String> {
+        match self {
+            EmailDisplay::Full => Some(email.to_owned()),
+            EmailDisplay::Hide => None,
+            EmailDisplay::TruncateDomain => Some(truncate_domain(email)),
+            EmailDisplay::Encode => Some(email.replace('@', " at ").replace('.', " dot ")),
+        }
+    }
+
+    /// `email` escaped for html or xml, or `None` if hidden.
+    pub(crate) fn html(self, email: &str) -> Option<Escaped> {
+        match self {
+            EmailDisplay::Encode => Some(Escaped::markup(
+                email.chars().map(|c| format!("&#{};", c as u32)).collect(),
+            )),
+            _ => self.text(email).map(Escaped::new),
+        }
+    }
+
+    /// Whether the whole email can be worked out from the pages, so publishing its hash for an
+    /// avatar gives nothing away.
+    pub(crate) fn shows_whole_email(self) -> bool {
+        matches!(self, EmailDisplay::Full | EmailDisplay::Encode)
+    }
+
+    /// `name <email>` escaped for html, or only the name if the email is hidden.
+    pub(crate) fn html_ident(self, name: &str, email: &str) -> Escaped {
+        match self.html(email) {
+            Some(email) => Escaped::markup(format!("{} &lt;{email}&gt;", Escaped::new(name))),
+            None => Escaped::new(name),
+        }
+    }
+}
+
+fn truncate_domain(email: &str) -> String {
+    match email.split_once('@') {
+        Some((user, _)) => format!("{user}@…"),
+        None => email.to_owned(),
+    }
+}
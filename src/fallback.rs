@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+use build_html::{Container, HtmlContainer as _};
+use tracing::debug;
+
+use crate::html::redirect_html;
+use crate::{Manifest, Meta, OutputSink, PageLocation, PageOptions};
+
+/// Page that static hosts such as GitHub Pages and Netlify serve for paths that don't exist.
+pub(crate) const NOT_FOUND_FILE: &str = "404.html";
+
+/// Content of the [`NOT_FOUND_FILE`] page.
+pub(crate) fn not_found_container() -> Container {
+    Container::new(build_html::ContainerType::Div)
+        .with_header(2, "Not found")
+        .with_paragraph(
+            "There is no page here, it may be of a file, commit or ref that has since been removed.",
+        )
+}
+
+/// Write the [`NOT_FOUND_FILE`] page of a repo, its links being absolute from the base url as
+/// hosts serve it at the depth of whichever path was missing. Left out without a base url.
+pub(crate) fn write_not_found_page(
+    meta: &Meta,
+    options: &PageOptions,
+    sink: &mut dyn OutputSink,
+) -> anyhow::Result<()> {
+    let Some(repo_url) = options.url_for(Path::new("")) else {
+        debug!("No base url for the links of the not found page, leaving it out");
+        return Ok(());
+    };
+    let to_index_root = format!("{repo_url}../");
+    let location = PageLocation {
+        path: Path::new(NOT_FOUND_FILE),
+        to_index_root: &to_index_root,
+        to_repo_root: &repo_url,
+    };
+    let mut buf = Vec::new();
+    meta.write_html_content(
+        "Not found",
+        &location,
+        not_found_container(),
+        true,
+        options,
+        &mut buf,
+    )?;
+    sink.write(Path::new(NOT_FOUND_FILE), &buf)
+}
+
+/// An `index.html` for each top level directory of the output that doesn't have one, such as
+/// `commits/`, redirecting to the page listing its contents, so browsing to the directory
+/// doesn't give a bare server error.
+pub(crate) fn dir_index_stubs(manifest: &Manifest) -> Vec<(PathBuf, String)> {
+    let paths = manifest
+        .files
+        .iter()
+        .map(|entry| entry.path.as_path())
+        .collect::<BTreeSet<_>>();
+    let dirs = paths
+        .iter()
+        .filter(|path| path.components().count() > 1)
+        .filter_map(|path| match path.components().next() {
+            Some(Component::Normal(dir)) => Some(Path::new(dir)),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+    dirs.into_iter()
+        // the dumb http export is for git to clone, not browsing
+        .filter(|dir| dir.extension().is_none_or(|extension| extension != "git"))
+        .map(|dir| dir.join("index.html"))
+        .filter(|index| !paths.contains(index.as_path()))
+        .map(|index| {
+            let dir = index.parent().unwrap_or(&index);
+            let target = match dir.to_str() {
+                Some("files" | "blob") => "files.html",
+                Some("branches" | "tags" | "compare" | "archives") => "refs.html",
+                _ => "log.html",
+            };
+            let html = redirect_html(&format!("../{target}"));
+            (index, html)
+        })
+        .collect()
+}
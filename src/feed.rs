@@ -0,0 +1,94 @@
+use build_html::escape_html;
+use gix::Repository;
+use gix::bstr::ByteSlice as _;
+use gix::date::time::format::ISO8601_STRICT;
+use std::path::Path;
+use tracing::debug;
+
+use crate::{Meta, PageOptions};
+
+/// Name of the feed file written at the root of a repo's output.
+pub(crate) const ATOM_FILE: &str = "atom.xml";
+
+/// Most commits to include in the feed, unless set in the repo config.
+const MAX_ENTRIES: usize = 100;
+
+/// Atom feed of the most recent commits, limited to those with a commit page to link to.
+pub(crate) fn get_atom(
+    repo: &Repository,
+    meta: &Meta,
+    log_length: Option<usize>,
+    options: &PageOptions,
+) -> anyhow::Result<String> {
+    debug!(repo=?repo.path(), log_length, "get atom feed");
+    let Some(head) = meta.head else {
+        anyhow::bail!("no commits for the feed");
+    };
+    let head = repo.find_commit(head)?;
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("<title>{}</title>\n", escape_html(&meta.name)));
+    xml.push_str(&format!(
+        "<subtitle>{}</subtitle>\n",
+        escape_html(&meta.description)
+    ));
+    let self_url = options.url_for(Path::new(ATOM_FILE));
+    let feed_id = self_url.clone().unwrap_or_else(|| meta.name.clone());
+    xml.push_str(&format!("<id>{}</id>\n", escape_html(&feed_id)));
+    if let Some(url) = self_url {
+        xml.push_str(&format!(
+            "<link rel=\"self\" href=\"{}\"/>\n",
+            escape_html(&url)
+        ));
+    }
+    xml.push_str(&format!(
+        "<updated>{}</updated>\n",
+        head.committer()?.time()?.format(ISO8601_STRICT)?
+    ));
+
+    let max_entries = meta.feed_length.unwrap_or(MAX_ENTRIES);
+    let limit = log_length.map_or(max_entries, |l| l.min(max_entries));
+    let revs = options.topology.walk(repo, head.id)?;
+    for rev in revs.take(limit) {
+        let commit = rev?.object()?;
+        let id = commit.id.to_string();
+        let author = commit.author()?;
+        let message = commit.message()?;
+        let page = Path::new("commits").join(format!("{id}.html"));
+        let href = options
+            .url_for(&page)
+            .unwrap_or_else(|| page.to_string_lossy().into_owned());
+        let time = author.time()?.format(ISO8601_STRICT)?;
+
+        xml.push_str("<entry>\n");
+        xml.push_str(&format!("<id>{id}</id>\n"));
+        xml.push_str(&format!(
+            "<title>{}</title>\n",
+            escape_html(message.title.trim().to_str_lossy().as_ref())
+        ));
+        xml.push_str(&format!(
+            "<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n",
+            escape_html(&href)
+        ));
+        let email = options
+            .emails
+            .html(&author.email.to_string())
+            .map_or(String::new(), |email| format!("<email>{email}</email>"));
+        xml.push_str(&format!(
+            "<author><name>{}</name>{email}</author>\n",
+            escape_html(&author.name.to_string()),
+        ));
+        xml.push_str(&format!("<published>{time}</published>\n"));
+        xml.push_str(&format!(
+            "<updated>{}</updated>\n",
+            commit.committer()?.time()?.format(ISO8601_STRICT)?
+        ));
+        xml.push_str(&format!(
+            "<content type=\"text\">{}</content>\n",
+            escape_html(commit.message_raw()?.to_str_lossy().as_ref())
+        ));
+        xml.push_str("</entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    Ok(xml)
+}
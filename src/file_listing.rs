@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::html::{Escaped, link};
+
+/// How the files page lists the files of the tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileListing {
+    /// A table of every path with its size and last commit.
+    #[default]
+    Flat,
+    /// A tree of collapsible directories, without needing any javascript.
+    Tree,
+    /// The tree followed by the table.
+    Both,
+}
+
+impl FileListing {
+    pub(crate) fn has_tree(self) -> bool {
+        matches!(self, Self::Tree | Self::Both)
+    }
+
+    pub(crate) fn has_table(self) -> bool {
+        matches!(self, Self::Flat | Self::Both)
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Dir(BTreeMap<String, Node>),
+    /// A file, with whether it has a page to link to.
+    File {
+        path: String,
+        has_page: bool,
+    },
+}
+
+/// A nested list of `<details>` for each directory, built up from the paths of the files.
+#[derive(Debug, Default)]
+pub(crate) struct FileTree {
+    root: BTreeMap<String, Node>,
+}
+
+impl FileTree {
+    /// Add the file at `path`, linking to its page if `has_page`.
+    pub(crate) fn add(&mut self, path: &str, has_page: bool) {
+        let mut dir = &mut self.root;
+        let mut components = path.split('/').peekable();
+        while let Some(component) = components.next() {
+            if components.peek().is_none() {
+                dir.insert(
+                    component.to_owned(),
+                    Node::File {
+                        path: path.to_owned(),
+                        has_page,
+                    },
+                );
+                break;
+            }
+            let node = dir
+                .entry(component.to_owned())
+                .or_insert_with(|| Node::Dir(BTreeMap::new()));
+            let Node::Dir(children) = node else {
+                // a file can't also be a directory
+                return;
+            };
+            dir = children;
+        }
+    }
+
+    pub(crate) fn to_html(&self) -> Escaped {
+        Escaped::markup(format!("<ul class=\"tree\">{}</ul>", items(&self.root)))
+    }
+}
+
+/// The list items of the directories, then the files, of `dir`.
+fn items(dir: &BTreeMap<String, Node>) -> Escaped {
+    let (dirs, files): (Vec<_>, Vec<_>) = dir
+        .iter()
+        .partition(|(_, node)| matches!(node, Node::Dir(_)));
+    let mut out = Escaped::default();
+    for (name, node) in dirs.into_iter().chain(files) {
+        let item = match node {
+            Node::Dir(children) => Escaped::markup(format!(
+                "<details><summary>{}/</summary><ul>{}</ul></details>",
+                Escaped::new(name),
+                items(children)
+            )),
+            Node::File {
+                path,
+                has_page: true,
+            } => link(
+                &Escaped::new(format!("files/{path}.html")),
+                &Escaped::new(name),
+            ),
+            Node::File { .. } => Escaped::new(name),
+        };
+        out.push(&Escaped::markup(format!("<li>{item}</li>")));
+    }
+    out
+}
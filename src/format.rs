@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use gix::{ObjectId, Repository};
+use serde::Deserialize;
+
+use crate::data::{
+    CollectOptions, RepoSummary, collect_files, collect_log, collect_refs, collect_summary,
+};
+use crate::gemtext::Gemtext;
+use crate::manifest::ManifestSink;
+use crate::markdown::Markdown;
+use crate::render::{Renderer, write_pages};
+use crate::{Meta, OutputSink, PageOptions};
+
+/// A format written alongside the html pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// `repo.json`, `log.json`, `refs.json` and `files.json`, with the data of the html pages,
+    /// and `index.json` for the index.
+    Json,
+    /// `.gmi` pages for serving the repo over the Gemini protocol.
+    Gemtext,
+    /// `.md` pages, for feeding into other static site generators.
+    Markdown,
+}
+
+impl Format {
+    /// Write the repo at `head` in this format.
+    pub(crate) fn write_repo(
+        self,
+        repo: &Repository,
+        meta: &Meta,
+        head: ObjectId,
+        log_length: Option<usize>,
+        options: &PageOptions,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Format::Json => write_json(repo, meta, head, log_length, options, sink),
+            Format::Gemtext => write_pages(&Gemtext, repo, meta, head, log_length, options, sink),
+            Format::Markdown => write_pages(&Markdown, repo, meta, head, log_length, options, sink),
+        }
+    }
+
+    /// Write the index of `repos`, each under `repos_url`, in this format.
+    pub(crate) fn write_index(
+        self,
+        repos: &[RepoSummary],
+        repos_url: &str,
+        sink: &mut dyn OutputSink,
+    ) -> anyhow::Result<()> {
+        match self {
+            Format::Json => sink.write(
+                Path::new("index.json"),
+                serde_json::to_string_pretty(repos)?.as_bytes(),
+            ),
+            Format::Gemtext => sink.write(
+                Path::new("index.gmi"),
+                Gemtext.index(repos, repos_url).as_bytes(),
+            ),
+            Format::Markdown => sink.write(
+                Path::new("index.md"),
+                Markdown.index(repos, repos_url).as_bytes(),
+            ),
+        }
+    }
+}
+
+/// Write the json files for the repo at `head`.
+fn write_json(
+    repo: &Repository,
+    meta: &Meta,
+    head: ObjectId,
+    log_length: Option<usize>,
+    options: &PageOptions,
+    sink: &mut dyn OutputSink,
+) -> anyhow::Result<()> {
+    let options = CollectOptions {
+        log_length,
+        topology: options.topology,
+        emails: options.emails,
+    };
+    let summary = collect_summary(repo, meta)?;
+    sink.write(
+        Path::new("repo.json"),
+        serde_json::to_string_pretty(&summary)?.as_bytes(),
+    )?;
+    let log = collect_log(repo, head, &options)?;
+    sink.write(
+        Path::new("log.json"),
+        serde_json::to_string_pretty(&log)?.as_bytes(),
+    )?;
+    let refs = collect_refs(repo)?;
+    sink.write(
+        Path::new("refs.json"),
+        serde_json::to_string_pretty(&refs)?.as_bytes(),
+    )?;
+    let files = collect_files(repo, head, &options)?;
+    sink.write(
+        Path::new("files.json"),
+        serde_json::to_string_pretty(&files)?.as_bytes(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+
+use crate::data::{CommitInfo, FileEntry, FileKind, RefInfo, RefKind, RepoSummary};
+use crate::render::Renderer;
+
+/// Renders `.gmi` pages for serving the repo over the Gemini protocol.
+pub(crate) struct Gemtext;
+
+impl Gemtext {
+    /// The repo's title, description and links to its other pages.
+    fn header(repo: &RepoSummary, to_root: &str) -> String {
+        let mut out = format!("# {}\n", one_line(&repo.name));
+        if !repo.description.is_empty() {
+            out.push_str(&text(&one_line(&repo.description)));
+        }
+        for url in &repo.clone_urls {
+            out.push_str(&format!("=> {url} git clone {url}\n"));
+        }
+        out.push('\n');
+        for (page, label) in [("log", "Log"), ("files", "Files"), ("refs", "Refs")] {
+            out.push_str(&format!("=> {to_root}{page}.gmi {label}\n"));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+impl Renderer for Gemtext {
+    fn extension(&self) -> &'static str {
+        "gmi"
+    }
+
+    fn index(&self, repos: &[RepoSummary], repos_url: &str) -> String {
+        let mut out = String::from("# Repositories\n\n");
+        for repo in repos {
+            let description = if repo.description.is_empty() {
+                String::new()
+            } else {
+                format!(" - {}", one_line(&repo.description))
+            };
+            out.push_str(&format!(
+                "=> {}{}/log.gmi {}{}\n",
+                repos_url,
+                repo.name,
+                one_line(&repo.name),
+                description
+            ));
+        }
+        out
+    }
+
+    fn log(&self, repo: &RepoSummary, commits: &[CommitInfo]) -> String {
+        let mut out = Self::header(repo, "");
+        out.push_str("## Log\n\n");
+        for commit in commits {
+            out.push_str(&format!(
+                "=> commits/{}.gmi {} {} ({})\n",
+                commit.id,
+                commit.time,
+                one_line(&commit.title),
+                one_line(&commit.author_name)
+            ));
+        }
+        out
+    }
+
+    fn refs(&self, repo: &RepoSummary, refs: &[RefInfo], paged: &HashSet<String>) -> String {
+        let mut out = Self::header(repo, "");
+        for (kind, heading) in [(RefKind::Branch, "Branches"), (RefKind::Tag, "Tags")] {
+            out.push_str(&format!("## {heading}\n\n"));
+            for r in refs.iter().filter(|r| r.kind == kind) {
+                if paged.contains(&r.commit) {
+                    out.push_str(&format!(
+                        "=> commits/{}.gmi {} {}\n",
+                        r.commit,
+                        one_line(&r.name),
+                        r.time
+                    ));
+                } else {
+                    out.push_str(&format!("* {} {}\n", one_line(&r.name), r.time));
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn files(&self, repo: &RepoSummary, files: &[FileEntry], _paged: &HashSet<String>) -> String {
+        let mut out = Self::header(repo, "");
+        out.push_str("## Files\n\n");
+        for file in files {
+            match file.kind {
+                FileKind::Submodule => {
+                    out.push_str(&format!("* {} @ {}\n", one_line(&file.path), file.id));
+                }
+                FileKind::File | FileKind::Executable => {
+                    out.push_str(&format!(
+                        "=> files/{}.gmi {} ({}B)\n",
+                        file.path.replace(' ', "%20"),
+                        one_line(&file.path),
+                        file.size
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    fn file(&self, repo: &RepoSummary, file: &FileEntry, content: &str, to_root: &str) -> String {
+        let mut out = Self::header(repo, to_root);
+        out.push_str(&format!("## {} ({}B)\n\n", one_line(&file.path), file.size));
+        out.push_str(&preformatted(content));
+        out
+    }
+
+    fn commit(
+        &self,
+        repo: &RepoSummary,
+        commit: &CommitInfo,
+        diff: &str,
+        paged: &HashSet<String>,
+    ) -> String {
+        let mut out = Self::header(repo, "../");
+        out.push_str(&format!("## {}\n\n", one_line(&commit.title)));
+        out.push_str(&format!("commit {}\n", commit.id));
+        for parent in &commit.parents {
+            if paged.contains(parent) {
+                out.push_str(&format!("=> {parent}.gmi parent {parent}\n"));
+            } else {
+                out.push_str(&format!("parent {parent}\n"));
+            }
+        }
+        if commit.author_email.is_empty() {
+            out.push_str(&format!("author {}\n", one_line(&commit.author_name)));
+        } else {
+            out.push_str(&format!(
+                "author {} <{}>\n",
+                one_line(&commit.author_name),
+                one_line(&commit.author_email)
+            ));
+        }
+        out.push_str(&format!("date {}\n\n", commit.time));
+        if let Some(body) = &commit.body {
+            out.push_str(&text(body));
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "{} files changed, {} insertions(+), {} deletions(-)\n\n",
+            commit.files_changed, commit.lines_added, commit.lines_removed
+        ));
+        out.push_str(&preformatted(diff));
+        out
+    }
+}
+
+/// `text` on a single line, as gemtext link labels and headings can't span lines.
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` as plain text lines, indenting any that would otherwise be read as a link, heading,
+/// list item, quote or the start of a preformatted block.
+fn text(text: &str) -> String {
+    let mut out = String::new();
+    for line in text.lines() {
+        if ["=>", "#", "```", "*", ">"]
+            .iter()
+            .any(|markup| line.starts_with(markup))
+        {
+            out.push(' ');
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// `text` in a preformatted block, indenting any lines that would end the block early.
+fn preformatted(text: &str) -> String {
+    let mut out = String::from("```\n");
+    for line in text.lines() {
+        if line.starts_with("```") {
+            out.push(' ');
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str("```\n");
+    out
+}
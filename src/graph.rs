@@ -0,0 +1,89 @@
+use gix::ObjectId;
+
+/// Draws the commit graph column of the log, a line per commit like `git log --graph`, from the
+/// commits in the order they are walked.
+///
+/// Each column is a line of history waiting for the commit it expects next, the commit being
+/// drawn as `*` in its column, other lines as `|`, and lines that end at it by joining its column
+/// as `/` or `\`.
+#[derive(Debug, Default)]
+pub(crate) struct Graph {
+    columns: Vec<Option<ObjectId>>,
+}
+
+impl Graph {
+    /// The row for the commit `id` with `parents`, moving the lines on to its parents.
+    pub(crate) fn row(&mut self, id: ObjectId, parents: &[ObjectId]) -> String {
+        let column = match self.columns.iter().position(|c| *c == Some(id)) {
+            Some(column) => column,
+            None => self.free_column(),
+        };
+        let mut row = String::new();
+        for (i, expected) in self.columns.iter_mut().enumerate() {
+            let symbol = if i == column {
+                '*'
+            } else if *expected == Some(id) {
+                // other lines to the commit join it here
+                *expected = None;
+                if i < column { '\\' } else { '/' }
+            } else if expected.is_some() {
+                '|'
+            } else {
+                ' '
+            };
+            row.push(symbol);
+            row.push(' ');
+        }
+
+        self.columns[column] = parents.first().copied();
+        for &parent in parents.iter().skip(1) {
+            if !self.columns.contains(&Some(parent)) {
+                let free = self.free_column();
+                self.columns[free] = Some(parent);
+            }
+        }
+        while self.columns.last() == Some(&None) {
+            self.columns.pop();
+        }
+        row.trim_end().to_owned()
+    }
+
+    /// The first column with no line in it, adding one if needed.
+    fn free_column(&mut self) -> usize {
+        match self.columns.iter().position(Option::is_none) {
+            Some(free) => free,
+            None => {
+                self.columns.push(None);
+                self.columns.len() - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> ObjectId {
+        ObjectId::from_bytes_or_panic(&[n; 20])
+    }
+
+    #[test]
+    fn linear_history() {
+        let mut graph = Graph::default();
+        assert_eq!(graph.row(id(3), &[id(2)]), "*");
+        assert_eq!(graph.row(id(2), &[id(1)]), "*");
+        assert_eq!(graph.row(id(1), &[]), "*");
+    }
+
+    #[test]
+    fn merge_and_fork_point() {
+        let mut graph = Graph::default();
+        // 4 merges 3 into 2, both branching from 1
+        assert_eq!(graph.row(id(4), &[id(2), id(3)]), "*");
+        assert_eq!(graph.row(id(3), &[id(1)]), "| *");
+        assert_eq!(graph.row(id(2), &[id(1)]), "* |");
+        assert_eq!(graph.row(id(1), &[]), "* /");
+        assert_eq!(graph.row(id(0), &[]), "*");
+    }
+}
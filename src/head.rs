@@ -0,0 +1,46 @@
+use gix::{ObjectId, Repository};
+use tracing::{debug, warn};
+
+/// Branches tried in order when `HEAD` doesn't lead to a commit, before any other branch.
+const FALLBACK_BRANCHES: [&str; 2] = ["main", "master"];
+
+/// The commit whose log and files the pages show.
+///
+/// This is the tip of `default_branch` if given, otherwise whatever `HEAD` points at, even when
+/// detached. When `HEAD` is unborn or broken it falls back to `main`, `master`, then the first
+/// local branch, so a repo whose `HEAD` names a deleted branch is still built. `None` if the repo
+/// has no commits at all.
+pub(crate) fn resolve_head(
+    repo: &Repository,
+    default_branch: Option<&str>,
+) -> anyhow::Result<Option<ObjectId>> {
+    if let Some(branch) = default_branch {
+        let Some(mut reference) =
+            repo.try_find_reference(format!("refs/heads/{branch}").as_str())?
+        else {
+            anyhow::bail!("default branch {branch} not found");
+        };
+        return Ok(Some(reference.peel_to_commit()?.id));
+    }
+    match repo.head()?.peel_to_commit() {
+        Ok(commit) => return Ok(Some(commit.id)),
+        Err(error) => debug!(repo=?repo.path(), %error, "HEAD has no commit, trying fallbacks"),
+    }
+    for branch in FALLBACK_BRANCHES {
+        if let Some(mut reference) =
+            repo.try_find_reference(format!("refs/heads/{branch}").as_str())?
+            && let Ok(commit) = reference.peel_to_commit()
+        {
+            warn!(repo=?repo.path(), branch, "HEAD has no commit, using fallback branch");
+            return Ok(Some(commit.id));
+        }
+    }
+    for branch in repo.references()?.local_branches()? {
+        let mut branch = branch.map_err(|e| anyhow::anyhow!(e))?;
+        if let Ok(commit) = branch.peel_to_commit() {
+            warn!(repo=?repo.path(), branch=%branch.name().shorten(), "HEAD has no commit, using first branch");
+            return Ok(Some(commit.id));
+        }
+    }
+    Ok(None)
+}
@@ -1,10 +1,10 @@
 use std::fmt::Display;
 
-use build_html::Html;
+use build_html::{Html, HtmlContainer as _, TableCell, TableRow, escape_html};
 
 #[derive(Debug)]
 pub struct Bold {
-    content: String,
+    content: Escaped,
 }
 
 impl Html for Bold {
@@ -13,10 +13,135 @@ impl Html for Bold {
     }
 }
 
-impl<T: Display> From<T> for Bold {
-    fn from(value: T) -> Self {
+impl From<&'static str> for Bold {
+    fn from(value: &'static str) -> Self {
         Bold {
-            content: value.to_string(),
+            content: Escaped::new(value),
         }
     }
 }
+
+/// Text escaped for html, so untrusted strings from a repo, such as commit messages, author
+/// names, ref names and paths, can be inserted raw into a page without injecting markup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Escaped(String);
+
+impl Escaped {
+    pub fn new(text: impl Display) -> Self {
+        Escaped(escape_html(&text.to_string()))
+    }
+
+    /// Markup put together by stagix itself, only from literals and text that was escaped
+    /// already, such as that of the helpers below.
+    pub(crate) fn markup(html: String) -> Self {
+        Escaped(html)
+    }
+
+    /// Append `html` to this.
+    pub(crate) fn push(&mut self, html: &Escaped) {
+        self.0.push_str(&html.0);
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Escaped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromIterator<Escaped> for Escaped {
+    fn from_iter<I: IntoIterator<Item = Escaped>>(iter: I) -> Self {
+        Escaped(iter.into_iter().map(|html| html.0).collect())
+    }
+}
+
+impl Html for Escaped {
+    fn to_html_string(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// A link to `href` around `content`.
+pub(crate) fn link(href: &Escaped, content: &Escaped) -> Escaped {
+    Escaped(format!("<a href=\"{href}\">{content}</a>"))
+}
+
+/// A table cell holding `content`.
+pub(crate) fn cell(content: &Escaped) -> TableCell {
+    TableCell::default().with_raw(content)
+}
+
+/// A table row of `cells`.
+pub(crate) fn row<'a>(cells: impl IntoIterator<Item = &'a Escaped>) -> TableRow {
+    cells
+        .into_iter()
+        .fold(TableRow::new(), |row, content| row.with_cell(cell(content)))
+}
+
+/// Escape a single line of text, wrapping trailing whitespace and a trailing carriage return in
+/// spans so they can be made visible.
+pub fn escape_line_marking_whitespace(line: &str) -> Escaped {
+    let (line, cr) = match line.strip_suffix('\r') {
+        Some(line) => (line, true),
+        None => (line, false),
+    };
+    let trimmed = line.trim_end_matches([' ', '\t']);
+    let trailing = &line[trimmed.len()..];
+    let mut out = Escaped::new(trimmed);
+    if !trailing.is_empty() {
+        out.0
+            .push_str(&format!("<span class=\"ws\">{}</span>", trailing));
+    }
+    if cr {
+        out.0.push_str("<span class=\"cr\">^M</span>");
+    }
+    out
+}
+
+/// Percent encode a `/` separated path for use in a url, leaving only unreserved characters and
+/// the separators as they are.
+pub(crate) fn url_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// A page sending the browser straight on to `url`, for paths that have moved.
+pub(crate) fn redirect_html(url: &str) -> String {
+    let url = escape_html(url);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta http-equiv=\"refresh\" content=\"0; url={url}\">\n\
+         <link rel=\"canonical\" href=\"{url}\">\n</head>\n\
+         <body><a href=\"{url}\">Moved to {url}</a></body>\n</html>\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_paths_are_percent_encoded() {
+        assert_eq!(url_path("src/lib.rs"), "src/lib.rs");
+        assert_eq!(url_path("a b/c#d?.md"), "a%20b/c%23d%3F.md");
+        assert_eq!(url_path("\"><x>"), "%22%3E%3Cx%3E");
+        assert_eq!(url_path("é"), "%C3%A9");
+    }
+
+    #[test]
+    fn escaped_text_has_no_markup() {
+        let escaped = Escaped::new("<script>\"a\" & 'b'</script>");
+        assert!(!escaped.as_str().contains(['<', '>', '"']), "{escaped}");
+    }
+}
@@ -0,0 +1,164 @@
+use std::cmp::Reverse;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gix::{ObjectId, Repository};
+use serde::Deserialize;
+
+use crate::html::Escaped;
+
+/// A column of the index table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexColumn {
+    /// Name of the repo, linking to its log.
+    Name,
+    Description,
+    Owner,
+    /// Time of the commit at `HEAD`.
+    LastCommit,
+    /// Link to the repo's published pages.
+    Pages,
+    /// First clone URL of the repo.
+    CloneUrl,
+    /// How long ago the commit at `HEAD` was made, such as `3 weeks`.
+    Idle,
+    /// Sparkline of the commits per week over the last 12 weeks.
+    Activity,
+}
+
+/// Columns shown when none are given.
+pub const DEFAULT_INDEX_COLUMNS: [IndexColumn; 5] = [
+    IndexColumn::Name,
+    IndexColumn::Description,
+    IndexColumn::Owner,
+    IndexColumn::LastCommit,
+    IndexColumn::Pages,
+];
+
+impl IndexColumn {
+    pub fn header(self) -> &'static str {
+        match self {
+            IndexColumn::Name => "Name",
+            IndexColumn::Description => "Description",
+            IndexColumn::Owner => "Owner",
+            IndexColumn::LastCommit => "Last commit",
+            IndexColumn::Pages => "Pages URL",
+            IndexColumn::CloneUrl => "Clone URL",
+            IndexColumn::Idle => "Idle",
+            IndexColumn::Activity => "Activity",
+        }
+    }
+}
+
+/// Order of the repos on the index page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexSort {
+    /// Alphabetically by repo name.
+    Name,
+    /// Most recently committed to first.
+    #[default]
+    LastCommit,
+    /// Alphabetically by owner.
+    Owner,
+}
+
+/// A repo's row of the index table along with what it is grouped and sorted by.
+pub(crate) struct IndexRow {
+    pub(crate) category: Option<String>,
+    pub(crate) name: String,
+    pub(crate) owner: String,
+    pub(crate) last_commit: i64,
+    pub(crate) cells: Vec<Escaped>,
+}
+
+/// Sort `rows` by `sort`, keeping argument order between equal rows.
+pub(crate) fn sort_index_rows(rows: &mut [IndexRow], sort: IndexSort, reverse: bool) {
+    match sort {
+        IndexSort::Name => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+        IndexSort::LastCommit => rows.sort_by_key(|row| Reverse(row.last_commit)),
+        IndexSort::Owner => rows.sort_by(|a, b| a.owner.cmp(&b.owner)),
+    }
+    if reverse {
+        rows.reverse();
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Time since `seconds` after the epoch in the largest whole unit, like cgit's idle column.
+pub(crate) fn idle_time(seconds: i64) -> String {
+    let now = now_secs();
+    let idle = (now - seconds).max(0);
+    let units = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "min"),
+    ];
+    for (size, unit) in units {
+        let count = idle / size;
+        if count >= 1 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{count} {unit}{plural}");
+        }
+    }
+    format!("{idle} secs")
+}
+
+/// Number of weeks shown in the activity sparkline.
+const ACTIVITY_WEEKS: usize = 12;
+
+/// An inline SVG bar per week of the commits reachable from `HEAD` over the last
+/// [`ACTIVITY_WEEKS`] weeks, oldest first.
+///
+/// The walk stops at the first commit older than that, so only recent history is read.
+pub(crate) fn activity_sparkline(repo: &Repository, head: ObjectId) -> anyhow::Result<Escaped> {
+    const WEEK: i64 = 7 * 24 * 60 * 60;
+    let now = now_secs();
+    let cutoff = now - ACTIVITY_WEEKS as i64 * WEEK;
+    let mut weeks = [0usize; ACTIVITY_WEEKS];
+    let revs = repo
+        .rev_walk([head])
+        .sorting(gix::revision::walk::Sorting::ByCommitTimeCutoff {
+            order: Default::default(),
+            seconds: cutoff,
+        })
+        .all()?;
+    for rev in revs {
+        let age = (now - rev?.commit_time()).max(0);
+        if let Some(count) = (ACTIVITY_WEEKS - 1)
+            .checked_sub((age / WEEK) as usize)
+            .and_then(|week| weeks.get_mut(week))
+        {
+            *count += 1;
+        }
+    }
+
+    let busiest = weeks.iter().copied().max().unwrap_or(0).max(1);
+    let total = weeks.iter().sum::<usize>();
+    let bars = weeks
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let height = count * 12 / busiest;
+            format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"4\" height=\"{}\"/>",
+                i * 5,
+                12 - height,
+                height
+            )
+        })
+        .collect::<String>();
+    Ok(Escaped::markup(format!(
+        "<svg class=\"activity\" width=\"{}\" height=\"12\" role=\"img\">\
+         <title>{total} commits in the last {ACTIVITY_WEEKS} weeks</title>{bars}</svg>",
+        ACTIVITY_WEEKS * 5
+    )))
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use build_html::{Container, HtmlContainer as _, HtmlElement, Table, TableCell, TableRow};
+
+use crate::attributes::LinguistAttributes;
+use crate::html::{Escaped, cell};
+
+/// Languages known by the extension of their files, others going by the extension itself.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("css", "CSS"),
+    ("go", "Go"),
+    ("hs", "Haskell"),
+    ("html", "HTML"),
+    ("java", "Java"),
+    ("js", "JavaScript"),
+    ("json", "JSON"),
+    ("kt", "Kotlin"),
+    ("lua", "Lua"),
+    ("md", "Markdown"),
+    ("nix", "Nix"),
+    ("ml", "OCaml"),
+    ("pl", "Perl"),
+    ("php", "PHP"),
+    ("py", "Python"),
+    ("rb", "Ruby"),
+    ("rs", "Rust"),
+    ("scala", "Scala"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("sql", "SQL"),
+    ("swift", "Swift"),
+    ("tex", "TeX"),
+    ("toml", "TOML"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("zig", "Zig"),
+];
+
+/// Files and lines of text per language in a tree, shown as a bar and table at the top of the
+/// files page.
+#[derive(Debug, Default)]
+pub(crate) struct Languages {
+    /// Files and lines of each language.
+    counts: HashMap<String, (usize, usize)>,
+}
+
+impl Languages {
+    /// Count a text file of `lines` at `path`, leaving out generated and vendored files like
+    /// linguist does.
+    pub(crate) fn record(&mut self, path: &str, attributes: &LinguistAttributes, lines: usize) {
+        if attributes.generated || attributes.vendored {
+            return;
+        }
+        let Some(language) = attributes.language.clone().or_else(|| language_of(path)) else {
+            return;
+        };
+        let (files, total) = self.counts.entry(language).or_default();
+        *files += 1;
+        *total += lines;
+    }
+
+    /// The breakdown, most lines first, `None` if no files were counted.
+    pub(crate) fn to_container(&self) -> Option<Container> {
+        let total = self.counts.values().map(|(_, lines)| lines).sum::<usize>();
+        if total == 0 {
+            return None;
+        }
+        let mut languages = self.counts.iter().collect::<Vec<_>>();
+        languages.sort_by(|(a_name, (_, a)), (b_name, (_, b))| b.cmp(a).then(a_name.cmp(b_name)));
+
+        let mut bar =
+            HtmlElement::new(build_html::HtmlTag::Div).with_attribute("class", "languages");
+        let mut table = Table::new()
+            .with_attributes([("id", "languages")])
+            .with_header_row(["Language", "Files", "Lines", "%"]);
+        for (name, (files, lines)) in languages {
+            let percent = *lines as f64 * 100.0 / total as f64;
+            let name = Escaped::new(name);
+            bar.add_html(
+                HtmlElement::new(build_html::HtmlTag::Span)
+                    .with_attribute(
+                        "style",
+                        format!(
+                            "width: {percent:.1}%; background-color: hsl({}, 50%, 50%)",
+                            hue(name.as_str())
+                        ),
+                    )
+                    .with_attribute("title", format!("{name} {percent:.1}%")),
+            );
+            table.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(cell(&name))
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(files),
+                    )
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(lines),
+                    )
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(format!("{percent:.1}")),
+                    ),
+            );
+        }
+        let mut container = Container::new(build_html::ContainerType::Div);
+        container.add_header(2, "Languages");
+        container.add_html(bar);
+        container.add_table(table);
+        Some(container)
+    }
+}
+
+/// The language of `path` from its extension, `None` for files without one.
+fn language_of(path: &str) -> Option<String> {
+    let extension = Path::new(path)
+        .extension()?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    Some(
+        EXTENSIONS
+            .iter()
+            .find(|(e, _)| *e == extension)
+            .map_or_else(
+                || format!(".{extension}"),
+                |(_, language)| (*language).to_owned(),
+            ),
+    )
+}
+
+/// A stable colour for each language in the bar.
+fn hue(name: &str) -> u32 {
+    name.bytes().fold(0u32, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(u32::from(b))
+    }) % 360
+}
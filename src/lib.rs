@@ -1,30 +1,151 @@
+use about::get_about;
+use ansi::{AnsiRenderer, has_ansi};
 use anyhow::Context as _;
+use archive::{Snapshot, get_snapshots};
+use attributes::Linguist;
+use authors::Authors;
+use avatar::avatar_html;
+use base64::Engine as _;
+use base64::prelude::BASE64_STANDARD;
+use blame::get_blames;
 use build_html::{
-    Container, Html as _, HtmlContainer as _, HtmlElement, HtmlPage, Table, TableCell, TableRow,
-    escape_html,
+    Container, Html as _, HtmlContainer as _, HtmlElement, HtmlPage, Table, TableCell,
+    TableCellType, TableRow,
 };
-use gix::bstr::ByteSlice as _;
+use commit_cache::{COMMIT_CACHE_FILE, CommitCache};
+use dirs::{dir_page_path, get_dir_pages};
+use dumb_http::export_dumb_http;
+use fallback::{NOT_FOUND_FILE, dir_index_stubs, not_found_container, write_not_found_page};
+use feed::{ATOM_FILE, get_atom};
+use file_listing::FileTree;
+use gix::bstr::{BStr, ByteSlice as _};
 use gix::date::time::format::ISO8601;
 use gix::diff::blob::UnifiedDiff;
 use gix::diff::blob::intern::InternedInput;
 use gix::diff::blob::unified_diff::{ConsumeBinaryHunk, ContextSize};
 use gix::objs::tree::EntryKind;
-use gix::traverse::tree::Recorder;
-use gix::{Repository, Tree};
-use html::Bold;
-use nix::fcntl::{OFlag, RenameFlags, open, renameat2};
-use nix::sys::stat::Mode;
-use std::fs::{File, create_dir, create_dir_all, read_to_string, remove_dir_all, remove_file};
+use gix::traverse::tree::{Recorder, recorder};
+use gix::{ObjectId, Repository, Tree};
+use graph::Graph;
+use head::resolve_head;
+use html::{
+    Bold, Escaped, cell, escape_line_marking_whitespace, link, redirect_html, row, url_path,
+};
+use languages::Languages;
+use log_archive::get_log_archive;
+use mailinglist::{MailingList, reply_href};
+use parallel::map_parallel;
+use patch::{format_patch, has_binary_changes};
+use progress::Progress;
+use release::{ReleasePage, get_releases, release_path_of};
+use renames::{RENAMES_CACHE_FILE, RenamesCache, rename_stubs, renamed_paths};
+use rich::rich_renderer;
+use safe_path::retain_safe_paths;
+use search::{SEARCH_INDEX_FILE, SearchIndex};
+use signature::{commit_signature, tag_signature};
+use size::{dir_size, format_size};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::{File, create_dir_all, read_to_string, remove_dir_all, remove_file};
 use std::os::unix::fs::symlink;
 use std::path::{Component, Path, PathBuf};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tracing::info;
 use tracing::{debug, warn};
-
+use trailers::{co_authors, trailers_table};
+
+mod about;
+mod ansi;
+mod archive;
+mod attributes;
+mod authors;
+mod avatar;
+mod blame;
+mod cli;
+mod commit_cache;
+mod commit_refs;
+mod data;
+mod dates;
+mod dirs;
+mod dumb_http;
+mod email;
+mod fallback;
+mod feed;
+mod file_listing;
+mod format;
+mod gemtext;
+mod graph;
+mod head;
 mod html;
+mod index_columns;
+mod languages;
+mod log_archive;
+mod mailinglist;
+mod manifest;
+mod markdown;
+mod merge_diff;
+mod output;
+mod pages;
+mod parallel;
+mod patch;
+mod progress;
+mod release;
+mod renames;
+mod render;
+mod repo_config;
+mod rich;
+mod safe_path;
+mod search;
+mod server_config;
+mod signature;
+mod sites;
+mod size;
+mod templates;
+mod theme;
+mod topology;
+mod trailers;
+mod validate;
+#[cfg(target_os = "linux")]
+mod watch;
+
+pub use cli::{AllArgs, Cli, IndexArgs, PagesArgs, RepoArgs, SitesArgs, VerifyArgs};
+pub use commit_refs::CommitRefs;
+pub use data::{
+    CollectOptions, CommitInfo, FileEntry, FileKind, RefInfo, RefKind, RepoSummary, collect_commit,
+    collect_diff, collect_files, collect_log, collect_refs, collect_summary,
+};
+pub use dates::{DateFormat, TimeZone};
+pub use email::EmailDisplay;
+pub use file_listing::FileListing;
+pub use format::Format;
+pub use index_columns::{DEFAULT_INDEX_COLUMNS, IndexColumn, IndexSort};
+use index_columns::{IndexRow, sort_index_rows};
+pub use log_archive::LogArchive;
+use manifest::ManifestSink;
+pub use manifest::{MANIFEST_FILE, Manifest, ManifestEntry, Source};
+pub use merge_diff::MergeDiff;
+pub use output::{Output, OutputSink};
+use output::{VerifySink, swap_dirs};
+use pages::{
+    PAGES_MANIFEST_FILE, PagesManifest, STAGING_MANIFEST_FILE, built_site_dir, run_pages_command,
+    split_pages_ref, sync_tree_to_dir, working_dir_name, write_redirect_stub,
+};
+pub use repo_config::{REPO_CONFIG_FILE, RepoConfig, is_hidden};
+pub use server_config::ServerConfig;
+pub use sites::{SiteConfig, SitesFile, build_site, build_sites};
+pub use size::parse_size;
+pub use templates::Templates;
+pub use theme::Theme;
+pub use topology::Topology;
+use topology::sort_by_committer_date;
+pub use validate::Validation;
+#[cfg(target_os = "linux")]
+pub use watch::watch_repo;
 
 const README_FILES: [&str; 2] = ["README", "README.md"];
 const LICENSE_FILES: [&str; 3] = ["LICENSE", "LICENSE.md", "COPYING"];
+const CHANGELOG_FILES: [&str; 2] = ["CHANGELOG", "CHANGELOG.md"];
+const CONTRIBUTING_FILES: [&str; 2] = ["CONTRIBUTING", "CONTRIBUTING.md"];
 
 #[derive(Debug)]
 pub struct Meta {
@@ -33,30 +154,72 @@ pub struct Meta {
     pub name: String,
     pub owner: String,
     pub pages: Option<String>,
+    /// Custom domain the pages are published to.
+    pub domain: Option<String>,
     pub readme: Option<String>,
     pub license: Option<String>,
+    pub changelog: Option<String>,
+    pub contributing: Option<String>,
+    /// Address of the mailing list patches are sent to, or the URL of its archive.
+    pub mailinglist: Option<String>,
     pub mod_time: SystemTime,
+    /// Log length from the repo config, used if none is given in the options.
+    pub log_length: Option<usize>,
+    /// Number of commits in the atom feed, from the repo config.
+    pub feed_length: Option<usize>,
+    /// Section of the index page the repo is listed under.
+    pub category: Option<String>,
+    /// Commit the log and files are built from, `None` if the repo has no commits.
+    pub head: Option<ObjectId>,
 }
 
 impl Meta {
+    /// Load the metadata of the repo at `path`, its head being the tip of `default_branch` if
+    /// given, otherwise of the `default_branch` in its config, otherwise `HEAD` or a fallback.
     pub fn load(
         repo: &Repository,
         path: &Path,
         clone_base_urls: &[String],
+        default_branch: Option<&str>,
     ) -> anyhow::Result<Self> {
         debug!(repo =? repo.path(), ?path, ?clone_base_urls, "loading metadata for repo");
-        let description = Self::load_meta_file(repo, "description")?.unwrap_or_default();
+        let config = RepoConfig::load(repo.path())?;
+        let description = match config.description {
+            Some(description) => description,
+            None => Self::load_meta_file(repo, "description")?.unwrap_or_default(),
+        };
         if description.is_empty() {
             debug!("no description file found");
         }
-        let owner = Self::load_meta_file(repo, "owner")?.unwrap_or_default();
+        let owner = match config.owner {
+            Some(owner) => owner,
+            None => Self::load_meta_file(repo, "owner")?.unwrap_or_default(),
+        };
         if owner.is_empty() {
             debug!("no owner file found");
         }
-        let pages = Self::load_meta_file(repo, "pages")?;
+        let pages = match config.pages {
+            Some(pages) => Some(pages),
+            None => Self::load_meta_file(repo, "pages")?,
+        };
         if pages.is_none() {
             debug!("no pages file found");
         }
+        let domain = match config.domain {
+            Some(domain) => Some(domain),
+            None => Self::load_meta_file(repo, "domain")?,
+        }
+        .filter(|d| !d.is_empty());
+        let category = match config.category {
+            Some(category) => Some(category),
+            None => Self::load_meta_file(repo, "category")?,
+        }
+        .filter(|c| !c.is_empty());
+        let mailinglist = match config.mailinglist {
+            Some(mailinglist) => Some(mailinglist),
+            None => Self::load_meta_file(repo, "mailinglist")?,
+        }
+        .filter(|m| !m.is_empty());
         let name = path
             .canonicalize()?
             .with_extension("")
@@ -65,14 +228,34 @@ impl Meta {
             .to_string_lossy()
             .into_owned();
 
-        let urls = clone_base_urls
-            .iter()
-            .map(|cbu| format!("{cbu}/{name}.git"))
-            .collect::<Vec<_>>();
-
-        let head_tree = repo.head_tree()?;
+        // a url file, as with stagit, lists the clone URLs in place of those from the base URLs
+        let urls = match Self::load_meta_file(repo, "url")? {
+            Some(url) => url.lines().map(|l| l.trim().to_owned()).collect::<Vec<_>>(),
+            None => clone_base_urls
+                .iter()
+                .map(|cbu| format!("{}/{name}.git", cbu.trim_end_matches('/')))
+                .collect(),
+        }
+        .into_iter()
+        .filter(|url| !url.is_empty())
+        .chain(config.clone_urls)
+        .collect::<Vec<_>>();
+
+        let head = resolve_head(repo, default_branch.or(config.default_branch.as_deref()))
+            .context("resolve head")?;
+        // an empty repo has no commits yet
+        let head_commit = match head {
+            Some(head) => Some(repo.find_commit(head)?),
+            None => None,
+        };
+        let head_tree = match &head_commit {
+            Some(head_commit) => head_commit.tree()?,
+            None => repo.empty_tree(),
+        };
         let mut readme = None;
         let mut license = None;
+        let mut changelog = None;
+        let mut contributing = None;
         for entry in head_tree.iter() {
             let entry = entry?;
             if !entry.mode().is_blob() {
@@ -83,21 +266,35 @@ impl Meta {
                 readme = Some(filename);
             } else if LICENSE_FILES.contains(&filename.as_str()) {
                 license = Some(filename);
+            } else if CHANGELOG_FILES.contains(&filename.as_str()) {
+                changelog = Some(filename);
+            } else if CONTRIBUTING_FILES.contains(&filename.as_str()) {
+                contributing = Some(filename);
             }
         }
 
-        let mut max_mod_time = ["description", "owner", "pages"]
-            .iter()
-            .map(|n| {
-                Self::load_meta_mod_time(repo, n)
-                    .unwrap_or_default()
-                    .unwrap_or(UNIX_EPOCH)
-            })
-            .max()
-            .unwrap_or(UNIX_EPOCH);
-        let head_commit_time = repo.head_commit()?.time()?;
-        max_mod_time =
-            max_mod_time.max(UNIX_EPOCH + Duration::from_secs(head_commit_time.seconds as u64));
+        let mut max_mod_time = [
+            "description",
+            "owner",
+            "pages",
+            "domain",
+            "url",
+            "mailinglist",
+            REPO_CONFIG_FILE,
+        ]
+        .iter()
+        .map(|n| {
+            Self::load_meta_mod_time(repo, n)
+                .unwrap_or_default()
+                .unwrap_or(UNIX_EPOCH)
+        })
+        .max()
+        .unwrap_or(UNIX_EPOCH);
+        if let Some(head_commit) = &head_commit {
+            let head_commit_time = head_commit.time()?;
+            max_mod_time =
+                max_mod_time.max(UNIX_EPOCH + Duration::from_secs(head_commit_time.seconds as u64));
+        }
 
         Ok(Meta {
             description,
@@ -105,9 +302,17 @@ impl Meta {
             name,
             owner,
             pages,
+            domain,
             readme,
             license,
+            changelog,
+            contributing,
+            mailinglist,
             mod_time: max_mod_time,
+            log_length: config.log_length,
+            feed_length: config.feed_length,
+            category,
+            head,
         })
     }
 
@@ -137,102 +342,365 @@ impl Meta {
         filepath: &Path,
         container: Container,
         nav: bool,
-        out_dir: &Path,
+        sink: &mut dyn OutputSink,
+        options: &PageOptions,
     ) -> anyhow::Result<()> {
-        debug!(
-            ?title,
-            ?filepath,
-            ?nav,
-            ?out_dir,
-            "writing html content to file"
-        );
-        let path = out_dir.join(filepath);
-        let mut file = File::create(&path)?;
-        let to_repo_root = to_root_path(&path, out_dir);
+        debug!(?title, ?filepath, ?nav, "writing html content to file");
+        let to_repo_root = to_root_path(filepath);
         let to_index_root = format!("../{}", to_repo_root);
-        self.write_html_content(
-            title,
-            &to_index_root,
-            &to_repo_root,
-            container,
-            nav,
-            &mut file,
-        )
+        let location = PageLocation {
+            path: filepath,
+            to_index_root: &to_index_root,
+            to_repo_root: &to_repo_root,
+        };
+        let mut buf = Vec::new();
+        self.write_html_content(title, &location, container, nav, options, &mut buf)?;
+        sink.write(filepath, &buf)
     }
 
     pub fn write_html_content(
         &self,
         title: &str,
-        to_index_root: &str,
-        to_repo_root: &str,
+        location: &PageLocation<'_>,
         container: Container,
         nav: bool,
+        options: &PageOptions,
         out: &mut impl std::io::Write,
     ) -> anyhow::Result<()> {
-        debug!(
-            ?title,
-            ?to_index_root,
-            ?to_repo_root,
-            ?nav,
-            "writing html content to writer"
-        );
-        let mut head_table = Table::new();
-        head_table.add_body_row([
-            &HtmlElement::new(build_html::HtmlTag::Div)
-                .with_link(
-                    format!("{}index.html", to_index_root),
-                    HtmlElement::new(build_html::HtmlTag::Div)
-                        .with_image_attr(
-                            format!("{}logo.png", to_index_root),
-                            "logo",
-                            [("id", "logo")],
-                        )
-                        .to_html_string(),
-                )
-                .to_html_string(),
-            &Container::new(build_html::ContainerType::Div)
-                .with_header(1, &self.name)
-                .with_html(
-                    HtmlElement::new(build_html::HtmlTag::Span)
-                        .with_attribute("class", "desc")
-                        .with_raw(&self.description),
-                )
-                .to_html_string(),
-        ]);
-        for url in &self.urls {
-            head_table.add_body_row(["", &format!("git clone {}", url)]);
+        debug!(?title, ?location, ?nav, "writing html content to writer");
+        let PageLocation {
+            path,
+            to_index_root,
+            to_repo_root,
+        } = *location;
+        if options.fragment {
+            let html = container.to_html_string();
+            if let Some(validation) = options.validate {
+                validate::validate_page(title, &html, validation)?;
+            }
+            out.write_all(html.as_bytes())?;
+            return Ok(());
         }
+
+        let inline_assets = options.inline_assets.as_ref();
+        let logo_src = inline_assets
+            .and_then(|a| a.logo.clone())
+            .unwrap_or_else(|| format!("{}logo.png", to_index_root));
+        let favicon_href = inline_assets
+            .and_then(|a| a.favicon.clone())
+            .unwrap_or_else(|| format!("{}favicon.png", to_index_root));
+
+        let mut nav_links = Vec::new();
         if nav {
-            let mut nav = Container::new(build_html::ContainerType::Nav)
-                .with_link(format!("{}log.html", to_repo_root), "Log")
-                .with_raw(" | ")
-                .with_link(format!("{}files.html", to_repo_root), "Files")
-                .with_raw(" | ")
-                .with_link(format!("{}refs.html", to_repo_root), "Refs");
-            if let Some(readme) = &self.readme {
-                nav.add_raw(" | ");
-                nav.add_link(format!("{}files/{}.html", to_repo_root, readme), "README");
+            let (log_root, files_root) = match &options.ref_root {
+                Some(ref_root) => {
+                    let dir = format!("{}{}/", to_repo_root, ref_root.dir.display());
+                    let log_root = if ref_root.has_log {
+                        dir.clone()
+                    } else {
+                        to_repo_root.to_owned()
+                    };
+                    (log_root, dir)
+                }
+                None => (to_repo_root.to_owned(), to_repo_root.to_owned()),
+            };
+            nav_links.push((format!("{}log.html", log_root), "Log"));
+            nav_links.push((format!("{}files.html", files_root), "Files"));
+            nav_links.push((format!("{}refs.html", to_repo_root), "Refs"));
+            // empty repos only get the log, files and refs pages
+            if self.head.is_some() {
+                nav_links.push((format!("{}stats.html", to_repo_root), "Stats"));
+            }
+            if options.search && self.head.is_some() {
+                nav_links.push((format!("{}search.html", to_repo_root), "Search"));
+            }
+            if self.readme.is_some() {
+                nav_links.push((format!("{}about.html", to_repo_root), "README"));
             }
             if let Some(license) = &self.license {
-                nav.add_raw(" | ");
-                nav.add_link(format!("{}files/{}.html", to_repo_root, license), "LICENSE");
+                nav_links.push((format!("{}files/{}.html", to_repo_root, license), "LICENSE"));
+            }
+            if self.changelog.is_some() {
+                nav_links.push((format!("{}changelog.html", to_repo_root), "CHANGELOG"));
+            }
+            if self.contributing.is_some() {
+                nav_links.push((format!("{}contributing.html", to_repo_root), "CONTRIBUTING"));
+            }
+            if let Some(mailinglist) = &self.mailinglist {
+                nav_links.push((MailingList::new(mailinglist).href(), "Patches"));
             }
-            head_table.add_body_row(["", &nav.to_html_string()]);
         }
+        let template_context = minijinja::context! {
+            title,
+            name => &self.name,
+            description => &self.description,
+            owner => &self.owner,
+            urls => &self.urls,
+            logo => &logo_src,
+            index => format!("{}index.html", to_index_root),
+            nav => nav_links
+                .iter()
+                .map(|(href, label)| minijinja::context! { href, label })
+                .collect::<Vec<_>>(),
+        };
+        let templates = options.templates.as_ref();
+        let render_template = |name: &str, context: &minijinja::Value| match templates {
+            Some(templates) => templates.render(name, context),
+            None => Ok(None),
+        };
+
+        let nav_html = match render_template("nav.html", &template_context)? {
+            Some(nav_html) => nav_html,
+            None if nav_links.is_empty() => String::new(),
+            None => {
+                let mut nav = Container::new(build_html::ContainerType::Nav);
+                for (i, (href, label)) in nav_links.iter().enumerate() {
+                    if i > 0 {
+                        nav.add_raw(" | ");
+                    }
+                    nav.add_raw(link(&Escaped::new(href), &Escaped::new(label)));
+                }
+                nav.to_html_string()
+            }
+        };
+        let header_context = minijinja::context! {
+            nav_html => minijinja::Value::from_safe_string(nav_html.clone()),
+            ..template_context.clone()
+        };
+        let header_html = match render_template("header.html", &header_context)? {
+            Some(header_html) => header_html,
+            None => {
+                let mut head_table = Table::new();
+                head_table.add_body_row([
+                    &HtmlElement::new(build_html::HtmlTag::Div)
+                        .with_link(
+                            format!("{}index.html", to_index_root),
+                            HtmlElement::new(build_html::HtmlTag::Div)
+                                .with_image_attr(&logo_src, "logo", [("id", "logo")])
+                                .to_html_string(),
+                        )
+                        .to_html_string(),
+                    &Container::new(build_html::ContainerType::Div)
+                        .with_header(1, Escaped::new(&self.name))
+                        .with_html(
+                            HtmlElement::new(build_html::HtmlTag::Span)
+                                .with_attribute("class", "desc")
+                                .with_raw(Escaped::new(&self.description)),
+                        )
+                        .to_html_string(),
+                ]);
+                for url in &self.urls {
+                    head_table.add_custom_body_row(row([
+                        &Escaped::default(),
+                        &Escaped::new(format!("git clone {url}")),
+                    ]));
+                }
+                if !nav_html.is_empty() {
+                    head_table.add_body_row(["", &nav_html]);
+                }
+                head_table.to_html_string()
+            }
+        };
 
-        let page = HtmlPage::new()
-            .with_title(format!("{} - {} - {}", title, self.name, self.description))
-            .with_stylesheet(format!("{}style.css", to_index_root))
-            .with_head_link(format!("{}favicon.png", to_index_root), "icon")
-            .with_table(head_table)
+        let full_title = Escaped::new(format!("{} - {} - {}", title, self.name, self.description));
+        let mut page = HtmlPage::new().with_title(full_title.as_str());
+        if let Some(url) = options.url_for(path) {
+            page.add_head_link(&url, "canonical");
+            page.add_meta([("property", "og:title"), ("content", full_title.as_str())]);
+            page.add_meta([("property", "og:type"), ("content", "website")]);
+            page.add_meta([("property", "og:url"), ("content", url.as_str())]);
+        }
+        if let Some(stylesheet) = inline_assets.and_then(|a| a.stylesheet.as_ref()) {
+            page.add_style(stylesheet);
+        } else {
+            page.add_stylesheet(format!("{}style.css", to_index_root));
+        }
+        page.add_head_link(favicon_href, "icon");
+        if nav && self.head.is_some() {
+            let feed_title = Escaped::new(format!("{} Atom Feed", self.name));
+            page.add_head_link_attr(
+                format!("{}{}", to_repo_root, ATOM_FILE),
+                "alternate",
+                [
+                    ("type", "application/atom+xml"),
+                    ("title", feed_title.as_str()),
+                ],
+            );
+        }
+        if let Some(tab_width) = options.tab_width {
+            page.add_style(format!(
+                "pre {{ tab-size: {tab_width}; -moz-tab-size: {tab_width}; }}"
+            ));
+        }
+        if let Some(header) = &options.header_include {
+            page.add_raw(header);
+        }
+        let mut page = page
+            .with_raw(header_html)
             .with_html(HtmlElement::new(build_html::HtmlTag::HorizontalRule))
             .with_container(container);
+        if let Some(footer) = render_template("footer.html", &template_context)? {
+            page.add_raw(footer);
+        }
+        if let Some(footer) = &options.footer_include {
+            page.add_raw(footer);
+        }
 
-        out.write_all(page.to_html_string().as_bytes())?;
+        let html = page.to_html_string();
+        if let Some(validation) = options.validate {
+            validate::validate_page(title, &html, validation)?;
+        }
+        out.write_all(html.as_bytes())?;
         Ok(())
     }
 }
 
+/// Where a page is written in the output, with the links from it to the roots of the index and of
+/// the repo's output, relative unless the page can be served from elsewhere.
+#[derive(Debug, Clone, Copy)]
+pub struct PageLocation<'a> {
+    pub path: &'a Path,
+    pub to_index_root: &'a str,
+    pub to_repo_root: &'a str,
+}
+
+/// Options affecting how every generated page is rendered.
+#[derive(Debug, Default, Clone)]
+pub struct PageOptions {
+    /// Check generated pages for well-formedness problems.
+    pub validate: Option<Validation>,
+    /// Assets to embed into every page rather than link to, making each page standalone.
+    pub inline_assets: Option<InlineAssets>,
+    /// URL the output is served from, used for all absolute links such as canonical links,
+    /// Open Graph metadata, sitemaps and feeds.
+    pub base_url: Option<String>,
+    /// Raw html inserted at the top of every page body, before the header.
+    pub header_include: Option<String>,
+    /// Raw html inserted at the bottom of every page body, after the content.
+    pub footer_include: Option<String>,
+    /// Width of tab characters in file views and diffs, the browser default of 8 if unset.
+    pub tab_width: Option<usize>,
+    /// Highlight trailing whitespace and carriage returns in file views and diffs.
+    pub show_whitespace: bool,
+    /// Emit only the content of each page, without the surrounding document or header, for
+    /// embedding into the layout of another site.
+    pub fragment: bool,
+    /// Pages of a ref other than `HEAD` that the nav links to instead of the repo's own.
+    pub ref_root: Option<RefRoot>,
+    /// Percentage of similarity for a deleted and added file in a diff to be shown as a rename or
+    /// copy, 50 if not given.
+    pub rename_similarity: Option<u8>,
+    /// Show before and after images for changed images in diffs, embedded into the page.
+    pub image_diffs: bool,
+    /// Number of changed files to show in a diff, with the rest only in the raw diff.
+    pub max_diff_files: Option<usize>,
+    /// Number of lines of a diff to show, with the rest only in the raw diff.
+    pub max_diff_lines: Option<usize>,
+    /// An ssh `allowed_signers` file to verify ssh signatures of commits and tags against.
+    pub allowed_signers: Option<PathBuf>,
+    /// How the diff of a merge commit is shown.
+    pub merge_diff: MergeDiff,
+    /// Which commits of the history are in the log and have commit pages.
+    pub topology: Topology,
+    /// Link to a search page listing every file, commit and ref, written with a `search.json`
+    /// index for client-side search.
+    pub search: bool,
+    /// Also write the files pages of `HEAD` and every tag under `blob/<commit>/`, which never
+    /// change, linking to them from the files pages as permalinks.
+    pub blob_pages: bool,
+    /// Templates replacing the built-in header, nav or footer.
+    pub templates: Option<Templates>,
+    /// Also write an index page for every directory at `files/<dir>/index.html`, linked from the
+    /// files page, for browsing the tree a directory at a time.
+    pub dir_pages: bool,
+    /// How the files page lists the files.
+    pub file_listing: FileListing,
+    /// Size in bytes above which a file's page only links to the raw file rather than showing
+    /// it.
+    pub max_blob_size: Option<u64>,
+    /// Url of a mermaid script loaded by pages with mermaid diagrams in their markdown to draw
+    /// them, the diagrams being left as their source otherwise.
+    pub mermaid_script: Option<String>,
+    /// How commit and tag times are shown on the log, refs and index pages.
+    pub date_format: DateFormat,
+    /// Offset the times of commits and tags are shown in.
+    pub time_zone: TimeZone,
+    /// Show the committer dates of commits in the log rather than their author dates, the log
+    /// keeping the order of the topology.
+    pub show_committer_dates: bool,
+    /// List the commits in the log newest first by committer date rather than in the order of
+    /// the history, without the commit graph.
+    pub committer_date_order: bool,
+    /// Url of author avatars shown in the log and authors tables, `{md5}` being replaced by the
+    /// md5 of each author's email, without avatars if unset or the emails aren't shown whole.
+    pub avatar_url_template: Option<String>,
+    /// How the emails of authors, committers and taggers are shown in pages, feeds and patches.
+    pub emails: EmailDisplay,
+}
+
+/// Directory in a repo's output holding the pages of a single branch or tag.
+#[derive(Debug, Clone)]
+pub struct RefRoot {
+    pub dir: PathBuf,
+    /// Whether the directory has its own log, or only files.
+    pub has_log: bool,
+}
+
+impl PageOptions {
+    /// Absolute URL of `path` in the output, if a base URL is set.
+    pub fn url_for(&self, path: &Path) -> Option<String> {
+        let base_url = self.base_url.as_deref()?.trim_end_matches('/');
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Some(format!("{base_url}/{path}"))
+    }
+}
+
+/// Stylesheet, logo and favicon contents embedded directly into pages.
+#[derive(Debug, Default, Clone)]
+pub struct InlineAssets {
+    pub stylesheet: Option<String>,
+    /// `data:` URI of the logo.
+    pub logo: Option<String>,
+    /// `data:` URI of the favicon.
+    pub favicon: Option<String>,
+}
+
+impl InlineAssets {
+    pub fn load(
+        stylesheet: Option<&Path>,
+        logo: Option<&Path>,
+        favicon: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        debug!(?stylesheet, ?logo, ?favicon, "loading inline assets");
+        let stylesheet = stylesheet
+            .map(|p| read_to_string(p).context("read stylesheet"))
+            .transpose()?;
+        let logo = logo
+            .map(|p| data_uri(p, "image/png").context("read logo"))
+            .transpose()?;
+        let favicon = favicon
+            .map(|p| data_uri(p, "image/png").context("read favicon"))
+            .transpose()?;
+        Ok(Self {
+            stylesheet,
+            logo,
+            favicon,
+        })
+    }
+}
+
+fn data_uri(path: &Path, mime: &str) -> anyhow::Result<String> {
+    let data = std::fs::read(path)?;
+    Ok(format!(
+        "data:{mime};base64,{}",
+        BASE64_STANDARD.encode(data)
+    ))
+}
+
 #[derive(Debug)]
 pub struct IndexOptions {
     pub out_dir: Option<PathBuf>,
@@ -241,19 +709,41 @@ pub struct IndexOptions {
     pub favicon: Option<PathBuf>,
     pub repos_url: Option<String>,
     pub pages_url: Option<String>,
+    /// Built-in theme providing any of the stylesheet, logo and favicon not given explicitly.
+    pub theme: Option<Theme>,
+    pub page: PageOptions,
+    /// Number of repos to read concurrently.
+    pub jobs: usize,
+    /// Columns of the index table, the [`DEFAULT_INDEX_COLUMNS`] if empty.
+    pub columns: Vec<IndexColumn>,
+    /// Order of the repos, within each category.
+    pub sort: IndexSort,
+    /// Reverse the order of the repos.
+    pub reverse: bool,
+    /// Formats to also write the index in, requiring an `out_dir`.
+    pub formats: Vec<Format>,
 }
 
 pub fn build_index_page(repos: Vec<PathBuf>, options: IndexOptions) -> anyhow::Result<()> {
     info!(num_repos = repos.len(), ?options, "building index page");
+    let repos = without_hidden(repos);
     let index_meta = Meta {
         description: String::new(),
         urls: Vec::new(),
         name: "Repositories".to_owned(),
         owner: String::new(),
         pages: None,
+        domain: None,
         readme: None,
         license: None,
+        changelog: None,
+        contributing: None,
+        mailinglist: None,
         mod_time: UNIX_EPOCH,
+        log_length: None,
+        feed_length: None,
+        category: None,
+        head: None,
     };
 
     let repos_url = options.repos_url.map_or_else(Default::default, |u| {
@@ -261,25 +751,127 @@ pub fn build_index_page(repos: Vec<PathBuf>, options: IndexOptions) -> anyhow::R
     });
     let pages_url = options.pages_url.as_deref();
 
+    let columns = if options.columns.is_empty() {
+        &DEFAULT_INDEX_COLUMNS[..]
+    } else {
+        &options.columns[..]
+    };
+
     let mut table = Table::new()
         .with_attributes([("id", "index")])
-        .with_header_row([
-            "Name",
-            "Description",
-            "Owner",
-            "Last commit",
-            if pages_url.is_some() { "Pages URL" } else { "" },
-        ]);
-    for repo_path in repos {
-        if let Err(error) = add_row_for_repo_index(&repo_path, &repos_url, pages_url, &mut table) {
-            warn!(?repo_path, %error, "Failed to add index row for repo");
+        .with_header_row(columns.iter().map(|column| match column {
+            IndexColumn::Pages if pages_url.is_none() => "",
+            column => column.header(),
+        }));
+    let rows = map_parallel(&repos, options.jobs, |repo_path| {
+        index_row_for_repo(
+            repo_path,
+            &repos_url,
+            pages_url,
+            columns,
+            options.page.date_format,
+            options.page.time_zone,
+        )
+    });
+    let mut rows = repos
+        .iter()
+        .zip(rows)
+        .filter_map(|(repo_path, row)| {
+            row.inspect_err(|error| warn!(?repo_path, %error, "Failed to add index row for repo"))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    sort_index_rows(&mut rows, options.sort, options.reverse);
+    // uncategorised repos first, then each category in order, keeping the order of the repos
+    let mut sections: BTreeMap<Option<String>, Vec<Vec<Escaped>>> = BTreeMap::new();
+    for row in rows {
+        sections.entry(row.category).or_default().push(row.cells);
+    }
+    for (category, rows) in sections {
+        if let Some(category) = category {
+            table.add_custom_body_row(
+                TableRow::new().with_cell(
+                    TableCell::new(TableCellType::Header)
+                        .with_attributes([
+                            ("colspan", columns.len().to_string().as_str()),
+                            ("class", "category"),
+                        ])
+                        .with_raw(Escaped::new(&category)),
+                ),
+            );
+        }
+        for cells in rows {
+            table.add_custom_body_row(row(&cells));
         }
     }
     let container = Container::new(build_html::ContainerType::Div).with_table(table);
+    let index_location = PageLocation {
+        path: Path::new("index.html"),
+        to_index_root: "",
+        to_repo_root: "",
+    };
 
     if let Some(out_dir) = options.out_dir {
+        if !options.formats.is_empty() {
+            let summaries = repos
+                .iter()
+                .filter_map(|repo_path| {
+                    let summary = (|| {
+                        let repo = gix::open(repo_path)?;
+                        let meta = Meta::load(&repo, repo_path, &[], None)?;
+                        collect_summary(&repo, &meta)
+                    })();
+                    summary
+                        .inspect_err(|error| warn!(?repo_path, %error, "Failed to summarise repo"))
+                        .ok()
+                })
+                .collect::<Vec<_>>();
+            let mut sink = Output::Dir(out_dir.clone()).open()?;
+            for format in &options.formats {
+                format
+                    .write_index(&summaries, &repos_url, sink.as_mut())
+                    .with_context(|| format!("write index {format:?}"))?;
+            }
+        }
         let mut out = File::create(out_dir.join("index.html"))?;
-        index_meta.write_html_content("Index", "", "", container, false, &mut out)?;
+        index_meta.write_html_content(
+            "Index",
+            &index_location,
+            container,
+            false,
+            &options.page,
+            &mut out,
+        )?;
+        // served at the depth of whichever path was missing, so only with absolute links
+        if let Some(index_url) = options.page.url_for(Path::new("")) {
+            let mut out = File::create(out_dir.join(NOT_FOUND_FILE))?;
+            let location = PageLocation {
+                path: Path::new(NOT_FOUND_FILE),
+                to_index_root: &index_url,
+                to_repo_root: &index_url,
+            };
+            index_meta.write_html_content(
+                "Not found",
+                &location,
+                not_found_container(),
+                false,
+                &options.page,
+                &mut out,
+            )?;
+        }
+        // the built-in assets fill in for any not given, so the pages never link to missing ones
+        let theme = options.theme.unwrap_or_default();
+        let given = [&options.stylesheet, &options.logo, &options.favicon];
+        for ((name, data), given) in theme.assets().into_iter().zip(given) {
+            if given.is_some() {
+                continue;
+            }
+            let out_asset = out_dir.join(name);
+            if out_asset.symlink_metadata().is_ok() {
+                remove_file(&out_asset)?;
+            }
+            std::fs::write(&out_asset, data).with_context(|| format!("write {name}"))?;
+        }
         if let Some(stylesheet) = options.stylesheet {
             let out_style = out_dir.join("style.css");
             if out_style.exists() {
@@ -303,7 +895,14 @@ pub fn build_index_page(repos: Vec<PathBuf>, options: IndexOptions) -> anyhow::R
         }
     } else {
         let mut out = std::io::stdout();
-        index_meta.write_html_content("Index", "", "", container, false, &mut out)?;
+        index_meta.write_html_content(
+            "Index",
+            &index_location,
+            container,
+            false,
+            &options.page,
+            &mut out,
+        )?;
     };
 
     Ok(())
@@ -314,10 +913,16 @@ pub struct PagesOptions {
     pub out_dir: PathBuf,
     pub working_dir: PathBuf,
     pub index: Option<IndexOptions>,
+    /// Number of repos to copy concurrently, each into its own directory under `working_dir`.
+    pub jobs: usize,
+    /// Command and arguments wrapping each repo's `pages_command`, such as `bwrap` or `firejail`,
+    /// the pages command being run directly if empty.
+    pub sandbox: Vec<String>,
 }
 
 pub fn build_pages_dirs(repos: Vec<PathBuf>, options: PagesOptions) -> anyhow::Result<()> {
     info!(num_repos = repos.len(), ?options, "building pages dir");
+    let repos = without_hidden(repos);
 
     if !options.out_dir.exists() {
         create_dir_all(&options.out_dir)?;
@@ -327,20 +932,28 @@ pub fn build_pages_dirs(repos: Vec<PathBuf>, options: PagesOptions) -> anyhow::R
         create_dir_all(&options.working_dir)?;
     }
     let working_dir = options.working_dir.canonicalize()?;
+    // the directories the repos' pages are published to without a custom domain
+    let repo_names = repos
+        .iter()
+        .filter_map(|repo_path| repo_path.file_stem())
+        .collect::<HashSet<_>>();
 
-    for repo_path in &repos {
-        if options.working_dir.exists() {
-            remove_dir_all(&working_dir)?;
-        }
-        create_dir_all(&working_dir)?;
+    let results = map_parallel(&repos, options.jobs, |repo_path| {
         let abs_repo_path = repo_path.canonicalize()?;
         let clone_base_urls = &[];
-        if let Err(error) =
-            copy_docs_to_out_dir(&abs_repo_path, &out_dir, &working_dir, clone_base_urls)
-        {
+        if let Err(error) = copy_docs_to_out_dir(
+            &abs_repo_path,
+            &out_dir,
+            &working_dir,
+            &repo_names,
+            clone_base_urls,
+            &options.sandbox,
+        ) {
             warn!(?repo_path, ?out_dir, %error, "Failed to copy docs to out_dir");
         }
-    }
+        anyhow::Ok(())
+    });
+    results.into_iter().collect::<anyhow::Result<()>>()?;
 
     if let Some(index) = options.index {
         build_index_page(repos, index)?;
@@ -349,24 +962,59 @@ pub fn build_pages_dirs(repos: Vec<PathBuf>, options: PagesOptions) -> anyhow::R
     Ok(())
 }
 
+/// The repos that aren't hidden, keeping any that can't be checked for the build to report.
+fn without_hidden(repos: Vec<PathBuf>) -> Vec<PathBuf> {
+    repos
+        .into_iter()
+        .filter(|repo_path| match is_hidden(repo_path) {
+            Ok(hidden) => {
+                if hidden {
+                    info!(?repo_path, "Skipping hidden repo");
+                }
+                !hidden
+            }
+            Err(error) => {
+                warn!(?repo_path, %error, "Failed to check if repo is hidden");
+                true
+            }
+        })
+        .collect()
+}
+
 fn copy_docs_to_out_dir(
     repo_path: &Path,
     out_dir: &Path,
     working_dir: &Path,
+    repo_names: &HashSet<&OsStr>,
     clone_base_urls: &[String],
+    sandbox: &[String],
 ) -> anyhow::Result<()> {
     debug!(?repo_path, ?out_dir, "Copying docs to out dir");
     let repo = gix::open(repo_path)?;
-    let head = repo.head_commit()?;
-    let meta = Meta::load(&repo, repo_path, clone_base_urls)?;
+    let meta = Meta::load(&repo, repo_path, clone_base_urls, None)?;
 
     let Some(repo_name) = repo_path.file_stem() else {
         anyhow::bail!("no repo name found")
     };
 
-    let Some(docs_dir) = meta.pages else {
+    let Some(pages) = &meta.pages else {
         return Ok(());
     };
+    let (pages_ref, docs_dir) = split_pages_ref(pages);
+    let head = match pages_ref {
+        Some(pages_ref) => repo
+            .rev_parse_single(pages_ref)
+            .with_context(|| format!("find pages ref {pages_ref}"))?
+            .object()?
+            .peel_to_commit()?,
+        None => {
+            let Some(head) = meta.head else {
+                debug!(?repo_path, "Empty repo has no docs to copy");
+                return Ok(());
+            };
+            repo.find_commit(head)?
+        }
+    };
 
     let docs_dir = if docs_dir.is_empty() {
         PathBuf::new()
@@ -378,59 +1026,93 @@ fn copy_docs_to_out_dir(
 
     let root_tree = find_root_of_docs_dir(docs_dir_parts, head.tree()?)?;
 
-    copy_tree_to_dir(root_tree, working_dir)?;
-
-    let repo_out_dir = out_dir.join(repo_name);
-    create_dir_all(&repo_out_dir)?;
-    debug!(
-        ?working_dir,
-        ?repo_out_dir,
-        "Moving working dir to repo out dir"
-    );
-    let working_dir_fd = open(
-        working_dir,
-        OFlag::O_DIRECTORY | OFlag::O_PATH,
-        Mode::S_IWUSR | Mode::S_IWGRP,
-    )?;
-    let repo_out_dir_fd = open(
-        &repo_out_dir,
-        OFlag::O_DIRECTORY | OFlag::O_PATH,
-        Mode::S_IWUSR | Mode::S_IWGRP,
-    )?;
-    // swap the directories atomically
-    renameat2(
-        working_dir_fd,
-        working_dir,
-        repo_out_dir_fd,
-        &repo_out_dir,
-        RenameFlags::RENAME_EXCHANGE,
-    )?;
-    // clean up the working dir
-    remove_dir_all(working_dir)?;
+    // kept between runs, holding the manifest of what was last copied
+    let repo_working_dir = working_dir.join(working_dir_name(repo_path, repo_name)?);
+    create_dir_all(&repo_working_dir)?;
+    let manifest_path = repo_working_dir.join(PAGES_MANIFEST_FILE);
+    let repo_out_dir = match &meta.domain {
+        Some(domain) => {
+            if domain.contains('/') || domain.starts_with('.') {
+                anyhow::bail!("invalid domain {domain:?}");
+            }
+            if OsStr::new(domain) != repo_name && repo_names.contains(OsStr::new(domain)) {
+                anyhow::bail!("domain {domain:?} is the name of another repo");
+            }
+            out_dir.join(domain)
+        }
+        None => out_dir.join(repo_name),
+    };
+    let previous = PagesManifest::load(&manifest_path);
+    // the pages have moved, to or from a custom domain
+    if let Some(previous) = &previous
+        && previous.out_dir != repo_out_dir
+        && previous.out_dir.starts_with(out_dir)
+        && previous.out_dir.exists()
+    {
+        debug!(from = ?previous.out_dir, to = ?repo_out_dir, "Removing moved pages");
+        remove_dir_all(&previous.out_dir)?;
+    }
 
-    Ok(())
-}
+    let config = RepoConfig::load(repo.path())?;
+    if let Some(command) = &config.pages_command {
+        // built from a fresh copy each time, the site it outputs being swapped in whole
+        let build_dir = repo_working_dir.join("build");
+        sync_tree_to_dir(&root_tree, &build_dir, None)?;
+        run_pages_command(command, sandbox, &build_dir)?;
+        let site_dir = built_site_dir(
+            &build_dir,
+            config.pages_output.as_deref().unwrap_or("public"),
+        )?;
+        create_dir_all(&repo_out_dir)?;
+        debug!(
+            ?site_dir,
+            ?repo_out_dir,
+            "Moving built site to repo out dir"
+        );
+        swap_dirs(&site_dir, &repo_out_dir)?;
+        remove_dir_all(&build_dir)?;
+        // only the directory is kept, what was published not being a copy of the tree
+        PagesManifest::default()
+            .with_out_dir(repo_out_dir.clone())
+            .save(&manifest_path)?;
+    } else {
+        // synced into a staging copy that is swapped in whole, so the site is never seen half
+        // updated, after which the staging copy holds what was published before
+        let staging_dir = repo_working_dir.join("staging");
+        let staging_manifest_path = repo_working_dir.join(STAGING_MANIFEST_FILE);
+        let staged = PagesManifest::load(&staging_manifest_path).filter(|staged| {
+            staged.out_dir == staging_dir && staging_dir.exists() && !staged.is_empty()
+        });
+        // only trusted while the files it lists are still there, and not for a site built by a
+        // pages command, which lists none
+        let previous = previous.filter(|previous| {
+            previous.out_dir == repo_out_dir && repo_out_dir.exists() && !previous.is_empty()
+        });
+        // neither copy is known until the swap is done, should the run stop part way
+        PagesManifest::remove(&staging_manifest_path)?;
+        PagesManifest::remove(&manifest_path)?;
+        let manifest = sync_tree_to_dir(&root_tree, &staging_dir, staged.as_ref())?;
+        create_dir_all(&repo_out_dir)?;
+        debug!(
+            ?staging_dir,
+            ?repo_out_dir,
+            "Swapping staged docs into repo out dir"
+        );
+        swap_dirs(&staging_dir, &repo_out_dir)?;
+        manifest
+            .with_out_dir(repo_out_dir.clone())
+            .save(&manifest_path)?;
+        previous
+            .unwrap_or_default()
+            .with_out_dir(staging_dir)
+            .save(&staging_manifest_path)?;
+    }
 
-fn copy_tree_to_dir(tree: Tree<'_>, working_dir: &Path) -> anyhow::Result<()> {
-    debug!(?working_dir, "Copying tree to temporary dir");
-    for entry in tree.iter() {
-        let entry = entry?;
-        let filename = entry.filename();
-        if entry.mode().is_blob() {
-            let blob = entry.object()?.into_blob();
-            let file_path = working_dir.join(filename.to_str()?);
-            std::fs::write(file_path, &blob.data)?;
-        } else if entry.mode().is_tree() {
-            let tree = entry.object()?.peel_to_tree()?;
-            let dir_path = working_dir.join(filename.to_str()?);
-            create_dir(&dir_path)?;
-            copy_tree_to_dir(tree, &dir_path)?;
-        } else {
-            warn!(
-                ?working_dir,
-                ?filename,
-                "unmatched mode in copy_tree_to_dir, not copying it"
-            );
+    if let Some(domain) = &meta.domain {
+        std::fs::write(repo_out_dir.join("CNAME"), format!("{domain}\n"))?;
+        // unless the domain is the repo's name, the pages then being where the stub would go
+        if repo_out_dir != out_dir.join(repo_name) {
+            write_redirect_stub(&out_dir.join(repo_name), &format!("https://{domain}/"))?;
         }
     }
 
@@ -463,54 +1145,179 @@ fn find_root_of_docs_dir<'a, 'repo>(
     Err(anyhow::anyhow!("root of docs dir not found"))
 }
 
-fn add_row_for_repo_index(
+fn index_row_for_repo(
     repo_path: &Path,
     repos_url: &str,
     pages_url: Option<&str>,
-    table: &mut Table,
-) -> anyhow::Result<()> {
+    columns: &[IndexColumn],
+    date_format: DateFormat,
+    time_zone: TimeZone,
+) -> anyhow::Result<IndexRow> {
     let repo = gix::open(repo_path)?;
-    let head = repo.head_commit()?;
-    let time = head.time()?.format(ISO8601)?;
     let clone_base_urls = &[];
-    let meta = Meta::load(&repo, repo_path, clone_base_urls)?;
-    let name = HtmlElement::new(build_html::HtmlTag::Link)
-        .with_attribute("href", format!("{}{}/log.html", repos_url, meta.name))
-        .with_raw(&meta.name)
-        .to_html_string();
-    let pages_url = if meta.pages.is_some()
-        && let Some(pages_url) = pages_url
-    {
-        let repo_name = repo_path.file_stem().unwrap_or_default().to_string_lossy();
-        let pages_full_url = format!("{pages_url}/{repo_name}");
-        HtmlElement::new(build_html::HtmlTag::Link)
-            .with_attribute("href", pages_full_url)
-            .with_raw(&repo_name)
-            .to_html_string()
-    } else {
-        String::new()
+    let meta = Meta::load(&repo, repo_path, clone_base_urls, None)?;
+    // empty repos are listed without any commit details
+    let time = match meta.head {
+        Some(head) => Some(repo.find_commit(head)?.time()?),
+        None => None,
     };
+    let mut row = Vec::with_capacity(columns.len());
+    for column in columns {
+        let cell = match column {
+            IndexColumn::Name => link(
+                &Escaped::new(format!("{}{}/log.html", repos_url, meta.name)),
+                &Escaped::new(&meta.name),
+            ),
+            IndexColumn::Description => Escaped::new(&meta.description),
+            IndexColumn::Owner => Escaped::new(&meta.owner),
+            IndexColumn::LastCommit => match time {
+                Some(time) => date_format.html(time, time_zone)?,
+                None => Escaped::new("empty"),
+            },
+            IndexColumn::Pages => {
+                if meta.pages.is_some()
+                    && let Some(pages_url) = pages_url
+                {
+                    let repo_name = repo_path.file_stem().unwrap_or_default().to_string_lossy();
+                    let pages_full_url = match &meta.domain {
+                        Some(domain) => format!("https://{domain}/"),
+                        None => format!("{pages_url}/{repo_name}"),
+                    };
+                    link(&Escaped::new(pages_full_url), &Escaped::new(&repo_name))
+                } else {
+                    Escaped::default()
+                }
+            }
+            IndexColumn::CloneUrl => meta.urls.first().map(Escaped::new).unwrap_or_default(),
+            IndexColumn::Idle => time
+                .map(|t| Escaped::new(index_columns::idle_time(t.seconds)))
+                .unwrap_or_default(),
+            IndexColumn::Activity => match meta.head {
+                Some(head) => index_columns::activity_sparkline(&repo, head)?,
+                None => Escaped::default(),
+            },
+        };
+        row.push(cell);
+    }
 
-    table.add_body_row([name, meta.description, meta.owner, time, pages_url]);
-    Ok(())
+    Ok(IndexRow {
+        category: meta.category,
+        name: meta.name,
+        owner: meta.owner,
+        last_commit: time.map_or(0, |t| t.seconds),
+        cells: row,
+    })
 }
 
-fn get_refs(repo: &Repository) -> anyhow::Result<Container> {
+/// The refs page of the refs [`collect_refs`] finds, linking each tag to its release notes in
+/// `releases`, to the page comparing it with the previous tag and to its snapshot, and each ref
+/// pointing at one of `paged_tips` to its commit page.
+fn get_refs(
+    build: &RepoBuild<'_>,
+    compares: &[ComparePage],
+    releases: &[ReleasePage],
+    snapshots: &[Snapshot],
+    branches: &[(String, ObjectId)],
+    paged_tips: &[ObjectId],
+) -> anyhow::Result<Container> {
+    let repo = build.repo;
+    let options = &build.options.page;
     debug!(repo=?repo.path(), "get refs");
-    let refs = repo.references()?;
+    let refs = collect_refs(repo)?;
+    let snapshot_link = |refname: &str| {
+        snapshots
+            .iter()
+            .find(|s| s.refname == refname)
+            .map(|s| {
+                let path = s.path.to_string_lossy();
+                link(
+                    &Escaped::new(&path),
+                    &Escaped::new(path.trim_start_matches("archives/")),
+                )
+            })
+            .unwrap_or_default()
+    };
+    let commit_time = |commit: &gix::Commit<'_>| -> anyhow::Result<Escaped> {
+        let time = options
+            .date_format
+            .html(commit.author()?.time()?, options.time_zone)?;
+        Ok(if paged_tips.contains(&commit.id) {
+            link(&Escaped::new(format!("commits/{}.html", commit.id)), &time)
+        } else {
+            time
+        })
+    };
     let mut container = build_html::Container::new(build_html::ContainerType::Div);
+    if snapshots.iter().any(|s| s.refname == "HEAD") {
+        container.add_html(
+            HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                .with_raw("Download HEAD: ")
+                .with_raw(snapshot_link("HEAD")),
+        );
+    }
+    if let Some(compare) = compares.iter().find(|c| c.to == "HEAD") {
+        container.add_html(
+            HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                .with_raw("Unreleased changes: ")
+                .with_raw(link(
+                    &Escaped::new(compare.path().to_string_lossy()),
+                    &Escaped::new(format!("{}..HEAD", compare.from)),
+                )),
+        );
+    }
     let mut table = build_html::Table::new()
         .with_attributes([("id", "tags")])
-        .with_header_row(["Name", "Last commit time", "Author"]);
+        .with_header_row(["Name", "Last commit time", "Author", "Changes", "Download"]);
     let mut has_tags = false;
-    for tag in refs.tags()? {
-        let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
-        let commit = tag.peel_to_commit()?;
+    for tag in refs.iter().filter(|r| r.kind == RefKind::Tag) {
+        let commit = repo.find_commit(ObjectId::from_hex(tag.commit.as_bytes())?)?;
         let author = commit.author()?;
-        let tag_name = tag.name().shorten().to_str()?;
-        let name = author.name.to_str()?;
-        let time = author.time()?.format(ISO8601)?;
-        table.add_body_row([tag_name, &time, name]);
+        let tag_name = tag.name.as_str();
+        let name = Escaped::new(author.name.to_str_lossy());
+        let time = commit_time(&commit)?;
+        let changes = compares
+            .iter()
+            .find(|c| c.to == tag_name)
+            .map(|c| {
+                link(
+                    &Escaped::new(c.path().to_string_lossy()),
+                    &Escaped::new(format!("{}..{}", c.from, c.to)),
+                )
+            })
+            .unwrap_or_default();
+        let mut tag_html = if build.options.tag_trees {
+            link(
+                &Escaped::new(format!("tags/{tag_name}/files.html")),
+                &Escaped::new(tag_name),
+            )
+        } else {
+            Escaped::new(tag_name)
+        };
+        if let Some(path) = release_path_of(releases, tag_name) {
+            tag_html.push(&Escaped::new(" ("));
+            tag_html.push(&link(
+                &Escaped::new(path.to_string_lossy()),
+                &Escaped::new("notes"),
+            ));
+            tag_html.push(&Escaped::new(")"));
+        }
+        if let Some(id) = &tag.tag
+            && let Some(signature) = tag_signature(
+                repo,
+                ObjectId::from_hex(id.as_bytes())?,
+                options.allowed_signers.as_deref(),
+            )?
+        {
+            tag_html.push(&Escaped::new(" "));
+            tag_html.push(&signature.to_html());
+        }
+        table.add_custom_body_row(row([
+            &tag_html,
+            &time,
+            &name,
+            &changes,
+            &snapshot_link(tag_name),
+        ]));
         has_tags = true;
     }
     if has_tags {
@@ -522,47 +1329,148 @@ fn get_refs(repo: &Repository) -> anyhow::Result<Container> {
     let mut table = build_html::Table::new()
         .with_attributes([("id", "branches")])
         .with_header_row(["Name", "Last commit time", "Author"]);
-    for branch in refs.local_branches()? {
-        let mut branch = branch.map_err(|e| anyhow::anyhow!(e))?;
-        let commit = branch.peel_to_commit()?;
+    for branch in refs.iter().filter(|r| r.kind == RefKind::Branch) {
+        let commit = repo.find_commit(ObjectId::from_hex(branch.commit.as_bytes())?)?;
         let author = commit.author()?;
-        let branch_name = branch.name().shorten().to_str()?;
-        let name = author.name.to_str()?;
-        let time = author.time()?.format(ISO8601)?;
-        table.add_body_row([branch_name, &time, name]);
+        let branch_name = branch.name.as_str();
+        let name = Escaped::new(author.name.to_str_lossy());
+        let time = commit_time(&commit)?;
+        let branch_html = if branches.iter().any(|(b, _)| b == branch_name) {
+            link(
+                &Escaped::new(format!("branches/{branch_name}/log.html")),
+                &Escaped::new(branch_name),
+            )
+        } else {
+            Escaped::new(branch_name)
+        };
+        table.add_custom_body_row(row([&branch_html, &time, &name]));
     }
     container.add_table(table);
     Ok(container)
 }
 
-fn get_log(repo: &Repository, log_length: Option<usize>) -> anyhow::Result<Container> {
-    debug!(repo=?repo.path(), log_length, "get log");
-    let mut container = build_html::Container::new(build_html::ContainerType::Div);
-    let mut table = build_html::Table::new()
-        .with_attributes([("id", "log")])
-        .with_header_row(["Time", "Commit message", "Author", "Files", "+", "-", "ID"]);
-    let head = repo.head()?;
-    let mut revs = repo
-        .rev_walk([head.id().unwrap()])
-        .first_parent_only()
-        .all()?
-        .enumerate();
-    for (i, rev) in &mut revs {
-        if let Some(log_len) = log_length {
-            if i >= log_len {
-                break;
-            }
-        }
-        let rev = rev?;
+/// Names of the local branches and tags pointing at each commit, like `git log --decorate`.
+fn get_decorations(repo: &Repository) -> anyhow::Result<HashMap<ObjectId, Vec<String>>> {
+    let mut decorations: HashMap<ObjectId, Vec<String>> = HashMap::new();
+    let refs = repo.references()?;
+    for branch in refs.local_branches()? {
+        let mut branch = branch.map_err(|e| anyhow::anyhow!(e))?;
+        let id = branch.peel_to_commit()?.id;
+        let name = branch.name().shorten().to_string();
+        decorations.entry(id).or_default().push(name);
+    }
+    for tag in refs.tags()? {
+        let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+        // tags of anything but commits don't decorate the history
+        let Ok(commit) = tag.peel_to_commit() else {
+            continue;
+        };
+        let name = format!("tag: {}", tag.name().shorten());
+        decorations.entry(commit.id).or_default().push(name);
+    }
+    Ok(decorations)
+}
+
+/// The refs pointing at a commit, for next to its title.
+fn decoration_html(names: &[String]) -> Escaped {
+    Escaped::markup(format!(
+        "<span class=\"refs\">{}</span>",
+        Escaped::new(format!("({})", names.join(", ")))
+    ))
+}
+
+/// Path of the `page`th page of the log, counting from zero.
+fn log_page_path(page: usize) -> PathBuf {
+    if page == 0 {
+        PathBuf::from("log.html")
+    } else {
+        PathBuf::from("log").join(format!("{}.html", page + 1))
+    }
+}
+
+/// Redirect `log/1.html` under `dir` to the first page of a paginated log, at `log.html`, so
+/// the numbered pages start from one.
+fn write_first_log_page_redirect(dir: &Path, sink: &mut ManifestSink<'_>) -> anyhow::Result<()> {
+    sink.write(
+        &dir.join("log").join("1.html"),
+        redirect_html("../log.html").as_bytes(),
+    )
+}
+
+/// The pages of the log, split every `page_size` commits to be written to [`log_page_path`],
+/// along with per author statistics over the same commits.
+fn get_log(
+    repo: &Repository,
+    tip: ObjectId,
+    log_length: Option<usize>,
+    page_size: Option<usize>,
+    decorations: &HashMap<ObjectId, Vec<String>>,
+    options: &PageOptions,
+) -> anyhow::Result<(Vec<Container>, Authors)> {
+    let topology = options.topology;
+    debug!(repo=?repo.path(), %tip, log_length, page_size, ?topology, "get log");
+    // the hash of an email in an avatar url is easily matched against known emails
+    let avatar_url_template = options
+        .avatar_url_template
+        .as_deref()
+        .filter(|_| options.emails.shows_whole_email());
+    let mut authors = Authors::new(repo.open_mailmap(), avatar_url_template);
+    // the graph only has branches to show when merged in commits are walked too, and only
+    // follows them in the order of the history
+    let has_graph = topology == Topology::All && !options.committer_date_order;
+    let mut graph = has_graph.then(Graph::default);
+    let header = ["Time", "Commit message", "Author", "Files", "+", "-", "ID"];
+    let new_table = || {
+        let table = build_html::Table::new().with_attributes([("id", "log")]);
+        if has_graph {
+            table.with_header_row(std::iter::once("Graph").chain(header))
+        } else {
+            table.with_header_row(header)
+        }
+    };
+    let mut tables = vec![new_table()];
+    let mut walk = topology.walk(repo, tip)?;
+    let mut revs = walk
+        .by_ref()
+        .take(log_length.unwrap_or(usize::MAX))
+        .collect::<Result<Vec<_>, _>>()?;
+    // the same commits are listed either way, as they decide which have pages
+    if options.committer_date_order {
+        sort_by_committer_date(&mut revs)?;
+    }
+    let remaining = walk.count();
+    if remaining > 0 {
+        authors.truncate();
+    }
+    for (i, rev) in revs.into_iter().enumerate() {
+        let page = page_size.map_or(0, |size| i / size.max(1));
+        if page == tables.len() {
+            tables.push(new_table());
+        }
+        let to_repo_root = if page == 0 { "" } else { "../" };
         let id = rev.id().to_string();
         let commit = rev.object()?;
-        let message = commit.message()?.title.trim().to_str()?.to_owned();
-        let message_html = HtmlElement::new(build_html::HtmlTag::Div)
-            .with_link_attr::<_, String>(format!("commits/{}.html", id), message, [])
-            .to_html_string();
+        let message = Escaped::new(commit.message()?.title.trim().to_str()?);
+        let mut message_html = link(
+            &Escaped::new(format!("{}commits/{}.html", to_repo_root, id)),
+            &message,
+        );
+        if let Some(names) = decorations.get(&rev.id) {
+            message_html.push(&Escaped::new(" "));
+            message_html.push(&decoration_html(names));
+        }
         let author = commit.author()?;
-        let name = author.name.to_string();
-        let time = author.time()?.format(ISO8601)?;
+        let mut name = Escaped::default();
+        if let Some(template) = avatar_url_template {
+            name.push(&avatar_html(template, &author.email.to_string()));
+        }
+        name.push(&Escaped::new(author.name));
+        let time = if options.show_committer_dates {
+            commit.committer()?.time()?
+        } else {
+            author.time()?
+        };
+        let time = options.date_format.html(time, options.time_zone)?;
         let tree = commit.tree()?;
         let mut ancestors = commit.ancestors().first_parent_only().all()?;
         let ancestor_tree = if let Some(ancestor) = ancestors.nth(1) {
@@ -573,15 +1481,34 @@ fn get_log(repo: &Repository, log_length: Option<usize>) -> anyhow::Result<Conta
             repo.empty_tree()
         };
         let stats = ancestor_tree.changes()?.stats(&tree)?;
+        authors.record(
+            author,
+            stats.files_changed as usize,
+            stats.lines_added as usize,
+            stats.lines_removed as usize,
+        )?;
+        if let Some(body) = commit.message()?.body() {
+            for co_author in co_authors(body.trailers(), author) {
+                authors.record_co_author(co_author)?;
+            }
+        }
         let changed = stats.files_changed.to_string();
         let added = format!("+{}", stats.lines_added);
         let removed = format!("-{}", stats.lines_removed);
 
-        table.add_custom_body_row(
-            TableRow::new()
-                .with_cell(TableCell::default().with_raw(time))
-                .with_cell(TableCell::default().with_raw(message_html))
-                .with_cell(TableCell::default().with_raw(name))
+        let mut row = TableRow::new();
+        if let Some(graph) = &mut graph {
+            let parents = commit.parent_ids().map(|p| p.detach()).collect::<Vec<_>>();
+            row.add_cell(
+                TableCell::default()
+                    .with_attributes([("class", "graph")])
+                    .with_raw(Escaped::new(graph.row(rev.id, &parents))),
+            );
+        }
+        tables[page].add_custom_body_row(
+            row.with_cell(cell(&time))
+                .with_cell(cell(&message_html))
+                .with_cell(cell(&name))
                 .with_cell(
                     TableCell::default()
                         .with_attributes([("class", "num")])
@@ -600,137 +1527,692 @@ fn get_log(repo: &Repository, log_length: Option<usize>) -> anyhow::Result<Conta
                 .with_cell(TableCell::default().with_raw(id)),
         );
     }
-    let remaining = revs.count();
-    if remaining > 0 {
-        table.add_body_row([
-            "...",
-            &format!("{} more commits remaining, fetch the repository", remaining),
-            "...",
-            "...",
-            "...",
-            "...",
-            "...",
-        ]);
+    if remaining > 0
+        && let Some(table) = tables.last_mut()
+    {
+        let message = format!("{} more commits remaining, fetch the repository", remaining);
+        let mut row = vec!["...", &message, "...", "...", "...", "...", "..."];
+        if has_graph {
+            row.insert(0, "");
+        }
+        table.add_body_row(row);
     }
-    container.add_table(table);
-    Ok(container)
+
+    let num_pages = tables.len();
+    let mut pages = Vec::with_capacity(num_pages);
+    for (page, table) in tables.into_iter().enumerate() {
+        let mut container = build_html::Container::new(build_html::ContainerType::Div);
+        container.add_table(table);
+        if num_pages > 1 {
+            let to_repo_root = if page == 0 { "" } else { "../" };
+            let page_link = |page: usize, label: &str| {
+                HtmlElement::new(build_html::HtmlTag::Link)
+                    .with_attribute(
+                        "href",
+                        format!("{}{}", to_repo_root, log_page_path(page).display()),
+                    )
+                    .with_raw(label)
+                    .to_html_string()
+            };
+            let mut links = Vec::new();
+            if page > 0 {
+                links.push(page_link(page - 1, "Newer"));
+            }
+            links.push(format!("Page {} of {}", page + 1, num_pages));
+            if page + 1 < num_pages {
+                links.push(page_link(page + 1, "Older"));
+            }
+            container.add_html(
+                HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                    .with_attribute("class", "pages")
+                    .with_raw(links.join(" | ")),
+            );
+        }
+        pages.push(container);
+    }
+    Ok((pages, authors))
+}
+
+struct CommitPage {
+    id: String,
+    title: String,
+    container: Container,
+    /// Plain unified diff of the commit against its first parent.
+    diff: String,
+    /// The commit as an email for `git am`, if it can be applied as one.
+    patch: Option<String>,
+    /// Whether the page stays the same as more commits are added, which isn't the case for
+    /// commits at the end of a truncated log as their parents aren't linked.
+    cacheable: bool,
+    /// Paths of the files pages the diff links to.
+    linked_files: BTreeSet<String>,
 }
 
+/// Pages for the commits in the log of each of `tips`, skipping those already in the cache for
+/// `commits_dir` and those shared with an earlier tip.
+///
+/// Diffs link to the files of the first tip.
 fn get_commits(
-    repo: &Repository,
-    log_length: Option<usize>,
-) -> anyhow::Result<Vec<(String, String, Container)>> {
-    debug!(repo=?repo.path(), log_length, "get commits");
+    build: &RepoBuild<'_>,
+    tips: &[ObjectId],
+    cache: &CommitCache,
+    commits_dir: &Path,
+    options: &PageOptions,
+) -> anyhow::Result<Vec<CommitPage>> {
+    let RepoBuild {
+        repo,
+        log_length,
+        ref decorations,
+        ..
+    } = *build;
+    let mailinglist = build.meta.mailinglist.as_deref().map(MailingList::new);
+    debug!(repo=?repo.path(), ?tips, log_length, "get commits");
     let mut containers = Vec::new();
-    let head = repo.head()?;
-    let revs = repo
-        .rev_walk([head.id().unwrap()])
-        .first_parent_only()
-        .all()?;
-    for (i, rev) in revs.enumerate() {
-        if let Some(log_len) = log_length {
-            if i >= log_len {
-                break;
+    let Some(&tip) = tips.first() else {
+        return Ok(containers);
+    };
+    let tip_files = file_page_paths(&repo.find_commit(tip)?.tree()?)?;
+    let mut revs = Vec::new();
+    // parents outside of the walked commits have no page to link to
+    let mut paged = HashSet::new();
+    let mut walked = Progress::new("walked", "commits", None);
+    for &tip in tips {
+        for rev in options
+            .topology
+            .walk(repo, tip)?
+            .take(log_length.unwrap_or(usize::MAX))
+        {
+            let rev = rev?;
+            if paged.insert(rev.id) {
+                revs.push(rev);
+                walked.inc();
             }
         }
-        let rev = rev?;
-        let mut container = build_html::Container::new(build_html::ContainerType::Div)
-            .with_attributes([("id", "content")]);
-        let mut pre = HtmlElement::new(build_html::HtmlTag::Div);
+    }
+    let uncached = revs
+        .iter()
+        .filter(|rev| !cache.contains(commits_dir, &rev.id.to_string(), &tip_files))
+        .count();
+    let mut rendered = Progress::new("rendered", "commits", Some(uncached));
+    for rev in revs {
+        if cache.contains(commits_dir, &rev.id.to_string(), &tip_files) {
+            continue;
+        }
+        let commit = rev.object()?;
+        let parent_ids = commit.parent_ids().map(|p| p.detach()).collect::<Vec<_>>();
 
-        pre.add_html(Bold::from("commit "));
-        pre.add_link(
-            format!("../commits/{}.html", rev.id),
-            format!("{}\n", rev.id),
-        );
+        let mut diff_container = Container::new(build_html::ContainerType::Div);
+        let tree = commit.tree()?;
+        let mut ancestors = commit.ancestors().first_parent_only().all()?;
+        let ancestor = ancestors.nth(1);
+        let ancestor_tree = if let Some(ancestor) = ancestor {
+            let commit2 = ancestor?.object()?;
 
-        let commit = rev.object()?;
-        let parent_revs = commit.parent_ids().map(|p| p.to_string());
+            commit2.tree()?
+        } else {
+            repo.empty_tree()
+        };
 
-        pre.add_html(Bold::from("parents "));
-        for (j, parent_rev) in parent_revs.enumerate() {
-            if j == 0 && Some(i + 1) != log_length {
-                pre.add_link(format!("../commits/{}.html", parent_rev), parent_rev);
-            } else {
-                pre.add_child(parent_rev.into());
+        let mut links = FileLinks::new(&tip_files, "../");
+        let merge_diff = if parent_ids.len() > 1 {
+            options.merge_diff
+        } else {
+            MergeDiff::FirstParent
+        };
+        let raw_diff = match merge_diff {
+            MergeDiff::FirstParent => add_tree_diff(
+                &mut diff_container,
+                repo,
+                &ancestor_tree,
+                &tree,
+                &mut links,
+                false,
+                options,
+            )?,
+            MergeDiff::PerParent => {
+                for parent_id in &parent_ids {
+                    diff_container.add_header(3, format!("Changes from {}", parent_id));
+                    add_tree_diff(
+                        &mut diff_container,
+                        repo,
+                        &repo.find_commit(*parent_id)?.tree()?,
+                        &tree,
+                        &mut links,
+                        true,
+                        options,
+                    )?;
+                }
+                add_tree_diff(
+                    &mut Container::new(build_html::ContainerType::Div),
+                    repo,
+                    &ancestor_tree,
+                    &tree,
+                    &mut links,
+                    true,
+                    options,
+                )?
+            }
+            MergeDiff::Collapse => {
+                diff_container.add_paragraph(format!(
+                    "Merge of {} parents, see the raw diff against the first parent.",
+                    parent_ids.len()
+                ));
+                add_tree_diff(
+                    &mut Container::new(build_html::ContainerType::Div),
+                    repo,
+                    &ancestor_tree,
+                    &tree,
+                    &mut links,
+                    true,
+                    options,
+                )?
             }
+        };
+        // like `git format-patch`, merges have no patch, nor do binary changes that a plain
+        // diff can't apply
+        let patch = if parent_ids.len() > 1 || has_binary_changes(&raw_diff) {
+            None
+        } else {
+            Some(format_patch(&commit, &raw_diff, options.emails)?)
+        };
+
+        let mut container = build_html::Container::new(build_html::ContainerType::Div)
+            .with_attributes([("id", "content")]);
+        let mut pre = HtmlElement::new(build_html::HtmlTag::Div);
+
+        pre.add_html(Bold::from("commit "));
+        pre.add_raw(link(
+            &Escaped::new(format!("../commits/{}.html", rev.id)),
+            &Escaped::new(rev.id),
+        ));
+        pre.add_child(" (".into());
+        pre.add_raw(link(
+            &Escaped::new(format!("../commits/{}.diff", rev.id)),
+            &Escaped::new("diff"),
+        ));
+        if patch.is_some() {
+            pre.add_child(", ".into());
+            pre.add_raw(link(
+                &Escaped::new(format!("../commits/{}.patch", rev.id)),
+                &Escaped::new("patch"),
+            ));
+        }
+        pre.add_child(")".into());
+        if let Some(names) = decorations.get(&rev.id) {
+            pre.add_child(" ".into());
+            pre.add_raw(decoration_html(names));
         }
         pre.add_child("\n".into());
 
+        for parent_id in &parent_ids {
+            pre.add_html(Bold::from("parent "));
+            if paged.contains(parent_id) {
+                pre.add_raw(link(
+                    &Escaped::new(format!("../commits/{}.html", parent_id)),
+                    &Escaped::new(parent_id),
+                ));
+            } else {
+                pre.add_raw(Escaped::new(parent_id));
+            }
+            pre.add_child("\n".into());
+        }
+
         let author = commit.author()?;
 
         pre.add_html(Bold::from("author "));
-        pre.add_child(escape_html(&format!("{} <{}>\n", author.name, author.email)).into());
+        pre.add_raw(
+            options
+                .emails
+                .html_ident(&author.name.to_string(), &author.email.to_string()),
+        );
+        pre.add_child("\n".into());
 
         pre.add_html(Bold::from("date "));
-        pre.add_child(author.time()?.format(ISO8601)?.into());
+        pre.add_child(
+            options
+                .time_zone
+                .apply(author.time()?)
+                .format(ISO8601)?
+                .into(),
+        );
         pre.add_child("\n".into());
 
+        // rebased and applied commits keep their author, with whoever rewrote them as committer
+        let committer = commit.committer()?;
+        if (committer.name, committer.email) != (author.name, author.email) {
+            pre.add_html(Bold::from("committer "));
+            pre.add_raw(
+                options
+                    .emails
+                    .html_ident(&committer.name.to_string(), &committer.email.to_string()),
+            );
+            pre.add_child("\n".into());
+        }
+        if committer.time()?.seconds != author.time()?.seconds {
+            pre.add_html(Bold::from("commit date "));
+            pre.add_child(
+                options
+                    .time_zone
+                    .apply(committer.time()?)
+                    .format(ISO8601)?
+                    .into(),
+            );
+            pre.add_child("\n".into());
+        }
+
+        if let Some(signature) = commit_signature(&commit, options.allowed_signers.as_deref())? {
+            pre.add_html(Bold::from("signature "));
+            pre.add_raw(signature.to_html());
+            pre.add_child("\n".into());
+        }
+
         let message = commit.message()?;
 
+        // an archive can't be replied to
+        if let Some(MailingList::Address(address)) = mailinglist {
+            let href = reply_href(
+                address,
+                &commit.id.to_string(),
+                &message.title.to_str_lossy(),
+            );
+            pre.add_html(Bold::from("reply "));
+            pre.add_raw(link(&Escaped::new(href), &Escaped::new("mail the list")));
+            pre.add_raw(Escaped::new(format!(" or git send-email --to={address}\n")));
+        }
+
         container.add_preformatted(pre);
-        container.add_paragraph(message.title);
-        container.add_paragraph(message.body.map_or(String::new(), |s| s.to_string()));
+        container.add_paragraph(Escaped::new(message.title));
+        let body = message.body();
+        container.add_paragraph(Escaped::new(
+            body.map_or(String::new(), |body| body.without_trailer().to_string()),
+        ));
+        if let Some(trailers) =
+            body.and_then(|body| trailers_table(body.trailers(), options.emails))
+        {
+            container.add_table(trailers);
+        }
+        container.add_container(diff_container);
 
-        let tree = commit.tree()?;
-        let mut ancestors = commit.ancestors().first_parent_only().all()?;
-        let ancestor = ancestors.nth(1);
-        let ancestor_tree = if let Some(ancestor) = ancestor {
-            let commit2 = ancestor?.object()?;
+        containers.push(CommitPage {
+            id: commit.id.to_string(),
+            title: message.title.to_string(),
+            container,
+            patch,
+            diff: raw_diff,
+            // decorated commits are rendered again in case their refs move away
+            cacheable: parent_ids.iter().all(|id| paged.contains(id))
+                && !decorations.contains_key(&commit.id),
+            linked_files: links.linked,
+        });
+        rendered.inc();
+    }
+    Ok(containers)
+}
 
-            commit2.tree()?
+/// Every commit in the log of each of `tips`, which are those given pages by [`get_commits`].
+fn paged_commits(
+    repo: &Repository,
+    tips: &[ObjectId],
+    log_length: Option<usize>,
+    topology: Topology,
+) -> anyhow::Result<HashSet<ObjectId>> {
+    let mut paged = HashSet::new();
+    for &tip in tips {
+        for rev in topology
+            .walk(repo, tip)?
+            .take(log_length.unwrap_or(usize::MAX))
+        {
+            paged.insert(rev?.id);
+        }
+    }
+    Ok(paged)
+}
+
+struct ComparePage {
+    from: String,
+    to: String,
+    container: Container,
+    /// Plain unified diff between the two tags.
+    diff: String,
+}
+
+impl ComparePage {
+    fn path(&self) -> PathBuf {
+        compare_path(&self.from, &self.to)
+    }
+}
+
+/// Path of the page comparing the refs `from` and `to`, nested deeper for refs with a `/`.
+fn compare_path(from: &str, to: &str) -> PathBuf {
+    PathBuf::from("compare").join(format!("{from}..{to}.html"))
+}
+
+/// A page per pair of consecutive tags, ordered by the time of the commit they point to, and from
+/// the latest tag to `HEAD` if `compare_head`, with the commits and diff between them.
+///
+/// Commits in `paged` link to their pages.
+fn get_compares(
+    repo: &Repository,
+    head: ObjectId,
+    compare_head: bool,
+    paged: &HashSet<ObjectId>,
+    options: &PageOptions,
+) -> anyhow::Result<Vec<ComparePage>> {
+    debug!(repo=?repo.path(), "get compares");
+    let mut tags = Vec::new();
+    for tag in repo.references()?.tags()? {
+        let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+        // tags of trees and blobs have no history to compare
+        let Ok(commit) = tag.peel_to_commit() else {
+            continue;
+        };
+        let time = commit.time()?.seconds;
+        tags.push((time, tag.name().shorten().to_string(), commit));
+    }
+    tags.sort_by(|(a_time, a_name, _), (b_time, b_name, _)| {
+        a_time.cmp(b_time).then_with(|| a_name.cmp(b_name))
+    });
+
+    let head_commit = repo.find_commit(head)?;
+    let head_files = file_page_paths(&head_commit.tree()?)?;
+    let mut ranges = tags
+        .windows(2)
+        .map(|pair| {
+            let [(_, from, from_commit), (_, to, to_commit)] = pair else {
+                unreachable!("windows of 2");
+            };
+            (from, from_commit, to.as_str(), to_commit)
+        })
+        .collect::<Vec<_>>();
+    if compare_head
+        && let Some((_, latest, latest_commit)) = tags.last()
+        && latest_commit.id != head
+    {
+        ranges.push((latest, latest_commit, "HEAD", &head_commit));
+    }
+
+    let mut pages = Vec::new();
+    for (from, from_commit, to, to_commit) in ranges {
+        let to_root = to_root_path(&compare_path(from, to));
+        let mut container = build_html::Container::new(build_html::ContainerType::Div)
+            .with_attributes([("id", "content")]);
+        let mut pre = HtmlElement::new(build_html::HtmlTag::Div);
+        pre.add_html(Bold::from("from "));
+        pre.add_raw(Escaped::new(format!("{} {}\n", from, from_commit.id)));
+        pre.add_html(Bold::from("to "));
+        pre.add_raw(Escaped::new(format!("{} {}\n", to, to_commit.id)));
+        container.add_preformatted(pre);
+
+        let mut commits = Table::new()
+            .with_attributes([("id", "log")])
+            .with_header_row(["Time", "Commit message", "Author"]);
+        for rev in options
+            .topology
+            .walk_hiding(repo, to_commit.id, Some(from_commit.id))?
+        {
+            let commit = rev?.object()?;
+            let title = Escaped::new(commit.message()?.title.trim().to_str_lossy());
+            let title = if paged.contains(&commit.id) {
+                link(
+                    &Escaped::new(format!("{to_root}commits/{}.html", commit.id)),
+                    &title,
+                )
+            } else {
+                title
+            };
+            let author = commit.author()?;
+            commits.add_custom_body_row(row([
+                &Escaped::new(options.time_zone.apply(author.time()?).format(ISO8601)?),
+                &title,
+                &Escaped::new(author.name),
+            ]));
+        }
+        container.add_header(2, "Commits");
+        container.add_table(commits);
+
+        let diff = add_tree_diff(
+            &mut container,
+            repo,
+            &from_commit.tree()?,
+            &to_commit.tree()?,
+            &mut FileLinks::new(&head_files, &to_root),
+            false,
+            options,
+        )?;
+        pages.push(ComparePage {
+            from: from.clone(),
+            to: to.to_owned(),
+            container,
+            diff,
+        });
+    }
+    Ok(pages)
+}
+
+/// Whether `data` looks binary, like git containing a NUL byte near the start, or isn't UTF-8.
+fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(8000)].contains(&0) || str::from_utf8(data).is_err()
+}
+
+/// Mime type of images that browsers can show, from the extension of `path`.
+fn image_mime(path: &BStr) -> Option<&'static str> {
+    let (_, extension) = path.rsplit_once_str(".")?;
+    match extension.to_ascii_lowercase().as_slice() {
+        b"png" => Some("image/png"),
+        b"jpg" | b"jpeg" => Some("image/jpeg"),
+        b"gif" => Some("image/gif"),
+        b"webp" => Some("image/webp"),
+        b"svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// A line of a unified diff, wrapped in a span classed by whether it is a hunk header (`h`), an
+/// insertion (`i`) or a deletion (`d`).
+fn diff_line_html(line: &str, options: &PageOptions) -> Escaped {
+    if line.starts_with("@@") {
+        return Escaped::markup(format!("<span class=\"h\">{}</span>\n", Escaped::new(line)));
+    }
+    let class = match line.chars().next() {
+        Some('+') => Some("i"),
+        Some('-') => Some("d"),
+        _ => None,
+    };
+    let content = match line.char_indices().nth(1) {
+        // keep the +/-/space marker out of the trailing whitespace
+        Some((i, _)) if options.show_whitespace => {
+            let mut content = Escaped::new(&line[..i]);
+            content.push(&escape_line_marking_whitespace(&line[i..]));
+            content
+        }
+        _ => Escaped::new(line),
+    };
+    Escaped::markup(match class {
+        Some(class) => format!("<span class=\"{class}\">{content}</span>\n"),
+        None => format!("{content}\n"),
+    })
+}
+
+/// A blob or symlink changed between two trees, with its contents on either side.
+struct FileDiff<'a> {
+    change: gix::object::tree::diff::Change<'a, 'a, 'a>,
+    old_location: &'a BStr,
+    new_location: &'a BStr,
+    old_data: Vec<u8>,
+    new_data: Vec<u8>,
+    binary: bool,
+    /// Hunks of the unified diff, empty for binary files.
+    hunks: String,
+    /// Lines added and removed, if they could be counted.
+    line_counts: Option<(usize, usize)>,
+}
+
+impl FileDiff<'_> {
+    /// Status letter as in `git diff --name-status`.
+    fn marker(&self) -> &'static str {
+        match self.change {
+            gix::object::tree::diff::Change::Addition { .. } => "A",
+            gix::object::tree::diff::Change::Deletion { .. } => "D",
+            gix::object::tree::diff::Change::Modification { .. } => "M",
+            gix::object::tree::diff::Change::Rewrite { copy: false, .. } => "R",
+            gix::object::tree::diff::Change::Rewrite { copy: true, .. } => "C",
+        }
+    }
+
+    /// Similarity of a rename or copy in percent, exact renames having no line stats.
+    fn similarity(&self) -> Option<f32> {
+        match self.change {
+            gix::object::tree::diff::Change::Rewrite { diff, .. } => {
+                Some(diff.map_or(100.0, |d| d.similarity * 100.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// The mode lines of the file's part of a `git diff`, and its `index` line of the blobs
+    /// before and after when they differ.
+    fn index_header(&self) -> (String, String) {
+        use gix::object::tree::diff::Change;
+        let null = "0000000";
+        let (old, new) = match self.change {
+            Change::Addition { entry_mode, id, .. } => {
+                let mode = format!("new file mode {:o}\n", entry_mode.value());
+                let index = format!("index {null}..{}\n", id.to_hex_with_len(7));
+                return (mode, index);
+            }
+            Change::Deletion { entry_mode, id, .. } => {
+                let mode = format!("deleted file mode {:o}\n", entry_mode.value());
+                let index = format!("index {}..{null}\n", id.to_hex_with_len(7));
+                return (mode, index);
+            }
+            Change::Modification {
+                previous_entry_mode,
+                previous_id,
+                entry_mode,
+                id,
+                ..
+            } => ((previous_entry_mode, previous_id), (entry_mode, id)),
+            Change::Rewrite {
+                source_entry_mode,
+                source_id,
+                entry_mode,
+                id,
+                ..
+            } => ((source_entry_mode, source_id), (entry_mode, id)),
+        };
+        let ((old_mode, old_id), (new_mode, new_id)) = (old, new);
+        let mode = if old_mode == new_mode {
+            String::new()
         } else {
-            repo.empty_tree()
+            format!(
+                "old mode {:o}\nnew mode {:o}\n",
+                old_mode.value(),
+                new_mode.value()
+            )
+        };
+        let index = if old_id.detach() == new_id.detach() {
+            String::new()
+        } else if old_mode == new_mode {
+            format!(
+                "index {}..{} {:o}\n",
+                old_id.to_hex_with_len(7),
+                new_id.to_hex_with_len(7),
+                new_mode.value()
+            )
+        } else {
+            format!(
+                "index {}..{}\n",
+                old_id.to_hex_with_len(7),
+                new_id.to_hex_with_len(7)
+            )
         };
+        (mode, index)
+    }
 
-        let mut total_files_changed = 0;
-        let mut total_lines_added = 0;
-        let mut total_lines_removed = 0;
-        let mut diffstat_table = Table::new();
+    /// The file's part of a plain `git diff`.
+    fn raw(&self) -> String {
+        let (old_location, new_location) = (self.old_location, self.new_location);
+        let (old_path, new_path) = match self.change {
+            gix::object::tree::diff::Change::Addition { .. } => {
+                ("/dev/null".to_owned(), format!("b/{new_location}"))
+            }
+            gix::object::tree::diff::Change::Deletion { .. } => {
+                (format!("a/{old_location}"), "/dev/null".to_owned())
+            }
+            _ => (format!("a/{old_location}"), format!("b/{new_location}")),
+        };
+        let rename_header = match (self.change, self.similarity()) {
+            (gix::object::tree::diff::Change::Rewrite { copy, .. }, Some(similarity)) => {
+                let kind = if copy { "copy" } else { "rename" };
+                format!(
+                    "similarity index {similarity:.0}%\n\
+                     {kind} from {old_location}\n\
+                     {kind} to {new_location}\n"
+                )
+            }
+            _ => String::new(),
+        };
+        let (mode_header, index_header) = self.index_header();
+        let header = format!(
+            "diff --git a/{old_location} b/{new_location}\n\
+             {mode_header}{rename_header}{index_header}"
+        );
+        if self.binary {
+            format!("{header}Binary files {old_path} and {new_path} differ\n")
+        } else if self.hunks.is_empty() {
+            // only the mode or name changed
+            header
+        } else {
+            format!("{header}--- {old_path}\n+++ {new_path}\n{}", self.hunks)
+        }
+    }
+}
 
-        let mut resource_cache = repo.diff_resource_cache_for_tree_diff()?;
+/// Call `f` with each blob and symlink changed from `old_tree` to `new_tree`, following renames
+/// and copies as similar as `options` asks.
+fn for_each_file_diff(
+    repo: &Repository,
+    old_tree: &Tree<'_>,
+    new_tree: &Tree<'_>,
+    options: &PageOptions,
+    mut f: impl FnMut(FileDiff<'_>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut resource_cache = repo.diff_resource_cache_for_tree_diff()?;
+
+    let similarity = f32::from(options.rename_similarity.unwrap_or(50).min(100)) / 100.0;
+    let rewrites = gix::diff::Rewrites {
+        copies: Some(gix::diff::rewrites::Copies {
+            percentage: Some(similarity),
+            ..Default::default()
+        }),
+        percentage: Some(similarity),
+        ..Default::default()
+    };
 
-        let mut pre_diffs = Vec::new();
-        ancestor_tree.changes()?.for_each_to_obtain_tree(
-            &tree,
+    // paths that aren't UTF-8 are looked up by their bytes
+    let blob_data = |tree: &Tree<'_>, location: &BStr| -> anyhow::Result<Vec<u8>> {
+        Ok(tree
+            .lookup_entry(location.split(|&b| b == b'/'))?
+            .map_or(Vec::new(), |entry| {
+                entry
+                    .object()
+                    .unwrap()
+                    .try_into_blob()
+                    .map_or(Vec::new(), |mut b| b.take_data())
+            }))
+    };
+    old_tree
+        .changes()?
+        .options(|o| {
+            o.track_rewrites(Some(rewrites));
+        })
+        .for_each_to_obtain_tree(
+            new_tree,
             |change| -> anyhow::Result<gix::object::tree::diff::Action> {
                 if !change.entry_mode().is_blob_or_symlink() {
                     return Ok(gix::object::tree::diff::Action::Continue);
                 }
 
-                // diffstat
-                let marker = match change {
-                    gix::object::tree::diff::Change::Addition { .. } => "A",
-                    gix::object::tree::diff::Change::Deletion { .. } => "D",
-                    gix::object::tree::diff::Change::Modification { .. } => "M",
-                    gix::object::tree::diff::Change::Rewrite { .. } => "R",
-                };
-
-                let mut lines_added = 0;
-                let mut lines_removed = 0;
-
-                let mut diff = change.diff(&mut resource_cache)?;
-                if let Some(counts) = diff.line_counts()? {
-                    total_files_changed += 1;
-                    lines_added += counts.insertions as usize;
-                    lines_removed += counts.removals as usize;
-                    total_lines_added += lines_added;
-                    total_lines_removed += lines_removed;
-                }
-
-                let location = change.location().to_str()?;
-                diffstat_table.add_body_row([
-                    marker,
-                    &HtmlElement::new(build_html::HtmlTag::Link)
-                        .with_attribute("href", format!("#{}", location))
-                        .with_raw(location)
-                        .to_html_string(),
-                    "|",
-                    &format!("+{} -{}", lines_added, lines_removed),
-                    &format!("{}{}", "+".repeat(lines_added), "-".repeat(lines_removed)),
-                ]);
-
-                // unified diff
                 let (old_location, new_location) = match change {
                     gix::object::tree::diff::Change::Addition { location, .. } => {
                         (location, location)
@@ -748,162 +2230,1232 @@ fn get_commits(
                     } => (source_location, location),
                 };
 
-                let location_marker = format!("--- {}\n+++ {}\n", old_location, new_location);
-                let location_marker_html = HtmlElement::new(build_html::HtmlTag::Span)
-                    .with_attribute("id", new_location)
-                    .with_raw(location_marker)
-                    .to_html_string();
-
-                let old_string = ancestor_tree
-                    .lookup_entry_by_path(change.location().to_str()?)?
-                    .map_or(String::new(), |entry| {
-                        let blob = entry
-                            .object()
-                            .unwrap()
-                            .try_into_blob()
-                            .map_or(Vec::new(), |mut b| b.take_data());
-
-                        String::from_utf8(blob).unwrap_or_else(|_| "binary_file".to_owned())
-                    });
-                let new_string = tree
-                    .lookup_entry_by_path(change.location().to_str()?)?
-                    .map_or(String::new(), |entry| {
-                        let blob = entry
-                            .object()
-                            .unwrap()
-                            .try_into_blob()
-                            .map_or(Vec::new(), |mut b| b.take_data());
-
-                        String::from_utf8(blob).unwrap_or_else(|_| "binary_file".to_owned())
-                    });
-                let input = InternedInput::new(old_string.as_str(), new_string.as_str());
-                let consumer = ConsumeBinaryHunk::new(String::new(), "\n");
-                let udiff = UnifiedDiff::new(&input, consumer, ContextSize::symmetrical(5));
-                let diff =
-                    gix::diff::blob::diff(gix::diff::blob::Algorithm::Histogram, &input, udiff)?;
-
-                pre_diffs.push(location_marker_html + &escape_html(&diff));
+                let line_counts = change
+                    .diff(&mut resource_cache)?
+                    .line_counts()?
+                    .map(|counts| (counts.insertions as usize, counts.removals as usize));
+
+                let old_data = blob_data(old_tree, old_location)?;
+                let new_data = blob_data(new_tree, new_location)?;
+                let binary = is_binary(&old_data) || is_binary(&new_data);
+                let hunks = if binary {
+                    String::new()
+                } else {
+                    let old_string = str::from_utf8(&old_data)?;
+                    let new_string = str::from_utf8(&new_data)?;
+                    let input = InternedInput::new(old_string, new_string);
+                    let consumer = ConsumeBinaryHunk::new(String::new(), "\n");
+                    let udiff = UnifiedDiff::new(&input, consumer, ContextSize::symmetrical(5));
+                    gix::diff::blob::diff(gix::diff::blob::Algorithm::Histogram, &input, udiff)?
+                };
 
+                f(FileDiff {
+                    change,
+                    old_location,
+                    new_location,
+                    old_data,
+                    new_data,
+                    binary,
+                    hunks,
+                    line_counts,
+                })?;
                 Ok(gix::object::tree::diff::Action::Continue)
             },
         )?;
+    Ok(())
+}
 
+/// The plain unified diff between two trees, as `git diff` gives it.
+pub(crate) fn raw_tree_diff(
+    repo: &Repository,
+    old_tree: &Tree<'_>,
+    new_tree: &Tree<'_>,
+    options: &PageOptions,
+) -> anyhow::Result<String> {
+    let mut raw_diff = String::new();
+    for_each_file_diff(repo, old_tree, new_tree, options, |file| {
+        raw_diff.push_str(&file.raw());
+        Ok(())
+    })?;
+    Ok(raw_diff)
+}
+
+/// Where the paths in the file headers of a diff link to, the pages of `files` from a page
+/// `to_root` away from the root of the repo's output, along with the paths linked so far.
+struct FileLinks<'a> {
+    files: &'a HashSet<String>,
+    to_root: &'a str,
+    linked: BTreeSet<String>,
+}
+
+impl<'a> FileLinks<'a> {
+    fn new(files: &'a HashSet<String>, to_root: &'a str) -> Self {
+        Self {
+            files,
+            to_root,
+            linked: BTreeSet::new(),
+        }
+    }
+}
+
+/// Add the diffstat and unified diff between two trees to a page, linking the paths in each file
+/// header to their pages as `links` has them, returning the plain diff.
+///
+/// With `stat_only` just the diffstat is added.
+fn add_tree_diff(
+    container: &mut Container,
+    repo: &Repository,
+    old_tree: &Tree<'_>,
+    new_tree: &Tree<'_>,
+    links: &mut FileLinks<'_>,
+    stat_only: bool,
+    options: &PageOptions,
+) -> anyhow::Result<String> {
+    let mut total_files_changed = 0;
+    let mut total_lines_added = 0;
+    let mut total_lines_removed = 0;
+    let mut diffstat_table = Table::new();
+
+    // generated and vendored files only have their diff in the raw diff
+    let linguist = Linguist::load(new_tree)?;
+    let mut pre_diffs = Vec::new();
+    let mut raw_diff = String::new();
+    let mut files_shown = 0;
+    let mut files_hidden = 0;
+    let mut lines_shown = 0;
+    let mut lines_truncated = false;
+    for_each_file_diff(repo, old_tree, new_tree, options, |file| {
+        raw_diff.push_str(&file.raw());
+        let (old_location, new_location) = (file.old_location, file.new_location);
+
+        // diffstat
+        let (lines_added, lines_removed) = file.line_counts.unwrap_or_default();
+        if file.line_counts.is_some() {
+            total_files_changed += 1;
+            total_lines_added += lines_added;
+            total_lines_removed += lines_removed;
+        }
+
+        let location = new_location.to_str_lossy();
+        let mut label = match file.similarity() {
+            Some(similarity) => Escaped::new(format!(
+                "{} -> {} ({:.0}%)",
+                old_location, new_location, similarity
+            )),
+            None => Escaped::new(&location),
+        };
+        let attributes = linguist.lookup(&location);
+        let collapsed = attributes.generated || attributes.vendored;
+        if attributes.generated {
+            label.push(&Escaped::new(" (generated)"));
+        } else if attributes.vendored {
+            label.push(&Escaped::new(" (vendored)"));
+        }
+        // files past the limit only count towards the totals
+        let show_file = options.max_diff_files.is_none_or(|max| files_shown < max);
+        if show_file {
+            files_shown += 1;
+        } else {
+            files_hidden += 1;
+        }
+        if show_file {
+            diffstat_table.add_custom_body_row(row([
+                &Escaped::new(file.marker()),
+                &link(&Escaped::new(format!("#{location}")), &label),
+                &Escaped::new("|"),
+                &Escaped::new(format!("+{} -{}", lines_added, lines_removed)),
+                &Escaped::new(format!(
+                    "{}{}",
+                    "+".repeat(lines_added),
+                    "-".repeat(lines_removed)
+                )),
+            ]));
+        }
+
+        // unified diff
+        // paths that aren't UTF-8 have no page of the same name to link to
+        let mut file_link = |location: &BStr| match location.to_str() {
+            Ok(path) if links.files.contains(path) => {
+                links.linked.insert(path.to_owned());
+                link(
+                    &Escaped::new(format!("{}files/{}.html", links.to_root, url_path(path))),
+                    &Escaped::new(path),
+                )
+            }
+            _ => Escaped::new(location.to_str_lossy()),
+        };
+        let (old_link, new_link) = (file_link(old_location), file_link(new_location));
+        let mut file_html = Escaped::markup(format!(
+            "<span id=\"{}\">--- {}\n+++ {}\n</span>",
+            Escaped::new(new_location),
+            old_link,
+            new_link
+        ));
+
+        let diff_html = if !show_file || stat_only {
+            Escaped::default()
+        } else if collapsed {
+            Escaped::new("Generated or vendored file, see the raw diff.\n")
+        } else if file.binary {
+            let mut html = Escaped::new(format!(
+                "Binary files differ ({} -> {})\n",
+                format_size(file.old_data.len() as u64),
+                format_size(file.new_data.len() as u64)
+            ));
+            if options.image_diffs
+                && let Some(mime) = image_mime(new_location)
+            {
+                for (label, data) in [("Before", &file.old_data), ("After", &file.new_data)] {
+                    if data.is_empty() {
+                        continue;
+                    }
+                    html.push(&Escaped::markup(format!(
+                        "<div class=\"preview\">{label}<img src=\"data:{mime};base64,{}\" \
+                         alt=\"{label}\"></div>",
+                        BASE64_STANDARD.encode(data)
+                    )));
+                }
+            }
+            html
+        } else {
+            let max_lines = options
+                .max_diff_lines
+                .map_or(usize::MAX, |max| max.saturating_sub(lines_shown));
+            let lines = file.hunks.split_inclusive('\n').collect::<Vec<_>>();
+            if lines.len() > max_lines {
+                lines_truncated = true;
+            }
+            lines
+                .into_iter()
+                .take(max_lines)
+                .inspect(|_| lines_shown += 1)
+                .map(|line| diff_line_html(line.strip_suffix('\n').unwrap_or(line), options))
+                .collect::<Escaped>()
+        };
+        // once truncated, later files only have their header left
+        if show_file && !stat_only && (!lines_truncated || !diff_html.as_str().is_empty()) {
+            file_html.push(&diff_html);
+            pre_diffs.push(file_html);
+        }
+        Ok(())
+    })?;
+
+    container.add_paragraph(format!(
+        "{} files changed, {} insertions(+), {} deletions(-)",
+        total_files_changed, total_lines_added, total_lines_removed
+    ));
+    container.add_html(Bold::from("Diffstat:"));
+    container.add_table(diffstat_table);
+    if stat_only {
+        return Ok(raw_diff);
+    }
+    container.add_html(HtmlElement::new(build_html::HtmlTag::HorizontalRule));
+    for diff in pre_diffs {
+        container.add_preformatted(diff);
+    }
+    if files_hidden > 0 {
+        container.add_paragraph(format!(
+            "{} more changed files not shown, see the raw diff.",
+            files_hidden
+        ));
+    }
+    if lines_truncated {
         container.add_paragraph(format!(
-            "{} files changed, {} insertions(+), {} deletions(-)",
-            total_files_changed, total_lines_added, total_lines_removed
+            "Diff truncated after {} lines, see the raw diff.",
+            lines_shown
         ));
-        container.add_html(Bold::from("Diffstat:"));
-        container.add_table(diffstat_table);
-        container.add_html(HtmlElement::new(build_html::HtmlTag::HorizontalRule));
-        for diff in pre_diffs {
-            container.add_preformatted(diff);
+    }
+    Ok(raw_diff)
+}
+
+/// Number of characters of a line shown on a file page, the rest being cut off.
+const MAX_LINE_LENGTH: usize = 1000;
+
+/// Totals for a directory in the tree.
+#[derive(Debug, Default)]
+struct DirStats {
+    /// Number of files and directories directly in the directory.
+    entries: usize,
+    /// Cumulative size of every blob below the directory.
+    size: usize,
+}
+
+struct LastCommit {
+    id: String,
+    title: String,
+    time: String,
+    /// Whether the commit is within the log length, and so has a page to link to.
+    has_page: bool,
+}
+
+/// Find the last commit to touch each of `paths` with a single walk of history,
+/// stopping as soon as every path has been seen.
+fn get_last_commits(
+    repo: &Repository,
+    tip: ObjectId,
+    mut paths: HashSet<String>,
+    log_length: Option<usize>,
+    topology: Topology,
+    time_zone: TimeZone,
+) -> anyhow::Result<HashMap<String, LastCommit>> {
+    debug!(repo=?repo.path(), %tip, num_paths = paths.len(), "get last commits");
+    let mut last_commits = HashMap::new();
+    let num_paths = paths.len();
+    let mut progress = Progress::new("found the last commit of", "files", Some(num_paths));
+    let revs = topology.walk(repo, tip)?;
+    for (i, rev) in revs.enumerate() {
+        if paths.is_empty() {
+            break;
         }
-        let title = message.title.to_string();
-        containers.push((commit.id.to_string(), title, container));
+        progress.update(num_paths - paths.len());
+        let commit = rev?.object()?;
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent_ids().next() {
+            Some(parent_id) => repo.find_commit(parent_id)?.tree()?,
+            None => repo.empty_tree(),
+        };
+        let id = commit.id.to_string();
+        let title = commit.message()?.title.trim().to_str()?.to_owned();
+        let time = time_zone.apply(commit.author()?.time()?).format(ISO8601)?;
+        parent_tree
+            .changes()?
+            .options(|o| {
+                o.track_rewrites(None);
+            })
+            .for_each_to_obtain_tree(
+                &tree,
+                |change| -> anyhow::Result<gix::object::tree::diff::Action> {
+                    let location = change.location().to_string();
+                    if paths.remove(&location) {
+                        last_commits.insert(
+                            location,
+                            LastCommit {
+                                id: id.clone(),
+                                title: title.clone(),
+                                time: time.clone(),
+                                has_page: log_length.is_none_or(|l| i < l),
+                            },
+                        );
+                    }
+                    Ok(gix::object::tree::diff::Action::Continue)
+                },
+            )?;
     }
-    Ok(containers)
+    Ok(last_commits)
 }
 
-fn get_files(repo: &Repository) -> anyhow::Result<(Container, Vec<(PathBuf, Container)>)> {
-    debug!(repo=?repo.path(), "get files");
-    let head_tree = repo.head_tree()?;
+/// Entries of `tree` to list and write pages for, leaving out those ignored for export and those
+/// with unsafe paths.
+fn listed_entries(tree: &Tree<'_>, linguist: &Linguist) -> anyhow::Result<Vec<recorder::Entry>> {
     let mut recorder = Recorder::default();
-    head_tree.traverse().depthfirst(&mut recorder)?;
+    tree.traverse().depthfirst(&mut recorder)?;
+    recorder
+        .records
+        .retain(|entry| !linguist.is_export_ignored(&entry.filepath.to_str_lossy()));
+    retain_safe_paths(&mut recorder.records);
+    Ok(recorder.records)
+}
 
+/// Paths of the files in `tree` that [`get_files`] writes a page for and diffs link to.
+fn file_page_paths(tree: &Tree<'_>) -> anyhow::Result<HashSet<String>> {
+    let linguist = Linguist::load(tree)?;
+    Ok(listed_entries(tree, &linguist)?
+        .into_iter()
+        .filter(|entry| entry.mode.is_blob())
+        .filter_map(|entry| entry.filepath.to_str().ok().map(str::to_owned))
+        .collect())
+}
+
+/// The pages of the files in a tree.
+struct TreePages {
+    /// The file list.
+    list: Container,
+    /// The page of each file, at its path with `.html` appended, with the id of its blob.
+    files: Vec<(PathBuf, ObjectId, Container)>,
+    /// Contents of any files too large or with lines too long to show in full, and of PDFs, to
+    /// be written under `raw/` at their [`raw_path`].
+    raw: Vec<(PathBuf, ObjectId, Vec<u8>)>,
+}
+
+/// The pages of the files in the tree of `tip`.
+fn get_files(
+    repo: &Repository,
+    tip: ObjectId,
+    log_length: Option<usize>,
+    blame: bool,
+    options: &PageOptions,
+) -> anyhow::Result<TreePages> {
+    debug!(repo=?repo.path(), %tip, "get files");
+    let tree = repo.find_commit(tip)?.tree()?;
+    let linguist = Linguist::load(&tree)?;
+    let records = listed_entries(&tree, &linguist)?;
+    let tracked_paths = records
+        .iter()
+        .filter(|entry| entry.mode.is_blob() || entry.mode.is_commit())
+        .map(|entry| entry.filepath.to_string())
+        .collect();
+    let last_commits = get_last_commits(
+        repo,
+        tip,
+        tracked_paths,
+        log_length,
+        options.topology,
+        options.time_zone,
+    )?;
+    let last_commit_cells = |filepath: &str| match last_commits.get(filepath) {
+        Some(last) if last.has_page => (
+            link(
+                &Escaped::new(format!("commits/{}.html", last.id)),
+                &Escaped::new(&last.title),
+            ),
+            Escaped::new(&last.time),
+        ),
+        Some(last) => (Escaped::new(&last.title), Escaped::new(&last.time)),
+        None => (Escaped::default(), Escaped::default()),
+    };
+    // a broken .gitmodules only loses the links to upstreams, the submodules are still listed
+    let submodules = match tree.lookup_entry_by_path(".gitmodules")? {
+        Some(entry) => gix::submodule::File::from_bytes(
+            &entry.object()?.data,
+            None,
+            repo.config_snapshot().plumbing(),
+        )
+        .inspect_err(|error| warn!(%error, "Failed to parse .gitmodules"))
+        .ok(),
+        None => None,
+    };
+
+    let mut dirs: BTreeMap<String, DirStats> = BTreeMap::new();
+    for entry in &records {
+        if let Some((parent, _)) = entry.filepath.to_str_lossy().rsplit_once('/') {
+            dirs.entry(parent.to_owned()).or_default().entries += 1;
+        }
+        if entry.mode.is_tree() {
+            dirs.entry(entry.filepath.to_string()).or_default();
+        }
+    }
+
+    let num_files = records.iter().filter(|entry| entry.mode.is_blob()).count();
+    let mut progress = Progress::new("rendered", "files", Some(num_files));
     let mut entries = Vec::new();
-    let mut list_container = Container::new(build_html::ContainerType::Div);
+    let mut raw_files = Vec::new();
+    let mut languages = Languages::default();
+    let mut total_size = 0;
+    let mut file_tree = FileTree::default();
     let mut table = Table::new()
         .with_attributes([("id", "files")])
-        .with_header_row(["Mode", "Name", "Size"]);
-    for entry in recorder.records {
+        .with_header_row(["Mode", "Name", "Size", "Last commit", "Last commit time"]);
+    for entry in records {
         let mode = match entry.mode.kind() {
             EntryKind::Tree => continue,
             EntryKind::Blob => "-rw-r--r--",
             EntryKind::BlobExecutable => "-rwxr-xr-x",
             EntryKind::Link => continue,
-            EntryKind::Commit => continue,
+            EntryKind::Commit => "m---------",
         };
+        if entry.mode.is_commit() {
+            // submodules have no file page, only a link to their upstream when it is browsable
+            let path = Escaped::new(&entry.filepath);
+            let url = submodules.as_ref().and_then(|submodules| {
+                let name = submodules.name_by_path(entry.filepath.as_bstr())?;
+                submodules.url(name).ok()
+            });
+            let name = match url {
+                Some(url)
+                    if matches!(url.scheme, gix::url::Scheme::Http | gix::url::Scheme::Https) =>
+                {
+                    link(&Escaped::new(url.to_bstring()), &path)
+                }
+                _ => path,
+            };
+            let (last_commit, last_commit_time) = last_commit_cells(&entry.filepath.to_string());
+            file_tree.add(&entry.filepath.to_string(), false);
+            table.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(TableCell::default().with_raw(mode))
+                    .with_cell(cell(&name))
+                    .with_cell(cell(&Escaped::new(format!("@ {}", entry.oid))))
+                    .with_cell(cell(&last_commit))
+                    .with_cell(cell(&last_commit_time)),
+            );
+            continue;
+        }
         let obj = repo.find_object(entry.oid)?;
+        let filepath = entry.filepath.to_str_lossy();
+        for (i, _) in filepath.match_indices('/') {
+            if let Some(stats) = dirs.get_mut(&filepath[..i]) {
+                stats.size += obj.data.len();
+            }
+        }
+
+        let attributes = linguist.lookup(&entry.filepath.to_string());
 
         let path = PathBuf::from(format!("{}.html", entry.filepath));
+        let size = obj.data.len() as u64;
+        total_size += size;
+        let description = match &attributes.language {
+            Some(language) => format!("{} ({}, {})", entry.filepath, format_size(size), language),
+            None => format!("{} ({})", entry.filepath, format_size(size)),
+        };
         let mut content = Container::new(build_html::ContainerType::Div)
             .with_attributes([("id", "content")])
-            .with_paragraph(format!("{} ({}B)", entry.filepath, obj.data.len()))
-            .with_html(HtmlElement::new(build_html::HtmlTag::HorizontalRule));
-
-        let size = if let Ok(file_content) = str::from_utf8(&obj.data) {
+            .with_paragraph(Escaped::new(description));
+        // blame pages are only written for text files
+        if blame && str::from_utf8(&obj.data).is_ok() {
+            let to_repo_root = to_root_path(&PathBuf::from("files").join(&path));
+            content.add_html(
+                HtmlElement::new(build_html::HtmlTag::ParagraphText).with_raw(link(
+                    &Escaped::new(format!(
+                        "{}blame/{}",
+                        to_repo_root,
+                        url_path(&path.to_string_lossy())
+                    )),
+                    &Escaped::new("Blame"),
+                )),
+            );
+        }
+        // only the latest files pages move, the ones under blob/ being the permalinks
+        if options.blob_pages && options.ref_root.is_none() {
+            let to_repo_root = to_root_path(&PathBuf::from("files").join(&path));
+            content.add_html(
+                HtmlElement::new(build_html::HtmlTag::ParagraphText).with_raw(link(
+                    &Escaped::new(format!(
+                        "{}{}/files/{}",
+                        to_repo_root,
+                        blob_dir(tip).display(),
+                        url_path(&path.to_string_lossy())
+                    )),
+                    &Escaped::new("Permalink"),
+                )),
+            );
+        }
+        content.add_html(HtmlElement::new(build_html::HtmlTag::HorizontalRule));
+
+        let extension = Path::new(&*filepath)
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        let image = image_mime(entry.filepath.as_bstr());
+        let to_repo_root = to_root_path(&PathBuf::from("files").join(&path));
+        let raw_href = format!(
+            "{}raw/{}",
+            to_repo_root,
+            url_path(&raw_path(Path::new(&*filepath), &obj.data).to_string_lossy())
+        );
+        // only text files have lines to count
+        let lines = if options.max_blob_size.is_some_and(|max| size > max) {
+            // too large to render, so only written raw
+            content.add_html(
+                HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                    .with_raw(format!(
+                        "File too large to display ({}), see the ",
+                        format_size(size)
+                    ))
+                    .with_raw(link(&Escaped::new(&raw_href), &Escaped::new("raw file")))
+                    .with_raw("."),
+            );
+            raw_files.push((PathBuf::from(&*filepath), entry.oid, obj.data.clone()));
+            None
+        } else if let Some(mime) = image {
+            // embedded rather than written raw, as opening an svg from the site would run its
+            // scripts with the same origin as the pages
+            content.add_html(
+                HtmlElement::new(build_html::HtmlTag::Div)
+                    .with_attribute("class", "preview")
+                    .with_image(
+                        format!("data:{mime};base64,{}", BASE64_STANDARD.encode(&obj.data)),
+                        Escaped::new(&filepath),
+                    ),
+            );
+            None
+        } else if extension.as_deref() == Some("pdf") {
+            content.add_html(
+                HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                    .with_raw(link(&Escaped::new(&raw_href), &Escaped::new("Download")))
+                    .with_raw(format!(" ({})", format_size(size))),
+            );
+            raw_files.push((PathBuf::from(&*filepath), entry.oid, obj.data.clone()));
+            None
+        } else if let Ok(file_content) = str::from_utf8(&obj.data) {
+            // rendered files, such as notebooks, are shown above their source
+            if let Some(render) = extension.as_deref().and_then(rich_renderer) {
+                match render(file_content) {
+                    Ok(rendered) => {
+                        content.add_raw(format!("<div class=\"rendered\">{rendered}</div>"));
+                        content.add_header(2, "Source");
+                    }
+                    Err(error) => warn!(path = %filepath, %error, "Failed to render file"),
+                }
+            }
+            // the commit the lines are from, for telling when a link to them may have moved
+            let permalink_title = format!("Permalink to this line at {}", tip.to_hex_with_len(7));
+            let mut truncated = false;
+            // such as captured terminal output, coloured rather than littered with escapes
+            let mut ansi = has_ansi(file_content).then(AnsiRenderer::default);
             let lines: Vec<String> = file_content
-                .lines()
+                .split_terminator('\n')
                 .enumerate()
                 .map(|(i, line)| {
                     let link = HtmlElement::new(build_html::HtmlTag::Link)
-                        .with_attribute("id", format!("l{}", i))
                         .with_attribute("href", format!("#l{}", i))
                         .with_attribute("class", "line")
+                        .with_attribute("title", &permalink_title)
                         .with_child(format!("{: >7} ", i).into())
                         .to_html_string();
-                    let content = escape_html(line);
-                    format!("{}{}", link, content)
+                    // such as minified files, which would otherwise be one huge line
+                    let line = match line.char_indices().nth(MAX_LINE_LENGTH) {
+                        Some((end, _)) => {
+                            truncated = true;
+                            &line[..end]
+                        }
+                        None => line,
+                    };
+                    let content = if let Some(ansi) = &mut ansi {
+                        ansi.line_html(line.strip_suffix('\r').unwrap_or(line))
+                    } else if options.show_whitespace {
+                        escape_line_marking_whitespace(line)
+                    } else {
+                        Escaped::new(line.strip_suffix('\r').unwrap_or(line))
+                    };
+                    // the whole line is the target, so it is highlighted without a script
+                    format!("<span id=\"l{}\" class=\"l\">{}{}</span>", i, link, content)
                 })
                 .collect();
 
             content.add_preformatted_attr(lines.join("\n"), [("id", "blob")]);
+            if truncated {
+                content.add_html(
+                    HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                        .with_raw(format!(
+                            "Lines longer than {MAX_LINE_LENGTH} characters are cut short, see the "
+                        ))
+                        .with_raw(link(&Escaped::new(&raw_href), &Escaped::new("raw file")))
+                        .with_raw(" for all of them."),
+                );
+                raw_files.push((PathBuf::from(&*filepath), entry.oid, obj.data.clone()));
+            }
 
-            format!("{}L", file_content.lines().count())
+            let lines = file_content.lines().count();
+            languages.record(&filepath, &attributes, lines);
+            Some(lines)
         } else {
             content.add_raw("binary file.");
-            format!("{}B", obj.data.len())
+            None
+        };
+        let size_title = match lines {
+            Some(lines) => format!("{size} bytes, {lines} lines"),
+            None => format!("{size} bytes"),
         };
 
-        entries.push((path, content));
+        file_tree.add(&filepath, true);
+        entries.push((path, entry.oid, content));
+        progress.inc();
 
-        let path = escape_html(&entry.filepath.to_string());
+        let (last_commit, last_commit_time) = last_commit_cells(&entry.filepath.to_string());
+
+        let mut name = link(
+            &Escaped::new(format!("files/{}.html", url_path(&filepath))),
+            &Escaped::new(&filepath),
+        );
+        if attributes.generated {
+            name.push(&Escaped::new(" (generated)"));
+        } else if attributes.vendored {
+            name.push(&Escaped::new(" (vendored)"));
+        }
         table.add_custom_body_row(
             TableRow::new()
                 .with_cell(TableCell::default().with_raw(mode))
-                .with_cell(
-                    TableCell::default().with_html(
-                        HtmlElement::new(build_html::HtmlTag::Span)
-                            .with_link(format!("files/{}.html", path), path)
-                            .to_html_string(),
-                    ),
-                )
+                .with_cell(cell(&name))
                 .with_cell(
                     TableCell::default()
-                        .with_attributes([("class", "num")])
-                        .with_raw(size),
-                ),
+                        .with_attributes([("class", "num"), ("title", size_title.as_str())])
+                        .with_raw(format_size(size)),
+                )
+                .with_cell(cell(&last_commit))
+                .with_cell(cell(&last_commit_time)),
+        );
+    }
+    table.add_custom_body_row(
+        TableRow::new()
+            .with_cell(TableCell::default())
+            .with_cell(TableCell::default().with_raw(format!("Total of {num_files} files")))
+            .with_cell(
+                TableCell::default()
+                    .with_attributes([("class", "num")])
+                    .with_raw(format_size(total_size)),
+            )
+            .with_cell(TableCell::default())
+            .with_cell(TableCell::default()),
+    );
+    let mut list_container = Container::new(build_html::ContainerType::Div);
+    if options.dir_pages {
+        list_container.add_html(
+            HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                .with_link("files/index.html", "Browse by directory"),
         );
     }
-    list_container.add_table(table);
+    let breakdown = languages.to_container();
+    let has_sections = breakdown.is_some() || !dirs.is_empty();
+    if let Some(breakdown) = breakdown {
+        list_container.add_container(breakdown);
+    }
+    if !dirs.is_empty() {
+        let mut dir_table = Table::new()
+            .with_attributes([("id", "dirs")])
+            .with_header_row(["Directory", "Entries", "Size"]);
+        for (dir, stats) in dirs {
+            let label = Escaped::new(format!("{dir}/"));
+            // a directory with a file named index has no page of its own
+            let has_page = options.dir_pages
+                && tree
+                    .lookup_entry_by_path(format!("{dir}/index"))?
+                    .is_none_or(|entry| !entry.mode().is_blob());
+            let name = if has_page {
+                let href = Path::new("files").join(dir_page_path(&dir));
+                link(&Escaped::new(href.to_string_lossy()), &label)
+            } else {
+                label
+            };
+            dir_table.add_custom_body_row(
+                TableRow::new()
+                    .with_cell(cell(&name))
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(stats.entries),
+                    )
+                    .with_cell(
+                        TableCell::default()
+                            .with_attributes([("class", "num")])
+                            .with_raw(format_size(stats.size as u64)),
+                    ),
+            );
+        }
+        list_container.add_header(2, "Directories");
+        list_container.add_table(dir_table);
+    }
+    if has_sections {
+        list_container.add_header(2, "Files");
+    }
+    if options.file_listing.has_tree() {
+        list_container.add_raw(file_tree.to_html());
+    }
+    if options.file_listing.has_table() {
+        list_container.add_table(table);
+    }
+
+    Ok(TreePages {
+        list: list_container,
+        files: entries,
+        raw: raw_files,
+    })
+}
 
-    Ok((list_container, entries))
+#[derive(Debug, Clone)]
+pub struct RepoOptions {
+    pub output: Output,
+    /// Build a directory output in a staging copy next to it, swapping it in once complete.
+    pub atomic: bool,
+    pub log_length: Option<usize>,
+    /// Also split the entire history into a log page per period.
+    pub log_archive: Option<LogArchive>,
+    /// Number of commits per page of the log, splitting it over `log/2.html` and onwards, with
+    /// `log/1.html` redirecting to `log.html`.
+    pub log_page_size: Option<usize>,
+    /// Also write a blame page for every text file, which is slow for long histories.
+    pub blame: bool,
+    /// Write `.tar.gz` snapshots of every tag to `archives/`.
+    pub snapshots: bool,
+    /// Also write a snapshot of `HEAD` along with those of the tags.
+    pub snapshot_head: bool,
+    /// Export the repo to `<name>.git/` so it can be cloned from the output over dumb HTTP.
+    pub dumb_http: bool,
+    /// Branches to also write log, files and commit pages for under `branches/<name>/`, `all`
+    /// selecting every local branch.
+    pub branches: Vec<String>,
+    /// Also write files pages for the tree of every tag under `tags/<name>/`.
+    pub tag_trees: bool,
+    /// Also write a compare page from the latest tag to `HEAD`.
+    pub compare_head: bool,
+    /// Refs whose history gets commit pages, besides `HEAD`.
+    pub commit_refs: CommitRefs,
+    /// Formats to also write the repo's data in.
+    pub formats: Vec<Format>,
+    /// Branch to build the log and files from, overriding the repo config and `HEAD`.
+    pub default_branch: Option<String>,
+    pub clone_base_urls: Vec<String>,
+    pub page: PageOptions,
+    /// Web server config to emit with cache headers for the generated pages.
+    pub server_config: Option<ServerConfig>,
+    /// Where to write the server config, [`ServerConfig::default_path`] if `None`.
+    pub server_config_path: Option<PathBuf>,
+}
+
+pub fn build_repo_pages(repo_path: &Path, options: &RepoOptions) -> anyhow::Result<()> {
+    let mut sink = if options.atomic {
+        options.output.open_atomic()?
+    } else {
+        options.output.open()?
+    };
+    build_repo_pages_to(repo_path, options, sink.as_mut())
+}
+
+/// Render the pages for a repo without writing them, returning the paths in the output directory
+/// that are missing or out of date.
+pub fn verify_repo_pages(repo_path: &Path, options: &RepoOptions) -> anyhow::Result<Vec<PathBuf>> {
+    let Output::Dir(out_dir) = &options.output else {
+        anyhow::bail!("can only verify output to a directory");
+    };
+    if options.page.date_format != DateFormat::Iso {
+        // the pages would never match, being rendered at a different time
+        anyhow::bail!(
+            "can't verify pages with relative dates, as they embed the time they were built"
+        );
+    }
+    let mut sink = VerifySink::new(out_dir.canonicalize()?);
+    // the server config isn't part of the output
+    let options = RepoOptions {
+        server_config: None,
+        ..options.clone()
+    };
+    build_repo_pages_to(repo_path, &options, &mut sink)?;
+    Ok(sink.stale)
 }
 
-pub fn build_repo_pages(
+/// Build the pages for a repo, writing them to `sink` rather than `options.output`.
+pub fn build_repo_pages_to(
     repo_path: &Path,
-    out_dir: &Path,
-    log_length: Option<usize>,
-    clone_base_urls: &[String],
+    options: &RepoOptions,
+    sink: &mut dyn OutputSink,
 ) -> anyhow::Result<()> {
-    info!(?repo_path, ?out_dir, ?log_length, "build repo pages");
+    info!(?repo_path, ?options, "build repo pages");
     let start = Instant::now();
-    let out_dir = out_dir.canonicalize()?;
     let repo = gix::open(repo_path).context("open repo")?;
 
-    let meta = Meta::load(&repo, repo_path, clone_base_urls)?;
-
-    if repo_is_newer(&repo, &out_dir.join("log.html"))
-        || meta.mod_time > out_dir.join("log.html").metadata()?.modified()?
+    let mut meta = Meta::load(
+        &repo,
+        repo_path,
+        &options.clone_base_urls,
+        options.default_branch.as_deref(),
+    )?;
+    let dumb_http_dir = PathBuf::from(format!("{}.git", meta.name));
+    if options.dumb_http
+        && let Some(url) = options.page.url_for(&dumb_http_dir)
     {
-        let refs = get_refs(&repo).context("get refs")?;
-        meta.write_html_content_to_file("Refs", &PathBuf::from("refs.html"), refs, true, &out_dir)?;
+        meta.urls.push(url);
+    }
 
-        let (file_list, files) = get_files(&repo).context("get files")?;
-        create_dir_all(out_dir.join("files"))?;
-        for (path, content) in files {
-            create_dir_all(out_dir.join("files").join(path.parent().unwrap()))?;
-            meta.write_html_content_to_file(
+    let last_built = sink.modified(Path::new("log.html"));
+    let Some(head_id) = meta.head else {
+        if last_built.is_none_or(|t| meta.mod_time > t) {
+            write_empty_repo_pages(&meta, options, sink)?;
+            info!(elapsed=? start.elapsed(), "Built empty repo");
+        }
+        return Ok(());
+    };
+    let outdated =
+        repo_is_newer(&repo, head_id, last_built) || last_built.is_none_or(|t| meta.mod_time > t);
+    if !outdated {
+        info!(elapsed=? start.elapsed(), "Skipped building repo as log.html is newer than head commit");
+        return Ok(());
+    }
+
+    let build = RepoBuild {
+        repo: &repo,
+        meta: &meta,
+        options,
+        head: head_id,
+        log_length: options.log_length.or(meta.log_length),
+        decorations: get_decorations(&repo).context("get decorations")?,
+        previous_manifest: sink
+            .read(Path::new(MANIFEST_FILE))
+            .and_then(|json| Manifest::from_json(&json).ok()),
+    };
+    let mut cache = CommitCache::load(sink, options, &meta)?;
+    let mut renames_cache = RenamesCache::load(sink, &options.page);
+    for id in build.decorations.keys() {
+        cache.remove(&id.to_string());
+    }
+    let mut sink = ManifestSink::new(sink);
+    // pages of cached commits aren't written again, so keep them in the manifest
+    if let Some(previous) = &build.previous_manifest {
+        sink.manifest.files.extend(
+            previous
+                .files
+                .iter()
+                .filter(|e| {
+                    (matches!(e.source, Source::Commit(_))
+                        || (options.page.blob_pages && e.path.starts_with("blob")))
+                        && cache.contains_file(&e.path)
+                })
+                .cloned(),
+        );
+    }
+    build.write_about_pages(&mut sink)?;
+
+    let snapshots = if options.snapshots {
+        build.snapshots(&sink).context("get snapshots")?
+    } else {
+        Vec::new()
+    };
+    let branches = select_branches(&repo, &options.branches).context("select branches")?;
+    let tips = options
+        .commit_refs
+        .tips(&repo, head_id)
+        .context("get commit refs")?;
+    // the tip of each walked ref has a page unless there are no commit pages at all
+    let paged_tips = if build.log_length == Some(0) {
+        &[][..]
+    } else {
+        &tips[..]
+    };
+    let paged = paged_commits(&repo, paged_tips, build.log_length, options.page.topology)?;
+    let compares = get_compares(&repo, head_id, options.compare_head, &paged, &options.page)
+        .context("get compares")?;
+    let mut search = options.page.search.then(SearchIndex::default);
+    if let Some(search) = &mut search {
+        search
+            .add_refs(&repo, paged_tips)
+            .context("add refs to search index")?;
+    }
+    let releases = get_releases(&repo, head_id, &compares, paged_tips, &options.page)
+        .context("get releases")?;
+    let refs = get_refs(
+        &build, &compares, &releases, &snapshots, &branches, paged_tips,
+    )
+    .context("get refs")?;
+    build.write_page("Refs", Path::new("refs.html"), refs, &mut sink)?;
+
+    build.write_ref_trees(&branches, &mut cache, &mut sink)?;
+    sink.set_source(Source::Ref("HEAD".to_owned()));
+    build.write_snapshots(&snapshots, &mut sink)?;
+    build.write_compare_pages(compares, &mut sink)?;
+    build.write_release_pages(releases, &mut sink)?;
+    sink.set_source(Source::Ref("HEAD".to_owned()));
+
+    build.write_files_pages(search.as_mut(), &mut sink)?;
+    // old links to moved files keep working until another file takes their path
+    let renames = renamed_paths(
+        &repo,
+        head_id,
+        build.log_length,
+        &mut renames_cache,
+        &options.page,
+    )
+    .context("find renamed paths")?;
+    let written = sink
+        .manifest
+        .files
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<HashSet<_>>();
+    for (path, html) in rename_stubs(&renames, |path| written.contains(path)) {
+        sink.write(&path, html.as_bytes())?;
+    }
+
+    let authors = build.write_log_pages(&mut sink)?;
+    // packs and loose objects, as cloning would fetch
+    let disk_size = match dir_size(&repo.common_dir().join("objects")) {
+        Ok(size) => Some(size),
+        Err(error) => {
+            warn!(%error, "Failed to get size of objects");
+            None
+        }
+    };
+    build.write_page(
+        "Statistics",
+        Path::new("stats.html"),
+        authors.to_stats_container(disk_size)?,
+        &mut sink,
+    )?;
+    // the authors page was folded into the stats page, keep old links to it working
+    sink.write(
+        Path::new("authors.html"),
+        redirect_html("stats.html").as_bytes(),
+    )?;
+
+    build.write_commit_pages(
+        &tips,
+        Path::new("commits"),
+        &options.page,
+        &mut cache,
+        &mut sink,
+    )?;
+    sink.set_source(Source::Ref("HEAD".to_owned()));
+    if let Some(mut search) = search {
+        search
+            .add_commits(&repo, &tips, build.log_length, options.page.topology)
+            .context("add commits to search index")?;
+        sink.write(Path::new(SEARCH_INDEX_FILE), search.to_json()?.as_bytes())?;
+        build.write_page(
+            "Search",
+            Path::new("search.html"),
+            search.to_container(),
+            &mut sink,
+        )?;
+    }
+    for format in &options.formats {
+        format
+            .write_repo(
+                &repo,
+                &meta,
+                head_id,
+                build.log_length,
+                &options.page,
+                &mut sink,
+            )
+            .with_context(|| format!("write {format:?}"))?;
+    }
+    if options.dumb_http {
+        export_dumb_http(
+            &repo,
+            &dumb_http_dir,
+            build.previous_manifest.as_ref(),
+            &mut sink,
+        )
+        .context("export for dumb http")?;
+    }
+    let atom = get_atom(&repo, &meta, build.log_length, &options.page).context("get atom feed")?;
+    sink.write(Path::new(ATOM_FILE), atom.as_bytes())?;
+    if let Some(sitemap) = sink.manifest.sitemap(&options.page) {
+        sink.write(Path::new("sitemap.xml"), sitemap.as_bytes())?;
+    }
+    let server_config = options.server_config.map(|server_config| {
+        let config = server_config.render(&sink.manifest, options.page.base_url.as_deref());
+        let path = options
+            .server_config_path
+            .clone()
+            .unwrap_or_else(|| server_config.default_path(&options.output));
+        (path, config)
+    });
+    write_not_found_page(&meta, &options.page, &mut sink)?;
+    for (path, html) in dir_index_stubs(&sink.manifest) {
+        sink.write(&path, html.as_bytes())?;
+    }
+    sink.write_unrecorded(Path::new(COMMIT_CACHE_FILE), cache.to_file().as_bytes())?;
+    sink.write_unrecorded(
+        Path::new(RENAMES_CACHE_FILE),
+        renames_cache.to_json()?.as_bytes(),
+    )?;
+    // pages of removed files, commits and refs from the last build
+    if let Some(previous) = &build.previous_manifest {
+        sink.prune(previous).context("prune stale output")?;
+    }
+    sink.finish_with_manifest()?;
+    // written once the output is in place, in case it is put inside it
+    if let Some((path, config)) = server_config {
+        std::fs::write(&path, config)
+            .with_context(|| format!("write server config to {path:?}"))?;
+    }
+    info!(elapsed=? start.elapsed(), "Built repo");
+    Ok(())
+}
+
+/// What the pages of a single build of a repo are rendered from.
+struct RepoBuild<'a> {
+    repo: &'a Repository,
+    meta: &'a Meta,
+    options: &'a RepoOptions,
+    head: ObjectId,
+    /// Number of commits in the log, from the options or else the repo's metadata.
+    log_length: Option<usize>,
+    /// Names of the branches and tags pointing at each commit.
+    decorations: HashMap<ObjectId, Vec<String>>,
+    /// What the previous build wrote, for the files it left that are still current.
+    previous_manifest: Option<Manifest>,
+}
+
+impl RepoBuild<'_> {
+    /// Write a page of `HEAD` with the nav.
+    fn write_page(
+        &self,
+        title: &str,
+        path: &Path,
+        container: Container,
+        sink: &mut dyn OutputSink,
+    ) -> anyhow::Result<()> {
+        self.meta
+            .write_html_content_to_file(title, path, container, true, sink, &self.options.page)
+    }
+
+    /// The README, changelog and contributing pages, for those the repo has.
+    fn write_about_pages(&self, sink: &mut dyn OutputSink) -> anyhow::Result<()> {
+        for (file, title, path) in [
+            (&self.meta.readme, "About", "about.html"),
+            (&self.meta.changelog, "Changelog", "changelog.html"),
+            (&self.meta.contributing, "Contributing", "contributing.html"),
+        ] {
+            let Some(file) = file else {
+                continue;
+            };
+            let about = get_about(
+                self.repo,
+                self.head,
+                file,
+                self.options.page.mermaid_script.as_deref(),
+            )
+            .with_context(|| format!("get {file}"))?;
+            self.write_page(title, Path::new(path), about, sink)?;
+        }
+        Ok(())
+    }
+
+    /// Snapshots of the tags, and of `HEAD` if asked for, those of commits the previous build
+    /// archived being left as they are.
+    fn snapshots(&self, sink: &ManifestSink<'_>) -> anyhow::Result<Vec<Snapshot>> {
+        let built = |path: &Path, commit: ObjectId| {
+            let source = Source::Commit(commit.to_string());
+            self.previous_manifest.as_ref().is_some_and(|previous| {
+                previous
+                    .files
+                    .iter()
+                    .any(|e| e.path == path && e.source == source)
+            }) && sink.modified(path).is_some()
+        };
+        get_snapshots(
+            self.repo,
+            &self.meta.name,
+            self.options.snapshot_head.then_some(self.head),
+            built,
+        )
+    }
+
+    fn write_snapshots(
+        &self,
+        snapshots: &[Snapshot],
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        for snapshot in snapshots {
+            sink.set_source(Source::Commit(snapshot.commit.to_string()));
+            match &snapshot.data {
+                Some(data) => sink.write(&snapshot.path, data)?,
+                // left as is from the previous build, so keep it in the manifest
+                None => sink.manifest.files.extend(
+                    self.previous_manifest
+                        .iter()
+                        .flat_map(|previous| &previous.files)
+                        .filter(|e| e.path == snapshot.path)
+                        .cloned(),
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn write_compare_pages(
+        &self,
+        compares: Vec<ComparePage>,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        for compare in compares {
+            let path = compare.path();
+            sink.set_source(Source::Ref(format!("{}..{}", compare.from, compare.to)));
+            self.write_page(
+                &format!("Changes {}..{}", compare.from, compare.to),
+                &path,
+                compare.container,
+                sink,
+            )?;
+            sink.write(&path.with_extension("diff"), compare.diff.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_release_pages(
+        &self,
+        releases: Vec<ReleasePage>,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        for release in releases {
+            sink.set_source(Source::Ref(release.tag.clone()));
+            let path = release.path();
+            self.write_page(
+                &format!("Release {}", release.tag),
+                &path,
+                release.container,
+                sink,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The pages of the selected `branches`, and of every tag and the permalink files pages if
+    /// asked for.
+    fn write_ref_trees(
+        &self,
+        branches: &[(String, ObjectId)],
+        cache: &mut CommitCache,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        for (branch, tip) in branches {
+            let ref_root = RefRoot {
+                dir: PathBuf::from("branches").join(branch),
+                has_log: true,
+            };
+            self.write_ref_pages(branch, *tip, ref_root, cache, sink)
+                .with_context(|| format!("write pages for branch {branch}"))?;
+        }
+        if self.options.tag_trees {
+            for tag in self.repo.references()?.tags()? {
+                let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+                let tag_name = tag.name().shorten().to_string();
+                // like the refs page, only tags of commits are listed
+                let Ok(tip) = tag.peel_to_commit().map(|commit| commit.id) else {
+                    continue;
+                };
+                let ref_root = RefRoot {
+                    dir: PathBuf::from("tags").join(&tag_name),
+                    has_log: false,
+                };
+                self.write_ref_pages(&tag_name, tip, ref_root, cache, sink)
+                    .with_context(|| format!("write pages for tag {tag_name}"))?;
+            }
+        }
+        if self.options.page.blob_pages {
+            // the files of a commit never change, so those from earlier builds are kept as long
+            // as the commit cache is, which is dropped when the header, nav or templates change
+            for tip in blob_tips(self.repo, self.head).context("get blob tips")? {
+                if cache.contains_blobs(&blob_dir(tip)) {
+                    continue;
+                }
+                let ref_root = RefRoot {
+                    dir: blob_dir(tip),
+                    has_log: false,
+                };
+                let short_id = tip.to_hex_with_len(7).to_string();
+                self.write_ref_pages(&short_id, tip, ref_root, cache, sink)
+                    .with_context(|| format!("write blob pages for {tip}"))?;
+                cache.insert_blobs(blob_dir(tip));
+            }
+        }
+        Ok(())
+    }
+
+    /// The files page and the page of every file of `HEAD`, along with their raw files, blame
+    /// pages and directory pages when asked for, adding the files to the `search` index.
+    fn write_files_pages(
+        &self,
+        mut search: Option<&mut SearchIndex>,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        let options = self.options;
+        let tree_pages = get_files(
+            self.repo,
+            self.head,
+            self.log_length,
+            options.blame,
+            &options.page,
+        )
+        .context("get files")?;
+        for (path, id, data) in tree_pages.raw {
+            sink.set_source(Source::blob(&path, id));
+            sink.write(&PathBuf::from("raw").join(raw_path(&path, &data)), &data)?;
+        }
+        for (path, id, content) in tree_pages.files {
+            sink.set_source(Source::blob(&path.with_extension(""), id));
+            if let Some(search) = &mut search {
+                search.add_file(&path.with_extension(""));
+            }
+            self.write_page(
                 path.with_extension("")
                     .file_name()
                     .unwrap()
@@ -911,52 +3463,369 @@ pub fn build_repo_pages(
                     .unwrap(),
                 &PathBuf::from("files").join(&path),
                 content,
+                sink,
+            )?;
+        }
+        if options.blame {
+            for (path, content) in get_blames(
+                self.repo,
+                self.head,
+                self.log_length,
+                options.page.topology,
+                options.page.time_zone,
+            )
+            .context("get blames")?
+            {
+                let file_path = path.strip_prefix("blame")?.with_extension("");
+                sink.set_source(Source::Path(file_path.to_string_lossy().into_owned()));
+                self.write_page(
+                    &format!("Blame {}", file_path.display()),
+                    &path,
+                    content,
+                    sink,
+                )?;
+            }
+        }
+        sink.set_source(Source::Ref("HEAD".to_owned()));
+        if options.page.dir_pages {
+            write_dir_pages(
+                self.repo,
+                self.meta,
+                self.head,
+                Path::new("files"),
+                &options.page,
+                sink,
+            )?;
+        }
+        self.write_page("Files", Path::new("files.html"), tree_pages.list, sink)
+    }
+
+    /// The pages of the log of `HEAD` and its archive if asked for, returning the statistics of
+    /// the authors of the commits in it.
+    fn write_log_pages(&self, sink: &mut ManifestSink<'_>) -> anyhow::Result<Authors> {
+        let options = self.options;
+        let (log_pages, authors) = get_log(
+            self.repo,
+            self.head,
+            self.log_length,
+            options.log_page_size,
+            &self.decorations,
+            &options.page,
+        )
+        .context("get log")?;
+        let paginated = log_pages.len() > 1;
+        let mut log_pages = log_pages.into_iter();
+        let mut log_page = Container::new(build_html::ContainerType::Div);
+        if let Some(period) = options.log_archive {
+            log_page.add_html(
+                HtmlElement::new(build_html::HtmlTag::ParagraphText)
+                    .with_link("log/index.html", "Full history archive"),
+            );
+            let (archive_index, archive_pages) = get_log_archive(
+                self.repo,
+                self.head,
+                period,
+                self.log_length,
+                options.page.topology,
+                options.page.time_zone,
+            )
+            .context("get log archive")?;
+            self.write_page(
+                "Log archive",
+                Path::new("log/index.html"),
+                archive_index,
+                sink,
+            )?;
+            for (name, page) in archive_pages {
+                self.write_page(
+                    &format!("Log {name}"),
+                    &PathBuf::from("log").join(format!("{name}.html")),
+                    page,
+                    sink,
+                )?;
+            }
+        }
+        if let Some(log) = log_pages.next() {
+            log_page.add_container(log);
+        }
+        self.write_page("Log", &log_page_path(0), log_page, sink)?;
+        for (page, log) in log_pages.enumerate() {
+            self.write_page(
+                &format!("Log page {}", page + 2),
+                &log_page_path(page + 1),
+                log,
+                sink,
+            )?;
+        }
+        if paginated {
+            write_first_log_page_redirect(Path::new(""), sink)?;
+        }
+        Ok(authors)
+    }
+
+    /// The page, diff and patch of every commit in the log of `tips` without one in `cache`,
+    /// into `commits_dir`, rendered with `page`.
+    fn write_commit_pages(
+        &self,
+        tips: &[ObjectId],
+        commits_dir: &Path,
+        page: &PageOptions,
+        cache: &mut CommitCache,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        let commits = get_commits(self, tips, cache, commits_dir, page).context("get commits")?;
+        info!(rendered = commits.len(), "rendering commit pages");
+        // cached pages rendered again as a file they link to lost its page were kept in the
+        // manifest, so drop them before writing them anew
+        let stale = commits
+            .iter()
+            .map(|commit| commits_dir.join(&commit.id))
+            .filter(|page| cache.remove_page(page))
+            .collect::<HashSet<_>>();
+        if !stale.is_empty() {
+            sink.manifest
+                .files
+                .retain(|entry| !stale.contains(&entry.path.with_extension("")));
+        }
+        for commit in commits {
+            if commit.cacheable {
+                cache.insert(commits_dir, &commit.id, commit.linked_files);
+            }
+            let commit_path = commits_dir.join(&commit.id);
+            sink.set_source(Source::Commit(commit.id.clone()));
+            self.meta.write_html_content_to_file(
+                &commit.title,
+                &commit_path.with_extension("html"),
+                commit.container,
+                true,
+                sink,
+                page,
+            )?;
+            sink.write(&commit_path.with_extension("diff"), commit.diff.as_bytes())?;
+            if let Some(patch) = &commit.patch {
+                sink.write(&commit_path.with_extension("patch"), patch.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the files pages, and the log and commit pages if it has a log, for the ref
+    /// `refname` pointing at `tip` into the directory of `ref_root`.
+    fn write_ref_pages(
+        &self,
+        refname: &str,
+        tip: ObjectId,
+        ref_root: RefRoot,
+        cache: &mut CommitCache,
+        sink: &mut ManifestSink<'_>,
+    ) -> anyhow::Result<()> {
+        debug!(?refname, %tip, ?ref_root, "writing ref pages");
+        let (repo, meta) = (self.repo, self.meta);
+        let dir = ref_root.dir.clone();
+        let has_log = ref_root.has_log;
+        let page = PageOptions {
+            ref_root: Some(ref_root),
+            ..self.options.page.clone()
+        };
+        // without a log of their own there are no commit pages to link to
+        let log_length = if has_log { self.log_length } else { Some(0) };
+
+        let tree_pages = get_files(repo, tip, log_length, false, &page).context("get files")?;
+        for (path, id, data) in tree_pages.raw {
+            sink.set_source(Source::blob(&path, id));
+            sink.write(&dir.join("raw").join(raw_path(&path, &data)), &data)?;
+        }
+        for (path, id, content) in tree_pages.files {
+            sink.set_source(Source::blob(&path.with_extension(""), id));
+            meta.write_html_content_to_file(
+                &format!("{} ({})", path.with_extension("").display(), refname),
+                &dir.join("files").join(&path),
+                content,
                 true,
-                &out_dir,
+                sink,
+                &page,
             )?;
         }
+        sink.set_source(Source::Ref(refname.to_owned()));
+        if page.dir_pages {
+            write_dir_pages(repo, meta, tip, &dir.join("files"), &page, sink)?;
+        }
         meta.write_html_content_to_file(
-            "Files",
-            &PathBuf::from("files.html"),
-            file_list,
+            &format!("Files ({refname})"),
+            &dir.join("files.html"),
+            tree_pages.list,
             true,
-            &out_dir,
+            sink,
+            &page,
         )?;
+        if !has_log {
+            return Ok(());
+        }
 
-        let log = get_log(&repo, log_length).context("get log")?;
-        meta.write_html_content_to_file("Log", &PathBuf::from("log.html"), log, true, &out_dir)?;
-
-        let commits = get_commits(&repo, log_length).context("get commits")?;
-        create_dir_all(out_dir.join("commits"))?;
-        for (id, title, commit) in commits {
+        let (log_pages, _) = get_log(
+            repo,
+            tip,
+            log_length,
+            self.options.log_page_size,
+            &self.decorations,
+            &self.options.page,
+        )
+        .context("get log")?;
+        if log_pages.len() > 1 {
+            write_first_log_page_redirect(&dir, sink)?;
+        }
+        for (i, log) in log_pages.into_iter().enumerate() {
             meta.write_html_content_to_file(
-                &title,
-                &PathBuf::from("commits").join(&id).with_extension("html"),
-                commit,
+                &format!("Log ({refname})"),
+                &dir.join(log_page_path(i)),
+                log,
                 true,
-                &out_dir,
+                sink,
+                &page,
             )?;
         }
-        info!(?out_dir, elapsed=? start.elapsed(), "Built repo");
-    } else {
-        info!(?out_dir, elapsed=? start.elapsed(), "Skipped building repo as log.html is newer than head commit");
+        self.write_commit_pages(&[tip], &dir.join("commits"), &page, cache, sink)
+    }
+}
+
+/// Placeholder log, files and refs pages for a repo without any commits, so its links from the
+/// index work until the first push.
+fn write_empty_repo_pages(
+    meta: &Meta,
+    options: &RepoOptions,
+    sink: &mut dyn OutputSink,
+) -> anyhow::Result<()> {
+    let previous_manifest = sink
+        .read(Path::new(MANIFEST_FILE))
+        .and_then(|json| Manifest::from_json(&json).ok());
+    let mut sink = ManifestSink::new(sink);
+    for (title, path) in [
+        ("Log", "log.html"),
+        ("Files", "files.html"),
+        ("Refs", "refs.html"),
+    ] {
+        let container = Container::new(build_html::ContainerType::Div)
+            .with_paragraph("Empty repository, nothing has been committed yet.");
+        meta.write_html_content_to_file(
+            title,
+            Path::new(path),
+            container,
+            true,
+            &mut sink,
+            &options.page,
+        )?;
+    }
+    write_not_found_page(meta, &options.page, &mut sink)?;
+    if let Some(previous) = &previous_manifest {
+        sink.prune(previous).context("prune stale output")?;
+    }
+    sink.finish_with_manifest()
+}
+
+/// The local branches named in `wanted`, or all of them if it is just `all`.
+fn select_branches(
+    repo: &Repository,
+    wanted: &[String],
+) -> anyhow::Result<Vec<(String, ObjectId)>> {
+    let mut branches = Vec::new();
+    if wanted.is_empty() {
+        return Ok(branches);
+    }
+    let all = wanted == ["all"];
+    for branch in repo.references()?.local_branches()? {
+        let mut branch = branch.map_err(|e| anyhow::anyhow!(e))?;
+        let name = branch.name().shorten().to_string();
+        if all || wanted.contains(&name) {
+            branches.push((name, branch.peel_to_commit()?.id));
+        }
+    }
+    for name in wanted {
+        if !all && !branches.iter().any(|(b, _)| b == name) {
+            warn!(branch = name, "branch not found");
+        }
+    }
+    Ok(branches)
+}
+
+/// Directory of the permalink files pages of the commit `id`.
+fn blob_dir(id: ObjectId) -> PathBuf {
+    PathBuf::from("blob").join(id.to_string())
+}
+
+/// Commits to write permalink files pages for, `head` and the commit of every tag.
+fn blob_tips(repo: &Repository, head: ObjectId) -> anyhow::Result<Vec<ObjectId>> {
+    let mut tips = vec![head];
+    for tag in repo.references()?.tags()? {
+        let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+        let Ok(commit) = tag.peel_to_commit() else {
+            continue;
+        };
+        if !tips.contains(&commit.id) {
+            tips.push(commit.id);
+        }
+    }
+    Ok(tips)
+}
+
+/// Write the index page of every directory in the tree of `tip` under `files_dir`.
+fn write_dir_pages(
+    repo: &Repository,
+    meta: &Meta,
+    tip: ObjectId,
+    files_dir: &Path,
+    options: &PageOptions,
+    sink: &mut dyn OutputSink,
+) -> anyhow::Result<()> {
+    for dir_page in get_dir_pages(repo, tip, &meta.name).context("get dir pages")? {
+        let title = if dir_page.dir.is_empty() {
+            "Files".to_owned()
+        } else {
+            format!("{}/", dir_page.dir)
+        };
+        meta.write_html_content_to_file(
+            &title,
+            &files_dir.join(dir_page.path()),
+            dir_page.container,
+            true,
+            sink,
+            options,
+        )?;
     }
     Ok(())
 }
 
-fn to_root_path(from: &Path, to: &Path) -> String {
-    let path = from.strip_prefix(to).unwrap();
+/// Where under `raw/` a file is written, suffixed so servers give it a type browsers show or
+/// download rather than run, as a repo's html or svg opened from the site would run its scripts
+/// with the same origin as the pages.
+fn raw_path(path: &Path, data: &[u8]) -> PathBuf {
+    if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+    {
+        return path.to_owned();
+    }
+    let suffix = if str::from_utf8(data).is_ok() {
+        ".txt"
+    } else {
+        ".bin"
+    };
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn to_root_path(path: &Path) -> String {
     "../".repeat(path.components().count().saturating_sub(1))
 }
 
-fn repo_is_newer(repo: &Repository, target: &Path) -> bool {
-    let Ok(target_modified) = target.metadata().and_then(|meta| meta.modified()) else {
+fn repo_is_newer(repo: &Repository, head: ObjectId, target_modified: Option<SystemTime>) -> bool {
+    let Some(target_modified) = target_modified else {
         return true;
     };
     let Ok(target_mod_duration) = target_modified.duration_since(UNIX_EPOCH) else {
         return true;
     };
-    let Ok(head_commit) = repo.head_commit() else {
+    let Ok(head_commit) = repo.find_commit(head) else {
         return true;
     };
     let Ok(time) = head_commit.time() else {
@@ -964,3 +3833,97 @@ fn repo_is_newer(repo: &Repository, target: &Path) -> bool {
     };
     Duration::from_secs(time.seconds as u64) > target_mod_duration
 }
+
+#[cfg(test)]
+mod tests {
+    use gix::objs::tree::Entry;
+
+    use super::*;
+
+    /// A tree of files, each with its name, whether it is executable and its contents.
+    fn write_tree(repo: &Repository, files: &[(&str, bool, &str)]) -> ObjectId {
+        let mut entries = files
+            .iter()
+            .map(|&(name, executable, contents)| Entry {
+                mode: if executable {
+                    EntryKind::BlobExecutable
+                } else {
+                    EntryKind::Blob
+                }
+                .into(),
+                filename: name.into(),
+                oid: repo.write_blob(contents).unwrap().detach(),
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        repo.write_object(gix::objs::Tree { entries })
+            .unwrap()
+            .detach()
+    }
+
+    #[test]
+    fn raw_diff_matches_git_diff() {
+        let dir = std::env::temp_dir().join(format!("stagix-raw-diff-{}", std::process::id()));
+        let repo = gix::init_bare(&dir).unwrap();
+        let moved = "a file that is moved\nwithout changing\n";
+        let old = write_tree(
+            &repo,
+            &[
+                ("changed.txt", false, "one\n"),
+                ("gone.txt", false, "gone\n"),
+                ("old-name.txt", false, moved),
+                ("script.sh", false, "#!/bin/sh\n"),
+            ],
+        );
+        let new = write_tree(
+            &repo,
+            &[
+                ("added.txt", true, "added\n"),
+                ("changed.txt", false, "one\ntwo\n"),
+                ("new-name.txt", false, moved),
+                ("script.sh", true, "#!/bin/sh\n"),
+            ],
+        );
+        let diff = raw_tree_diff(
+            &repo,
+            &repo.find_tree(old).unwrap(),
+            &repo.find_tree(new).unwrap(),
+            &PageOptions::default(),
+        )
+        .unwrap();
+        // as `git diff -M` gives it, but for renames coming first and the hunk headers keeping
+        // their line counts, which `git apply` takes all the same
+        assert_eq!(
+            diff,
+            "diff --git a/old-name.txt b/new-name.txt\n\
+             similarity index 100%\n\
+             rename from old-name.txt\n\
+             rename to new-name.txt\n\
+             diff --git a/added.txt b/added.txt\n\
+             new file mode 100755\n\
+             index 0000000..d5f7fc3\n\
+             --- /dev/null\n\
+             +++ b/added.txt\n\
+             @@ -1,0 +1,1 @@\n\
+             +added\n\
+             diff --git a/changed.txt b/changed.txt\n\
+             index 5626abf..814f4a4 100644\n\
+             --- a/changed.txt\n\
+             +++ b/changed.txt\n\
+             @@ -1,1 +1,2 @@\n \
+             one\n\
+             +two\n\
+             diff --git a/gone.txt b/gone.txt\n\
+             deleted file mode 100644\n\
+             index 286c5f5..0000000\n\
+             --- a/gone.txt\n\
+             +++ /dev/null\n\
+             @@ -1,1 +1,0 @@\n\
+             -gone\n\
+             diff --git a/script.sh b/script.sh\n\
+             old mode 100644\n\
+             new mode 100755\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use build_html::{Container, HtmlContainer as _, Table, TableCell, TableRow};
+use gix::bstr::ByteSlice as _;
+use gix::date::time::CustomFormat;
+use gix::date::time::format::ISO8601;
+use gix::{ObjectId, Repository};
+use tracing::debug;
+
+use crate::html::{Escaped, cell, link};
+use crate::{TimeZone, Topology};
+
+/// Period to split the full history into for the log archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogArchive {
+    /// One page per month, such as `log/2024-05.html`.
+    Month,
+    /// One page per year, such as `log/2024.html`.
+    Year,
+}
+
+impl LogArchive {
+    fn format(self) -> CustomFormat {
+        match self {
+            LogArchive::Month => CustomFormat::new("%Y-%m"),
+            LogArchive::Year => CustomFormat::new("%Y"),
+        }
+    }
+}
+
+/// Split the entire history walked in `topology` into a page per period in `time_zone`, returning
+/// the archive index and each period's page, newest first.
+pub(crate) fn get_log_archive(
+    repo: &Repository,
+    head: ObjectId,
+    period: LogArchive,
+    log_length: Option<usize>,
+    topology: Topology,
+    time_zone: TimeZone,
+) -> anyhow::Result<(Container, Vec<(String, Container)>)> {
+    debug!(repo=?repo.path(), ?period, ?topology, "get log archive");
+    let mut periods: BTreeMap<String, (Table, usize)> = BTreeMap::new();
+    let revs = topology.walk(repo, head)?;
+    for (i, rev) in revs.enumerate() {
+        let rev = rev?;
+        let id = rev.id().to_string();
+        let commit = rev.object()?;
+        let author = commit.author()?;
+        let time = time_zone.apply(author.time()?);
+        let title = Escaped::new(commit.message()?.title.trim().to_str()?);
+        // commits beyond the log length don't get their own page
+        let message_html = if log_length.is_none_or(|l| i < l) {
+            link(&Escaped::new(format!("../commits/{id}.html")), &title)
+        } else {
+            title
+        };
+        let (table, count) = periods
+            .entry(time.format(period.format())?)
+            .or_insert_with(|| {
+                let table = Table::new()
+                    .with_attributes([("id", "log")])
+                    .with_header_row(["Time", "Commit message", "Author", "ID"]);
+                (table, 0)
+            });
+        *count += 1;
+        table.add_custom_body_row(
+            TableRow::new()
+                .with_cell(TableCell::default().with_raw(time.format(ISO8601)?))
+                .with_cell(cell(&message_html))
+                .with_cell(cell(&Escaped::new(author.name)))
+                .with_cell(TableCell::default().with_raw(id)),
+        );
+    }
+
+    let mut index_table = Table::new()
+        .with_attributes([("id", "archive")])
+        .with_header_row(["Period", "Commits"]);
+    let mut pages = Vec::new();
+    for (name, (table, count)) in periods.into_iter().rev() {
+        index_table.add_custom_body_row(
+            TableRow::new()
+                .with_cell(cell(&link(
+                    &Escaped::new(format!("{name}.html")),
+                    &Escaped::new(&name),
+                )))
+                .with_cell(
+                    TableCell::default()
+                        .with_attributes([("class", "num")])
+                        .with_raw(count),
+                ),
+        );
+        pages.push((
+            name,
+            Container::new(build_html::ContainerType::Div).with_table(table),
+        ));
+    }
+    let index = Container::new(build_html::ContainerType::Div).with_table(index_table);
+    Ok((index, pages))
+}
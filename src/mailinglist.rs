@@ -0,0 +1,49 @@
+/// Where patches for a repo are sent, from its `mailinglist` metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MailingList<'a> {
+    /// An address to mail patches to.
+    Address(&'a str),
+    /// A web archive of the list, for lists that can't be mailed directly.
+    Archive(&'a str),
+}
+
+impl<'a> MailingList<'a> {
+    /// Parse the metadata, an address if it has an `@` and no scheme, a URL otherwise.
+    pub(crate) fn new(mailinglist: &'a str) -> Self {
+        if mailinglist.contains('@') && !mailinglist.contains("://") {
+            Self::Address(mailinglist.trim_start_matches("mailto:"))
+        } else {
+            Self::Archive(mailinglist)
+        }
+    }
+
+    /// Link for the "Patches" nav entry.
+    pub(crate) fn href(&self) -> String {
+        match self {
+            Self::Address(address) => format!("mailto:{address}"),
+            Self::Archive(url) => (*url).to_owned(),
+        }
+    }
+}
+
+/// A `mailto:` link to `address` replying to the commit `id` titled `title`.
+pub(crate) fn reply_href(address: &str, id: &str, title: &str) -> String {
+    format!(
+        "mailto:{address}?subject={}&body={}",
+        percent_encode(&format!("Re: {title}")),
+        percent_encode(&format!("commit {id}\n\n")),
+    )
+}
+
+/// Percent encode everything but the unreserved characters, for a `mailto:` query.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use build_html::escape_html;
+use gix::ObjectId;
+
+use crate::{OutputSink, PageOptions};
+
+/// Name of the manifest file written at the root of a repo's output.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// What a generated file was rendered from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// A ref, such as `HEAD`, for pages summarising the repo.
+    Ref(String),
+    /// A single commit, by id.
+    Commit(String),
+    /// A path in the tree, for pages built from its history.
+    Path(String),
+    /// A file in the tree, by path and the id of its blob.
+    Blob { path: String, id: String },
+}
+
+impl Source {
+    pub(crate) fn blob(path: &Path, id: ObjectId) -> Self {
+        Source::Blob {
+            path: path.to_string_lossy().into_owned(),
+            id: id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub source: Source,
+    /// Hex encoded sha1 of the file contents.
+    pub sha1: String,
+}
+
+/// Every file generated for a repo.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn record(&mut self, path: &Path, source: Source, contents: &[u8]) -> anyhow::Result<()> {
+        let mut hasher = gix::hash::hasher(gix::hash::Kind::Sha1);
+        hasher.update(contents);
+        let sha1 = hasher.try_finalize()?.to_string();
+        self.files.push(ManifestEntry {
+            path: path.to_owned(),
+            source,
+            sha1,
+        });
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &[u8]) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    /// Sitemap of every html page, if a base URL is set to make them absolute.
+    pub fn sitemap(&self, options: &PageOptions) -> Option<String> {
+        options.base_url.as_ref()?;
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        );
+        for entry in &self.files {
+            if entry.path.extension().is_none_or(|ext| ext != "html") {
+                continue;
+            }
+            let url = options.url_for(&entry.path)?;
+            xml.push_str(&format!("  <url><loc>{}</loc></url>\n", escape_html(&url)));
+        }
+        xml.push_str("</urlset>\n");
+        Some(xml)
+    }
+}
+
+/// Sink recording everything written through it into a [`Manifest`], attributing files to the
+/// most recently set [`Source`].
+pub(crate) struct ManifestSink<'a> {
+    inner: &'a mut dyn OutputSink,
+    source: Source,
+    pub(crate) manifest: Manifest,
+}
+
+impl<'a> ManifestSink<'a> {
+    pub(crate) fn new(inner: &'a mut dyn OutputSink) -> Self {
+        Self {
+            inner,
+            source: Source::Ref("HEAD".to_owned()),
+            manifest: Manifest::default(),
+        }
+    }
+
+    pub(crate) fn set_source(&mut self, source: Source) {
+        self.source = source;
+    }
+
+    /// Write `contents` to `path` without recording it in the manifest, for files only used by
+    /// stagix itself.
+    pub(crate) fn write_unrecorded(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        self.inner.write(path, contents)
+    }
+
+    /// Remove every file of the `previous` manifest that hasn't been recorded in this one.
+    pub(crate) fn prune(&mut self, previous: &Manifest) -> anyhow::Result<()> {
+        let current = self
+            .manifest
+            .files
+            .iter()
+            .map(|entry| &entry.path)
+            .collect::<HashSet<_>>();
+        for entry in &previous.files {
+            if !current.contains(&entry.path) {
+                self.inner.remove(&entry.path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write out the manifest itself, then finish the inner sink.
+    pub(crate) fn finish_with_manifest(self) -> anyhow::Result<()> {
+        let json = self.manifest.to_json()?;
+        self.inner
+            .write(Path::new(MANIFEST_FILE), json.as_bytes())?;
+        self.inner.finish()
+    }
+}
+
+impl OutputSink for ManifestSink<'_> {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        self.manifest.record(path, self.source.clone(), contents)?;
+        self.inner.write(path, contents)
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.inner.modified(path)
+    }
+
+    fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        self.inner.read(path)
+    }
+
+    fn remove(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.inner.remove(path)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.inner.finish()
+    }
+}
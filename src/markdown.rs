@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use crate::data::{CommitInfo, FileEntry, FileKind, RefInfo, RefKind, RepoSummary};
+use crate::render::Renderer;
+
+/// Renders `.md` pages, for feeding into other static site generators.
+pub(crate) struct Markdown;
+
+impl Markdown {
+    /// The repo's title, description and links to its other pages.
+    fn header(repo: &RepoSummary, to_root: &str) -> String {
+        let mut out = format!("# {}\n\n", escape(&repo.name));
+        if !repo.description.is_empty() {
+            out.push_str(&format!("{}\n\n", escape(&repo.description)));
+        }
+        for url in &repo.clone_urls {
+            out.push_str(&format!("    git clone {url}\n"));
+        }
+        if !repo.clone_urls.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "[Log]({to_root}log.md) | [Files]({to_root}files.md) | [Refs]({to_root}refs.md)\n\n"
+        ));
+        out
+    }
+}
+
+impl Renderer for Markdown {
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn index(&self, repos: &[RepoSummary], repos_url: &str) -> String {
+        let mut out = String::from("# Repositories\n\n");
+        out.push_str("| Name | Description | Owner | Last commit |\n");
+        out.push_str("| --- | --- | --- | --- |\n");
+        for repo in repos {
+            out.push_str(&format!(
+                "| [{}](<{}{}/log.md>) | {} | {} | {} |\n",
+                escape(&repo.name),
+                repos_url,
+                repo.name,
+                escape(&repo.description),
+                escape(&repo.owner),
+                repo.last_commit
+            ));
+        }
+        out
+    }
+
+    fn log(&self, repo: &RepoSummary, commits: &[CommitInfo]) -> String {
+        let mut out = Self::header(repo, "");
+        out.push_str("## Log\n\n");
+        out.push_str("| Time | Commit message | Author | Files | + | - |\n");
+        out.push_str("| --- | --- | --- | --: | --: | --: |\n");
+        for commit in commits {
+            out.push_str(&format!(
+                "| {} | [{}](commits/{}.md) | {} | {} | +{} | -{} |\n",
+                commit.time,
+                escape(&commit.title),
+                commit.id,
+                escape(&commit.author_name),
+                commit.files_changed,
+                commit.lines_added,
+                commit.lines_removed
+            ));
+        }
+        out
+    }
+
+    fn refs(&self, repo: &RepoSummary, refs: &[RefInfo], paged: &HashSet<String>) -> String {
+        let mut out = Self::header(repo, "");
+        for (kind, heading) in [(RefKind::Branch, "Branches"), (RefKind::Tag, "Tags")] {
+            out.push_str(&format!("## {heading}\n\n"));
+            out.push_str("| Name | Last commit time |\n");
+            out.push_str("| --- | --- |\n");
+            for r in refs.iter().filter(|r| r.kind == kind) {
+                let time = if paged.contains(&r.commit) {
+                    format!("[{}](commits/{}.md)", r.time, r.commit)
+                } else {
+                    r.time.clone()
+                };
+                out.push_str(&format!("| {} | {} |\n", escape(&r.name), time));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn files(&self, repo: &RepoSummary, files: &[FileEntry], paged: &HashSet<String>) -> String {
+        let mut out = Self::header(repo, "");
+        out.push_str("## Files\n\n");
+        out.push_str("| Name | Size | Last commit |\n");
+        out.push_str("| --- | --: | --- |\n");
+        for file in files {
+            let name = match file.kind {
+                FileKind::Submodule => escape(&file.path),
+                FileKind::File | FileKind::Executable => {
+                    format!("[{}](<files/{}.md>)", escape(&file.path), file.path)
+                }
+            };
+            let size = match file.kind {
+                FileKind::Submodule => format!("@ {}", file.id),
+                FileKind::File | FileKind::Executable => format!("{}B", file.size),
+            };
+            let last_commit = match &file.last_commit {
+                Some(id) if paged.contains(id) => format!("[{}](commits/{}.md)", &id[..7], id),
+                Some(id) => id[..7].to_owned(),
+                None => String::new(),
+            };
+            out.push_str(&format!("| {name} | {size} | {last_commit} |\n"));
+        }
+        out
+    }
+
+    fn file(&self, repo: &RepoSummary, file: &FileEntry, content: &str, to_root: &str) -> String {
+        let mut out = Self::header(repo, to_root);
+        out.push_str(&format!("## {} ({}B)\n\n", escape(&file.path), file.size));
+        out.push_str(&fenced(content, ""));
+        out
+    }
+
+    fn commit(
+        &self,
+        repo: &RepoSummary,
+        commit: &CommitInfo,
+        diff: &str,
+        paged: &HashSet<String>,
+    ) -> String {
+        let mut out = Self::header(repo, "../");
+        out.push_str(&format!("## {}\n\n", escape(&commit.title)));
+        out.push_str(&format!("- commit {}\n", commit.id));
+        for parent in &commit.parents {
+            if paged.contains(parent) {
+                out.push_str(&format!("- parent [{parent}]({parent}.md)\n"));
+            } else {
+                out.push_str(&format!("- parent {parent}\n"));
+            }
+        }
+        if commit.author_email.is_empty() {
+            out.push_str(&format!("- author {}\n", escape(&commit.author_name)));
+        } else {
+            out.push_str(&format!(
+                "- author {} \\<{}\\>\n",
+                escape(&commit.author_name),
+                escape(&commit.author_email)
+            ));
+        }
+        out.push_str(&format!("- date {}\n\n", commit.time));
+        if let Some(body) = &commit.body {
+            out.push_str(&fenced(body, "text"));
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "{} files changed, {} insertions(+), {} deletions(-)\n\n",
+            commit.files_changed, commit.lines_added, commit.lines_removed
+        ));
+        out.push_str(&fenced(diff, "diff"));
+        out
+    }
+}
+
+/// Escape characters with a meaning in markdown, keeping `text` on one line so it fits in a table
+/// cell or heading.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// `text` in a fenced code block, the fence longer than any run of backticks in it.
+fn fenced(text: &str, info: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let mut out = format!("{fence}{info}\n{text}");
+    if !text.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("{fence}\n"));
+    out
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+/// How the diff of a merge commit is shown on its page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeDiff {
+    /// The full diff against the first parent, the same as any other commit.
+    #[default]
+    FirstParent,
+    /// A diffstat against each parent, without the diffs themselves.
+    PerParent,
+    /// No diff at all, leaving only the raw diff against the first parent.
+    Collapse,
+}
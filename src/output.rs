@@ -0,0 +1,613 @@
+use std::fs::{
+    File, create_dir_all, hard_link, read_link, remove_dir, remove_dir_all, remove_file, rename,
+};
+use std::io::{ErrorKind, Write as _};
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context as _;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+#[cfg(target_os = "linux")]
+use nix::errno::Errno;
+#[cfg(target_os = "linux")]
+use nix::fcntl::{OFlag, RenameFlags, open, renameat2};
+#[cfg(target_os = "linux")]
+use nix::sys::stat::Mode;
+use tracing::{debug, warn};
+
+use crate::safe_path::check_relative_path;
+
+/// Where the generated site for a repo is written to.
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// A directory, with a file per page.
+    Dir(PathBuf),
+    /// A single archive, the format being picked from the extension (`.tar`, `.tar.gz`, `.tgz`
+    /// or `.zip`).
+    Archive(PathBuf),
+}
+
+impl Output {
+    /// Open the default sink for this output.
+    pub fn open(&self) -> anyhow::Result<Box<dyn OutputSink>> {
+        match self {
+            Output::Dir(dir) => {
+                let root = dir.canonicalize().context("canonicalize out dir")?;
+                Ok(Box::new(DirSink { root }))
+            }
+            Output::Archive(path) => {
+                let name = path.to_string_lossy();
+                // check the format first so a mistyped name doesn't leave an empty file behind
+                let format = if name.ends_with(".zip") {
+                    ArchiveFormat::Zip
+                } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+                    ArchiveFormat::TarGz
+                } else if name.ends_with(".tar") {
+                    ArchiveFormat::Tar
+                } else {
+                    anyhow::bail!("unsupported archive format for {:?}", path)
+                };
+                let file = File::create(path).context("create archive")?;
+                let writer: Box<dyn FinishWrite> = match format {
+                    ArchiveFormat::Zip => {
+                        return Ok(Box::new(ZipSink {
+                            zip: Some(zip::ZipWriter::new(file)),
+                        }));
+                    }
+                    ArchiveFormat::TarGz => Box::new(GzEncoder::new(file, Compression::default())),
+                    ArchiveFormat::Tar => Box::new(file),
+                };
+                Ok(Box::new(TarSink {
+                    tar: tar::Builder::new(writer),
+                }))
+            }
+        }
+    }
+
+    /// Open a sink that only replaces the output once everything has been written, so a build
+    /// that fails part way through leaves the previous output untouched.
+    ///
+    /// A directory is built in a staging copy next to it, `.<name>.staging` in its parent, that
+    /// is swapped in when finished.
+    pub fn open_atomic(&self) -> anyhow::Result<Box<dyn OutputSink>> {
+        match self {
+            Output::Dir(dir) => {
+                let root = dir.canonicalize().context("canonicalize out dir")?;
+                let Some(name) = root.file_name() else {
+                    anyhow::bail!("out dir {:?} has no name", root);
+                };
+                // the swap would leave whatever started the build in the old, removed output
+                if std::env::current_dir().is_ok_and(|cwd| cwd == root) {
+                    anyhow::bail!(
+                        "can't swap in the current directory {:?} as the out dir, give a directory \
+                         below it",
+                        root
+                    );
+                }
+                let staging = root.with_file_name(format!(".{}.staging", name.to_string_lossy()));
+                Ok(Box::new(AtomicDirSink {
+                    root,
+                    staging,
+                    staged: false,
+                    finished: false,
+                }))
+            }
+            Output::Archive(_) => self.open(),
+        }
+    }
+}
+
+/// Kind of archive an [`Output::Archive`] is written as.
+enum ArchiveFormat {
+    Zip,
+    TarGz,
+    Tar,
+}
+
+/// Destination that generated files are streamed into.
+///
+/// A local directory is used by default, but this can be implemented to push pages straight to
+/// remote storage (such as an S3 bucket or an SFTP server) with
+/// [`build_repo_pages_to`](crate::build_repo_pages_to).
+pub trait OutputSink {
+    /// Write `contents` to `path`, relative to the root of the output.
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()>;
+
+    /// When `path` was last written, if it exists in the output from a previous build.
+    ///
+    /// Returning `None` always triggers a full build.
+    fn modified(&self, path: &Path) -> Option<SystemTime>;
+
+    /// Contents of `path` in the output from a previous build, used to carry state between
+    /// builds.
+    ///
+    /// Returning `None` renders every commit page again.
+    fn read(&self, _path: &Path) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Remove `path`, left over from a previous build but no longer generated.
+    ///
+    /// Sinks that start out empty, such as archives, have nothing to remove.
+    fn remove(&mut self, _path: &Path) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Flush any buffered output once everything has been written.
+    fn finish(&mut self) -> anyhow::Result<()>;
+}
+
+struct DirSink {
+    root: PathBuf,
+}
+
+impl OutputSink for DirSink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        check_relative_path(path)?;
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.root
+            .join(path)
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        std::fs::read(self.root.join(path)).ok()
+    }
+
+    fn remove(&mut self, path: &Path) -> anyhow::Result<()> {
+        remove_from_dir(&self.root, path)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Directory sink writing to a staging directory, which starts out as a hard linked copy of the
+/// output so the pages that aren't written again are kept.
+struct AtomicDirSink {
+    root: PathBuf,
+    staging: PathBuf,
+    /// Whether the staging directory has been set up, which is left until the first write so
+    /// skipped builds don't copy anything.
+    staged: bool,
+    finished: bool,
+}
+
+impl AtomicDirSink {
+    fn staging(&mut self) -> anyhow::Result<&Path> {
+        if !self.staged {
+            // left over from a build that was interrupted
+            if self.staging.exists() {
+                remove_dir_all(&self.staging).context("remove old staging dir")?;
+            }
+            debug!(root=?self.root, staging=?self.staging, "Linking out dir into staging dir");
+            link_tree(&self.root, &self.staging)
+                .with_context(|| format!("link out dir into staging dir {:?}", self.staging))?;
+            self.staged = true;
+        }
+        Ok(&self.staging)
+    }
+}
+
+impl OutputSink for AtomicDirSink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        check_relative_path(path)?;
+        let path = self.staging()?.join(path);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        // the file may be a link to the one in the live output, which mustn't change
+        match remove_file(&path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+            _ => {}
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.root
+            .join(path)
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        std::fs::read(self.root.join(path)).ok()
+    }
+
+    fn remove(&mut self, path: &Path) -> anyhow::Result<()> {
+        let staging = self.staging()?.to_owned();
+        remove_from_dir(&staging, path)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        if self.staged {
+            debug!(root=?self.root, staging=?self.staging, "Swapping staging dir into out dir");
+            swap_dirs(&self.staging, &self.root)?;
+            // now holding the previous output
+            remove_dir_all(&self.staging)?;
+        }
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for AtomicDirSink {
+    fn drop(&mut self) {
+        if self.staged
+            && !self.finished
+            && let Err(error) = remove_dir_all(&self.staging)
+        {
+            warn!(staging=?self.staging, %error, "Failed to remove staging dir");
+        }
+    }
+}
+
+/// Remove the file at `path` under `root` if it exists, along with any directories that leaves
+/// empty.
+pub(crate) fn remove_from_dir(root: &Path, path: &Path) -> anyhow::Result<()> {
+    check_relative_path(path)?;
+    let path = root.join(path);
+    debug!(?path, "removing stale output");
+    match remove_file(&path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+        _ => {}
+    }
+    let mut dir = path.parent();
+    while let Some(parent) = dir
+        && parent != root
+        && parent
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_none())
+    {
+        remove_dir(parent)?;
+        dir = parent.parent();
+    }
+    Ok(())
+}
+
+/// Recreate the tree of `from` at `to`, hard linking files rather than copying them.
+fn link_tree(from: &Path, to: &Path) -> anyhow::Result<()> {
+    create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            link_tree(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            symlink(read_link(entry.path())?, target)?;
+        } else {
+            hard_link(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Swap the directories `a` and `b`, which must be on the same filesystem.
+///
+/// On Linux this is atomic, with `renameat2`. Elsewhere, or on filesystems without it, `b` is
+/// moved aside for `a` to be renamed into its place, leaving a short window without `b`.
+pub(crate) fn swap_dirs(a: &Path, b: &Path) -> anyhow::Result<()> {
+    #[cfg(target_os = "linux")]
+    match exchange_dirs(a, b) {
+        Ok(()) => return Ok(()),
+        Err(Errno::EINVAL | Errno::ENOSYS | Errno::EOPNOTSUPP) => {
+            debug!(?a, ?b, "renameat2 unsupported, swapping dirs by renaming");
+        }
+        Err(error) => return Err(error.into()),
+    }
+    rename_dirs(a, b)
+}
+
+#[cfg(target_os = "linux")]
+fn exchange_dirs(a: &Path, b: &Path) -> nix::Result<()> {
+    let a_fd = open(
+        a,
+        OFlag::O_DIRECTORY | OFlag::O_PATH,
+        Mode::S_IWUSR | Mode::S_IWGRP,
+    )?;
+    let b_fd = open(
+        b,
+        OFlag::O_DIRECTORY | OFlag::O_PATH,
+        Mode::S_IWUSR | Mode::S_IWGRP,
+    )?;
+    renameat2(a_fd, a, b_fd, b, RenameFlags::RENAME_EXCHANGE)
+}
+
+/// Swap `a` and `b` with three renames, putting `b` back if `a` can't take its place.
+fn rename_dirs(a: &Path, b: &Path) -> anyhow::Result<()> {
+    let Some(name) = b.file_name() else {
+        anyhow::bail!("dir {:?} has no name", b);
+    };
+    let aside = b.with_file_name(format!(".{}.old", name.to_string_lossy()));
+    // left over from a swap that was interrupted
+    if aside.exists() {
+        remove_dir_all(&aside).context("remove old moved aside dir")?;
+    }
+    rename(b, &aside).context("move dir aside")?;
+    if let Err(error) = rename(a, b) {
+        rename(&aside, b).context("move dir back")?;
+        return Err(error).context("move dir into place");
+    }
+    rename(&aside, a).context("move old dir into place")?;
+    Ok(())
+}
+
+/// Sink that writes nothing, instead comparing what would be written against an existing
+/// directory.
+pub(crate) struct VerifySink {
+    root: PathBuf,
+    /// Paths that are missing or differ from what would be written.
+    pub(crate) stale: Vec<PathBuf>,
+}
+
+impl VerifySink {
+    pub(crate) fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            stale: Vec::new(),
+        }
+    }
+}
+
+impl OutputSink for VerifySink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let existing = std::fs::read(self.root.join(path)).ok();
+        if existing.as_deref() != Some(contents) {
+            debug!(?path, "output is stale");
+            self.stale.push(path.to_owned());
+        }
+        Ok(())
+    }
+
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        // always render everything to compare it
+        None
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+struct TarSink {
+    tar: tar::Builder<Box<dyn FinishWrite>>,
+}
+
+impl OutputSink for TarSink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        debug!(?path, "appending file to tar");
+        check_relative_path(path)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(now_secs());
+        self.tar.append_data(&mut header, path, contents)?;
+        Ok(())
+    }
+
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.tar.finish()?;
+        self.tar.get_mut().finish_write()
+    }
+}
+
+struct ZipSink {
+    /// Taken when finishing the archive.
+    zip: Option<zip::ZipWriter<File>>,
+}
+
+impl ZipSink {
+    fn zip(&mut self) -> anyhow::Result<&mut zip::ZipWriter<File>> {
+        self.zip.as_mut().context("zip archive already finished")
+    }
+}
+
+impl OutputSink for ZipSink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        debug!(?path, "appending file to zip");
+        check_relative_path(path)?;
+        let zip = self.zip()?;
+        zip.start_file(
+            path.to_string_lossy(),
+            zip::write::SimpleFileOptions::default(),
+        )?;
+        zip.write_all(contents)?;
+        Ok(())
+    }
+
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        if let Some(zip) = self.zip.take() {
+            zip.finish()?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// A writer that may need finalising, such as writing a compression trailer.
+trait FinishWrite: std::io::Write {
+    fn finish_write(&mut self) -> anyhow::Result<()>;
+}
+
+impl FinishWrite for File {
+    fn finish_write(&mut self) -> anyhow::Result<()> {
+        self.sync_all()?;
+        Ok(())
+    }
+}
+
+impl FinishWrite for GzEncoder<File> {
+    fn finish_write(&mut self) -> anyhow::Result<()> {
+        self.try_finish()?;
+        self.get_ref().sync_all()?;
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::MetadataExt as _;
+
+    use super::*;
+
+    /// An empty directory to build into, named `name` under a fresh one of its own.
+    fn out_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("stagix-output-{}-{name}", std::process::id()))
+            .join(name);
+        if let Some(parent) = dir.parent()
+            && parent.exists()
+        {
+            remove_dir_all(parent).unwrap();
+        }
+        create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    fn staging_of(root: &Path) -> PathBuf {
+        let name = root.file_name().unwrap().to_string_lossy();
+        root.with_file_name(format!(".{name}.staging"))
+    }
+
+    fn read(path: impl AsRef<Path>) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn staging_links_the_output() {
+        let root = out_dir("links");
+        std::fs::write(root.join("kept.html"), "kept").unwrap();
+        std::fs::write(root.join("changed.html"), "old").unwrap();
+        let mut sink = Output::Dir(root.clone()).open_atomic().unwrap();
+        sink.write(Path::new("changed.html"), b"new").unwrap();
+
+        let staging = staging_of(&root);
+        let inode = |path: PathBuf| path.metadata().unwrap().ino();
+        assert_eq!(
+            inode(staging.join("kept.html")),
+            inode(root.join("kept.html"))
+        );
+        // writing replaces the link rather than changing the live page
+        assert_eq!(read(staging.join("changed.html")), "new");
+        assert_eq!(read(root.join("changed.html")), "old");
+        assert_eq!(sink.read(Path::new("changed.html")).unwrap(), b"old");
+
+        sink.finish().unwrap();
+        assert_eq!(read(root.join("changed.html")), "new");
+        assert_eq!(read(root.join("kept.html")), "kept");
+        assert!(!staging.exists());
+    }
+
+    #[test]
+    fn skipped_build_stages_nothing() {
+        let root = out_dir("skipped");
+        let mut sink = Output::Dir(root.clone()).open_atomic().unwrap();
+        sink.finish().unwrap();
+        assert!(!staging_of(&root).exists());
+    }
+
+    #[test]
+    fn failed_build_leaves_the_output() {
+        let root = out_dir("failed");
+        std::fs::write(root.join("page.html"), "old").unwrap();
+        let mut sink = Output::Dir(root.clone()).open_atomic().unwrap();
+        sink.write(Path::new("page.html"), b"new").unwrap();
+        drop(sink);
+        assert_eq!(read(root.join("page.html")), "old");
+        assert!(!staging_of(&root).exists());
+    }
+
+    #[test]
+    fn pruning_waits_for_the_swap() {
+        let root = out_dir("pruning");
+        create_dir_all(root.join("files/dir")).unwrap();
+        std::fs::write(root.join("files/dir/gone.html"), "gone").unwrap();
+        std::fs::write(root.join("files/kept.html"), "kept").unwrap();
+        let mut sink = Output::Dir(root.clone()).open_atomic().unwrap();
+        sink.remove(Path::new("files/dir/gone.html")).unwrap();
+        assert!(root.join("files/dir/gone.html").exists());
+        assert!(!staging_of(&root).join("files/dir").exists());
+
+        sink.finish().unwrap();
+        assert!(!root.join("files/dir").exists());
+        assert_eq!(read(root.join("files/kept.html")), "kept");
+    }
+
+    #[test]
+    fn unsafe_paths_are_refused() {
+        let root = out_dir("unsafe");
+        let mut sink = Output::Dir(root.clone()).open_atomic().unwrap();
+        assert!(sink.write(Path::new("../escaped.html"), b"").is_err());
+        assert!(sink.remove(Path::new("/etc/passwd")).is_err());
+    }
+
+    #[test]
+    fn current_dir_is_refused() {
+        let cwd = std::env::current_dir().unwrap();
+        assert!(Output::Dir(cwd).open_atomic().is_err());
+    }
+
+    #[test]
+    fn swap_exchanges_dirs() {
+        let a = out_dir("swap-a");
+        let b = out_dir("swap-b");
+        std::fs::write(a.join("a"), "a").unwrap();
+        std::fs::write(b.join("b"), "b").unwrap();
+        swap_dirs(&a, &b).unwrap();
+        assert!(a.join("b").exists() && !a.join("a").exists());
+        assert!(b.join("a").exists() && !b.join("b").exists());
+    }
+
+    #[test]
+    fn rename_fallback_exchanges_dirs() {
+        let a = out_dir("rename-a");
+        let b = out_dir("rename-b");
+        std::fs::write(a.join("a"), "a").unwrap();
+        std::fs::write(b.join("b"), "b").unwrap();
+        // left over from an interrupted swap
+        create_dir_all(b.with_file_name(".rename-b.old")).unwrap();
+        rename_dirs(&a, &b).unwrap();
+        assert!(a.join("b").exists() && !a.join("a").exists());
+        assert!(b.join("a").exists() && !b.join("b").exists());
+        assert!(!b.with_file_name(".rename-b.old").exists());
+    }
+
+    #[test]
+    fn rename_fallback_puts_back_on_failure() {
+        let b = out_dir("rename-back");
+        std::fs::write(b.join("b"), "b").unwrap();
+        let missing = b.with_file_name("missing");
+        assert!(rename_dirs(&missing, &b).is_err());
+        assert_eq!(read(b.join("b")), "b");
+    }
+}
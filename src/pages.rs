@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{
+    create_dir_all, read, read_dir, remove_dir_all, remove_file, symlink_metadata, write,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Context as _;
+use gix::Tree;
+use gix::bstr::ByteSlice as _;
+use gix::traverse::tree::Recorder;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::html::redirect_html;
+use crate::output::remove_from_dir;
+use crate::safe_path::{check_relative_path, retain_safe_paths};
+
+/// Name of the manifest kept in a repo's working directory between runs.
+pub(crate) const PAGES_MANIFEST_FILE: &str = "manifest.json";
+
+/// Name of the manifest of the staging copy in a repo's working directory, which holds what was
+/// published before the last run.
+pub(crate) const STAGING_MANIFEST_FILE: &str = "staging.json";
+
+/// The blob id of every file copied from a repo's docs, for the next run to only write the files
+/// that changed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct PagesManifest {
+    /// Directory the files were copied to, which moves when a custom domain is set.
+    #[serde(default)]
+    pub(crate) out_dir: PathBuf,
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl PagesManifest {
+    /// The manifest at `path`, `None` if there isn't a readable one.
+    pub(crate) fn load(path: &Path) -> Option<Self> {
+        let json = read(path).ok()?;
+        match serde_json::from_slice(&json) {
+            Ok(manifest) => Some(manifest),
+            Err(error) => {
+                warn!(?path, %error, "Failed to parse pages manifest, copying every file");
+                None
+            }
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The same files, copied to `out_dir` instead.
+    pub(crate) fn with_out_dir(self, out_dir: PathBuf) -> Self {
+        Self { out_dir, ..self }
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Remove the manifest at `path`, while what it lists is being changed.
+    pub(crate) fn remove(path: &Path) -> anyhow::Result<()> {
+        match remove_file(path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Name of a repo's directory in the working directory, which has a hash of the repo's path as
+/// repos in different directories can share a name.
+pub(crate) fn working_dir_name(repo_path: &Path, repo_name: &OsStr) -> anyhow::Result<String> {
+    let mut hasher = gix::hash::hasher(gix::hash::Kind::Sha1);
+    hasher.update(repo_path.as_os_str().as_encoded_bytes());
+    let hash = hasher.try_finalize()?;
+    Ok(format!(
+        "{}-{}",
+        repo_name.to_string_lossy(),
+        hash.to_hex_with_len(12)
+    ))
+}
+
+/// The ref and directory of a repo's `pages`, which is a directory of `HEAD`, or a ref and a
+/// directory of it separated by a colon, such as `refs/heads/pages:docs/`.
+pub(crate) fn split_pages_ref(pages: &str) -> (Option<&str>, &str) {
+    match pages.split_once(':') {
+        Some((pages_ref, dir)) => (Some(pages_ref), dir.trim_end_matches('/')),
+        None => (None, pages),
+    }
+}
+
+/// Copy the files of `tree` into `out_dir`, only writing those whose blob changed since the
+/// `previous` copy and removing those no longer in the tree. Without a previous manifest the
+/// `out_dir` is cleared and every file written.
+///
+/// Files are written in place, so `out_dir` should be a staging copy swapped in once synced.
+pub(crate) fn sync_tree_to_dir(
+    tree: &Tree<'_>,
+    out_dir: &Path,
+    previous: Option<&PagesManifest>,
+) -> anyhow::Result<PagesManifest> {
+    debug!(?out_dir, "Syncing tree to out dir");
+    let mut recorder = Recorder::default();
+    tree.traverse().breadthfirst(&mut recorder)?;
+    retain_safe_paths(&mut recorder.records);
+    let mut files = Vec::new();
+    for record in recorder.records {
+        if record.mode.is_tree() {
+            continue;
+        }
+        if !record.mode.is_blob() {
+            warn!(path = ?record.filepath, "unmatched mode in sync_tree_to_dir, not copying it");
+            continue;
+        }
+        files.push((PathBuf::from(record.filepath.to_str()?), record.oid));
+    }
+    let manifest = PagesManifest {
+        out_dir: out_dir.to_owned(),
+        files: files
+            .iter()
+            .map(|(path, id)| (path.clone(), id.to_string()))
+            .collect(),
+    };
+
+    match previous {
+        Some(previous) => {
+            for path in previous
+                .files
+                .keys()
+                .filter(|path| !manifest.files.contains_key(*path))
+            {
+                remove_from_dir(out_dir, path)
+                    .with_context(|| format!("remove {}", path.display()))?;
+            }
+        }
+        None => {
+            if out_dir.exists() {
+                remove_dir_all(out_dir)?;
+            }
+        }
+    }
+    create_dir_all(out_dir)?;
+
+    let mut written = 0;
+    for (path, id) in files {
+        let out_path = out_dir.join(&path);
+        let unchanged = previous
+            .and_then(|previous| previous.files.get(&path))
+            .is_some_and(|previous_id| *previous_id == id.to_string());
+        if unchanged && out_path.is_file() {
+            continue;
+        }
+        // a directory in the previous copy that is now a file
+        if out_path.is_dir() {
+            remove_dir_all(&out_path)?;
+        }
+        if let Some(parent) = out_path.parent() {
+            create_dir_all(parent)?;
+        }
+        let blob = tree.repo.find_object(id)?;
+        write(&out_path, &blob.data).with_context(|| format!("write {}", out_path.display()))?;
+        written += 1;
+    }
+    debug!(
+        written,
+        files = manifest.files.len(),
+        "Wrote changed files to out dir"
+    );
+    Ok(manifest)
+}
+
+/// Replace `dir` with a page redirecting to `url`, for the old path of pages moved to a custom
+/// domain.
+pub(crate) fn write_redirect_stub(dir: &Path, url: &str) -> anyhow::Result<()> {
+    debug!(?dir, url, "Writing redirect stub");
+    if dir.exists() {
+        remove_dir_all(dir)?;
+    }
+    create_dir_all(dir)?;
+    write(dir.join("index.html"), redirect_html(url))?;
+    Ok(())
+}
+
+/// The directory a pages command wrote its site to, `pages_output` below `build_dir`. Symlinks
+/// are refused, in the output or leading to it, as a command could otherwise publish any
+/// directory on the host.
+pub(crate) fn built_site_dir(build_dir: &Path, pages_output: &str) -> anyhow::Result<PathBuf> {
+    let pages_output = Path::new(pages_output);
+    check_relative_path(pages_output)?;
+    let site_dir = build_dir.join(pages_output);
+    if !symlink_metadata(&site_dir).is_ok_and(|metadata| metadata.is_dir()) {
+        anyhow::bail!("pages command didn't write {}", site_dir.display());
+    }
+    if !site_dir
+        .canonicalize()?
+        .starts_with(build_dir.canonicalize()?)
+    {
+        anyhow::bail!(
+            "pages output {} is outside the build",
+            pages_output.display()
+        );
+    }
+    check_no_symlinks(&site_dir)?;
+    Ok(site_dir)
+}
+
+/// Fail if there is a symlink anywhere below `dir`.
+fn check_no_symlinks(dir: &Path) -> anyhow::Result<()> {
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            anyhow::bail!("refusing to publish symlink {}", entry.path().display());
+        }
+        if file_type.is_dir() {
+            check_no_symlinks(&entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Run the `command` building a repo's docs with `sh` in `dir`, wrapped in the `sandbox` command
+/// if given, such as `bwrap` or `firejail` with their arguments.
+pub(crate) fn run_pages_command(
+    command: &str,
+    sandbox: &[String],
+    dir: &Path,
+) -> anyhow::Result<()> {
+    debug!(command, ?sandbox, ?dir, "Running pages command");
+    let mut args = sandbox
+        .iter()
+        .map(String::as_str)
+        .chain(["sh", "-c", command]);
+    let program = args.next().unwrap_or("sh");
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("run {program}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "pages command {command:?} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
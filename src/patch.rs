@@ -0,0 +1,62 @@
+use gix::Commit;
+use gix::bstr::ByteSlice as _;
+use gix::date::time::format::RFC2822;
+
+use crate::EmailDisplay;
+
+/// The commit as a `git format-patch` email, from its raw `diff` against its first parent, so it
+/// can be applied with `git am`.
+pub(crate) fn format_patch(
+    commit: &Commit<'_>,
+    diff: &str,
+    emails: EmailDisplay,
+) -> anyhow::Result<String> {
+    let author = commit.author()?;
+    let message = commit.message()?;
+    // `git am` needs an address, left empty when hidden
+    let email = emails.text(&author.email.to_string()).unwrap_or_default();
+    let mut patch = format!(
+        "From {} Mon Sep 17 00:00:00 2001\nFrom: {} <{}>\nDate: {}\nSubject: [PATCH] {}\n\n",
+        commit.id,
+        author.name,
+        email,
+        author.time()?.format(RFC2822)?,
+        message.title.trim().to_str_lossy(),
+    );
+    if let Some(body) = message.body {
+        patch.push_str(&format!("{}\n\n", body.trim().to_str_lossy()));
+    }
+    let (files, insertions, deletions) = diff_stats(diff);
+    patch.push_str(&format!(
+        "---\n {files} files changed, {insertions} insertions(+), {deletions} deletions(-)\n\n"
+    ));
+    patch.push_str(diff);
+    patch.push_str("-- \nstagix\n\n");
+    Ok(patch)
+}
+
+/// Whether a raw diff has binary files, whose contents it leaves out.
+pub(crate) fn has_binary_changes(diff: &str) -> bool {
+    diff.lines()
+        .any(|line| line.starts_with("Binary files ") && line.ends_with(" differ"))
+}
+
+/// Files changed, lines added and lines removed in a raw diff.
+fn diff_stats(diff: &str) -> (usize, usize, usize) {
+    let (mut files, mut insertions, mut deletions) = (0, 0, 0);
+    // the ---/+++ file headers come before the first hunk of each file
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            files += 1;
+            in_hunk = false;
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if in_hunk && line.starts_with('+') {
+            insertions += 1;
+        } else if in_hunk && line.starts_with('-') {
+            deletions += 1;
+        }
+    }
+    (files, insertions, deletions)
+}
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+/// How often progress is logged during a long running step.
+const INTERVAL: Duration = Duration::from_secs(2);
+
+/// Periodically logs how far through a step a build is, such as `rendered 3200/41000 commits`,
+/// so a big repo doesn't look like it has hung.
+pub(crate) struct Progress {
+    /// What is being done, in the past tense.
+    action: &'static str,
+    /// What is being counted, in the plural.
+    items: &'static str,
+    total: Option<usize>,
+    done: usize,
+    last_logged: Instant,
+}
+
+impl Progress {
+    pub(crate) fn new(action: &'static str, items: &'static str, total: Option<usize>) -> Self {
+        Self {
+            action,
+            items,
+            total,
+            done: 0,
+            last_logged: Instant::now(),
+        }
+    }
+
+    pub(crate) fn inc(&mut self) {
+        self.update(self.done + 1);
+    }
+
+    /// Set the number of items done so far, logging it if it has been a while since the last log.
+    pub(crate) fn update(&mut self, done: usize) {
+        self.done = done;
+        if self.last_logged.elapsed() < INTERVAL {
+            return;
+        }
+        self.last_logged = Instant::now();
+        let (action, items) = (self.action, self.items);
+        match self.total {
+            Some(total) => info!(action, done, total, "{action} {done}/{total} {items}"),
+            None => info!(action, done, "{action} {done} {items}"),
+        }
+    }
+}
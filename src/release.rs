@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use build_html::{Container, HtmlContainer as _, HtmlElement};
+use gix::bstr::ByteSlice as _;
+use gix::date::time::format::ISO8601;
+use gix::{ObjectId, Repository};
+use tracing::debug;
+
+use crate::html::{Bold, Escaped, link};
+use crate::signature::tag_signature;
+use crate::{ComparePage, FileLinks, PageOptions, add_tree_diff, file_page_paths, to_root_path};
+
+/// The release notes of an annotated tag with a message.
+pub(crate) struct ReleasePage {
+    pub(crate) tag: String,
+    pub(crate) container: Container,
+}
+
+impl ReleasePage {
+    pub(crate) fn path(&self) -> PathBuf {
+        release_path(&self.tag)
+    }
+}
+
+fn release_path(tag: &str) -> PathBuf {
+    PathBuf::from("tags").join(format!("{tag}.html"))
+}
+
+/// A page per annotated tag with a message, showing the message, tagger, signature and target
+/// commit, linked to if in `paged_tips`, along with the diffstat against the previous tag from
+/// `compares`.
+pub(crate) fn get_releases(
+    repo: &Repository,
+    head: ObjectId,
+    compares: &[ComparePage],
+    paged_tips: &[ObjectId],
+    options: &PageOptions,
+) -> anyhow::Result<Vec<ReleasePage>> {
+    debug!(repo=?repo.path(), "get releases");
+    let head_files = file_page_paths(&repo.find_commit(head)?.tree()?)?;
+    let mut pages = Vec::new();
+    for tag in repo.references()?.tags()? {
+        let mut tag = tag.map_err(|e| anyhow::anyhow!(e))?;
+        let Some(id) = tag.try_id().map(|id| id.detach()) else {
+            continue;
+        };
+        let object = repo.find_object(id)?;
+        if object.kind != gix::object::Kind::Tag {
+            continue;
+        }
+        let tag_object = object.into_tag();
+        let decoded = tag_object.decode()?;
+        let message = decoded.message.trim();
+        if message.is_empty() {
+            continue;
+        }
+        let name = tag.name().shorten().to_string();
+        // tags of trees and blobs have no history to show
+        let Ok(commit) = tag.peel_to_commit() else {
+            continue;
+        };
+        let to_root = to_root_path(&release_path(&name));
+
+        let mut container =
+            Container::new(build_html::ContainerType::Div).with_attributes([("id", "content")]);
+        let mut pre = HtmlElement::new(build_html::HtmlTag::Div);
+        pre.add_html(Bold::from("tag "));
+        pre.add_raw(Escaped::new(&name));
+        pre.add_child("\n".into());
+        if let Some(tagger) = decoded.tagger {
+            pre.add_html(Bold::from("tagger "));
+            pre.add_raw(
+                options
+                    .emails
+                    .html_ident(&tagger.name.to_string(), &tagger.email.to_string()),
+            );
+            pre.add_raw("\n");
+            pre.add_html(Bold::from("date "));
+            pre.add_child(
+                options
+                    .time_zone
+                    .apply(tagger.time()?)
+                    .format(ISO8601)?
+                    .into(),
+            );
+            pre.add_child("\n".into());
+        }
+        if let Some(signature) = tag_signature(repo, id, options.allowed_signers.as_deref())? {
+            pre.add_html(Bold::from("signature "));
+            pre.add_raw(signature.to_html());
+            pre.add_child("\n".into());
+        }
+        pre.add_html(Bold::from("commit "));
+        if paged_tips.contains(&commit.id) {
+            pre.add_link(
+                format!("{to_root}commits/{}.html", commit.id),
+                commit.id.to_string(),
+            );
+        } else {
+            pre.add_child(commit.id.to_string().into());
+        }
+        pre.add_child("\n".into());
+        container.add_preformatted(pre);
+
+        let (title, body) = message.split_once_str("\n").unwrap_or((message, &b""[..]));
+        container.add_paragraph(Escaped::new(title.to_str_lossy()));
+        container.add_paragraph(Escaped::new(body.trim().to_str_lossy()));
+
+        if let Some(compare) = compares.iter().find(|c| c.to == name) {
+            container.add_header(
+                2,
+                format!(
+                    "Changes since {}",
+                    link(
+                        &Escaped::new(format!("{to_root}{}", compare.path().to_string_lossy())),
+                        &Escaped::new(&compare.from),
+                    )
+                ),
+            );
+            let from = repo
+                .find_reference(format!("refs/tags/{}", compare.from).as_str())?
+                .peel_to_commit()?;
+            add_tree_diff(
+                &mut container,
+                repo,
+                &from.tree()?,
+                &commit.tree()?,
+                &mut FileLinks::new(&head_files, &to_root),
+                true,
+                options,
+            )?;
+        }
+        pages.push(ReleasePage {
+            tag: name,
+            container,
+        });
+    }
+    Ok(pages)
+}
+
+/// Path of the release notes of the tag `name`, if it has a page in `releases`.
+pub(crate) fn release_path_of(releases: &[ReleasePage], name: &str) -> Option<PathBuf> {
+    releases
+        .iter()
+        .find(|r| r.tag == name)
+        .map(ReleasePage::path)
+}
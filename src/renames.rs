@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use gix::object::tree::diff::{Action, Change};
+use gix::{ObjectId, Repository};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::html::redirect_html;
+use crate::safe_path::is_safe_tree_path;
+use crate::{OutputSink, PageOptions, to_root_path};
+
+/// Name of the renames cache written at the root of a repo's output.
+pub(crate) const RENAMES_CACHE_FILE: &str = ".stagix-renames";
+
+/// The files each commit renamed, as found by a previous build, so the history needn't be diffed
+/// again for them.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RenamesCache {
+    /// Similarity the renames were found with, the cache being dropped when it changes.
+    similarity: u8,
+    /// The old and new paths of the files renamed by each commit, keyed by its id.
+    commits: HashMap<String, Vec<(String, String)>>,
+}
+
+impl RenamesCache {
+    /// Load the cache from the previous build in `sink`, empty if it was built with another
+    /// similarity.
+    pub(crate) fn load(sink: &dyn OutputSink, options: &PageOptions) -> Self {
+        let similarity = rename_similarity(options);
+        let cache = sink
+            .read(Path::new(RENAMES_CACHE_FILE))
+            .and_then(|json| serde_json::from_slice::<Self>(&json).ok())
+            .filter(|cache| cache.similarity == similarity);
+        debug!(cached = ?cache.as_ref().map(|cache| cache.commits.len()), "loaded renames cache");
+        cache.unwrap_or(Self {
+            similarity,
+            commits: HashMap::new(),
+        })
+    }
+
+    pub(crate) fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+fn rename_similarity(options: &PageOptions) -> u8 {
+    options.rename_similarity.unwrap_or(50).min(100)
+}
+
+/// Paths files were renamed away from in the history of `tip`, each mapped to where the file
+/// ended up, following any later renames of it. Only commits not in the `cache` are diffed, the
+/// cache being left with just the commits walked.
+pub(crate) fn renamed_paths(
+    repo: &Repository,
+    tip: ObjectId,
+    log_length: Option<usize>,
+    cache: &mut RenamesCache,
+    options: &PageOptions,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    debug!(repo=?repo.path(), %tip, log_length, "find renamed paths");
+    let mut renames = BTreeMap::<String, String>::new();
+    let mut commits = HashMap::new();
+    // newest first, so later renames of a file are known when reaching earlier ones
+    let revs = options.topology.walk(repo, tip)?;
+    for rev in revs.take(log_length.unwrap_or(usize::MAX)) {
+        let id = rev?.id;
+        let key = id.to_string();
+        let commit_renames = match cache.commits.remove(&key) {
+            Some(commit_renames) => commit_renames,
+            None => commit_renames(repo, id, cache.similarity)?,
+        };
+        for (from, to) in &commit_renames {
+            let to = renames.get(to).cloned().unwrap_or_else(|| to.clone());
+            renames.entry(from.clone()).or_insert(to);
+        }
+        commits.insert(key, commit_renames);
+    }
+    cache.commits = commits;
+    Ok(renames)
+}
+
+/// The old and new paths of the files commit `id` renamed from its first parent.
+fn commit_renames(
+    repo: &Repository,
+    id: ObjectId,
+    similarity: u8,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let commit = repo.find_commit(id)?;
+    let parent_tree = match commit.parent_ids().next() {
+        Some(parent_id) => repo.find_commit(parent_id)?.tree()?,
+        None => return Ok(Vec::new()),
+    };
+    let rewrites = gix::diff::Rewrites {
+        copies: None,
+        percentage: Some(f32::from(similarity) / 100.0),
+        ..Default::default()
+    };
+    let mut renames = Vec::new();
+    parent_tree
+        .changes()?
+        .options(|o| {
+            o.track_rewrites(Some(rewrites));
+        })
+        .for_each_to_obtain_tree(&commit.tree()?, |change| -> anyhow::Result<Action> {
+            if let Change::Rewrite {
+                source_location,
+                location,
+                entry_mode,
+                copy: false,
+                ..
+            } = change
+                && entry_mode.is_blob_or_symlink()
+                && is_safe_tree_path(source_location)
+                && is_safe_tree_path(location)
+            {
+                renames.push((source_location.to_string(), location.to_string()));
+            }
+            Ok(Action::Continue)
+        })?;
+    Ok(renames)
+}
+
+/// A redirect from the files page of each renamed path to that of where the file is now, for
+/// those whose new page exists and whose old one hasn't been taken by a new file, as
+/// `exists` says.
+pub(crate) fn rename_stubs(
+    renames: &BTreeMap<String, String>,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<(PathBuf, String)> {
+    renames
+        .iter()
+        .filter_map(|(from, to)| {
+            let from = PathBuf::from("files").join(format!("{from}.html"));
+            let to = PathBuf::from("files").join(format!("{to}.html"));
+            if exists(&from) || !exists(&to) {
+                return None;
+            }
+            let url = format!("{}{}", to_root_path(&from), to.to_string_lossy());
+            Some((from, redirect_html(&url)))
+        })
+        .collect()
+}
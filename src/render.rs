@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use gix::{ObjectId, Repository};
+use tracing::{debug, warn};
+
+use crate::data::{
+    CollectOptions, CommitInfo, FileEntry, FileKind, RefInfo, RepoSummary, collect_diff,
+    collect_files, collect_log, collect_refs, collect_summary,
+};
+use crate::manifest::ManifestSink;
+use crate::{Meta, OutputSink, PageOptions, Source, to_root_path};
+
+/// Renders the data gathered for a repo into pages of a text format, written alongside the html
+/// pages under the same paths with the renderer's extension.
+///
+/// The html pages have far more on them, such as the graph, diffstats and signatures, so they
+/// are built by their own functions rather than a renderer, with only the refs page built from
+/// the same [`RefInfo`].
+///
+/// Links between pages are relative, `to_root` leading from the page back to the repo's root.
+pub(crate) trait Renderer {
+    /// Extension of the rendered pages, without the dot.
+    fn extension(&self) -> &'static str;
+
+    /// The index of every repo, each of them under `repos_url`.
+    fn index(&self, repos: &[RepoSummary], repos_url: &str) -> String;
+
+    fn log(&self, repo: &RepoSummary, commits: &[CommitInfo]) -> String;
+
+    /// The refs, linking to the commits in `paged`.
+    fn refs(&self, repo: &RepoSummary, refs: &[RefInfo], paged: &HashSet<String>) -> String;
+
+    /// The list of files, linking to the commits in `paged`.
+    fn files(&self, repo: &RepoSummary, files: &[FileEntry], paged: &HashSet<String>) -> String;
+
+    /// The page of a single text file.
+    fn file(&self, repo: &RepoSummary, file: &FileEntry, content: &str, to_root: &str) -> String;
+
+    /// The page of a commit with its raw diff, linking to the parents in `paged`.
+    fn commit(
+        &self,
+        repo: &RepoSummary,
+        commit: &CommitInfo,
+        diff: &str,
+        paged: &HashSet<String>,
+    ) -> String;
+}
+
+/// Write the log, refs, files and commit pages of the repo at `head` with `renderer`.
+///
+/// Commit pages already in the manifest were kept from the previous build along with the cached
+/// html page of the commit, so aren't rendered again.
+pub(crate) fn write_pages(
+    renderer: &dyn Renderer,
+    repo: &Repository,
+    meta: &Meta,
+    head: ObjectId,
+    log_length: Option<usize>,
+    options: &PageOptions,
+    sink: &mut ManifestSink<'_>,
+) -> anyhow::Result<()> {
+    let extension = renderer.extension();
+    debug!(repo=?repo.path(), extension, "writing rendered pages");
+    let page = |name: &str| PathBuf::from(format!("{name}.{extension}"));
+    let summary = collect_summary(repo, meta)?;
+    let collect = CollectOptions {
+        log_length,
+        topology: options.topology,
+        emails: options.emails,
+    };
+    let commits = collect_log(repo, head, &collect)?;
+    let paged = commits
+        .iter()
+        .map(|commit| commit.id.clone())
+        .collect::<HashSet<_>>();
+
+    sink.write(&page("log"), renderer.log(&summary, &commits).as_bytes())?;
+    let refs = collect_refs(repo)?;
+    sink.write(
+        &page("refs"),
+        renderer.refs(&summary, &refs, &paged).as_bytes(),
+    )?;
+    let files = collect_files(repo, head, &collect)?;
+    sink.write(
+        &page("files"),
+        renderer.files(&summary, &files, &paged).as_bytes(),
+    )?;
+    for file in &files {
+        if file.kind == FileKind::Submodule {
+            continue;
+        }
+        let data = repo.find_object(ObjectId::from_hex(file.id.as_bytes())?)?;
+        let Ok(content) = str::from_utf8(&data.data) else {
+            continue;
+        };
+        let path = Path::new("files").join(format!("{}.{extension}", file.path));
+        let to_root = to_root_path(&path);
+        sink.write(
+            &path,
+            renderer.file(&summary, file, content, &to_root).as_bytes(),
+        )?;
+    }
+    let kept = sink
+        .manifest
+        .files
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<HashSet<_>>();
+    for commit in &commits {
+        let path = Path::new("commits").join(format!("{}.{extension}", commit.id));
+        if kept.contains(&path) {
+            continue;
+        }
+        let diff = match collect_diff(repo, ObjectId::from_hex(commit.id.as_bytes())?, options) {
+            Ok(diff) => diff,
+            Err(error) => {
+                warn!(id = commit.id, %error, "Failed to diff commit");
+                String::new()
+            }
+        };
+        sink.set_source(Source::Commit(commit.id.clone()));
+        sink.write(
+            &path,
+            renderer.commit(&summary, commit, &diff, &paged).as_bytes(),
+        )?;
+    }
+    sink.set_source(Source::Ref("HEAD".to_owned()));
+    Ok(())
+}
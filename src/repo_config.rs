@@ -0,0 +1,66 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use tracing::debug;
+
+/// Name of the config file read from a repo's git dir.
+pub const REPO_CONFIG_FILE: &str = "stagix.toml";
+
+/// Per repo settings, read from `stagix.toml` in the git dir, taking precedence over the bare
+/// `description`, `owner`, `pages`, `domain`, `category`, `mailinglist` and `hidden` files.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoConfig {
+    pub description: Option<String>,
+    pub owner: Option<String>,
+    /// Full clone URLs, shown along with any made from the clone base URLs.
+    #[serde(default)]
+    pub clone_urls: Vec<String>,
+    /// Directory of the tree to publish with `stagix-pages`, the root of the tree if empty. It is
+    /// taken from `HEAD` unless prefixed with a ref and a colon, as in `refs/heads/pages:docs/`.
+    pub pages: Option<String>,
+    /// Command building the docs for `stagix-pages`, such as `mdbook build`, run with `sh` in a
+    /// copy of the pages directory.
+    pub pages_command: Option<String>,
+    /// Directory the pages command writes the site to, relative to the pages directory,
+    /// `public` if not given.
+    pub pages_output: Option<String>,
+    /// Custom domain the pages are served from, publishing them under `<out_dir>/<domain>/` with
+    /// a redirect from the repo's usual path.
+    pub domain: Option<String>,
+    /// Number of commits to limit log history to, if not given on the command line.
+    pub log_length: Option<usize>,
+    /// Number of commits to include in the atom feed.
+    pub feed_length: Option<usize>,
+    /// Section of the index page to list the repo under.
+    pub category: Option<String>,
+    /// Address patches are mailed to, or the URL of the list's archive.
+    pub mailinglist: Option<String>,
+    /// Branch to build the log and files from, rather than `HEAD`.
+    pub default_branch: Option<String>,
+    /// Leave the repo out of the index and pages, as does a bare `hidden` file.
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl RepoConfig {
+    /// Load the config from `git_dir`, the default config if there is none.
+    pub fn load(git_dir: &Path) -> anyhow::Result<Self> {
+        let path = git_dir.join(REPO_CONFIG_FILE);
+        if !path.is_file() {
+            debug!(?path, "no repo config file found");
+            return Ok(Self::default());
+        }
+        let content = read_to_string(&path).context("read repo config")?;
+        toml::from_str(&content).with_context(|| format!("parse {}", path.display()))
+    }
+}
+
+/// Whether the repo at `repo_path` is marked as hidden, by its config or a `hidden` file in its
+/// git dir, so it is left out of the index and pages.
+pub fn is_hidden(repo_path: &Path) -> anyhow::Result<bool> {
+    let git_dir = gix::open(repo_path).context("open repo")?.path().to_owned();
+    Ok(git_dir.join("hidden").is_file() || RepoConfig::load(&git_dir)?.hidden)
+}
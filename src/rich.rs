@@ -0,0 +1,639 @@
+use std::collections::HashMap;
+
+use build_html::escape_html;
+use serde::Deserialize;
+
+use crate::about::render_markdown;
+use crate::ansi::AnsiRenderer;
+
+/// Converts the content of a file to html for showing it rendered above its source.
+type Render = fn(&str) -> anyhow::Result<String>;
+
+/// The rich renderers, keyed by the lowercase extension of the files they render.
+const RENDERERS: &[(&str, Render)] = &[
+    ("ipynb", render_notebook),
+    ("rst", render_rst),
+    ("org", render_org),
+];
+
+/// The renderer for files with `extension`, if there is one.
+pub(crate) fn rich_renderer(extension: &str) -> Option<Render> {
+    RENDERERS
+        .iter()
+        .find(|(e, _)| *e == extension)
+        .map(|(_, render)| *render)
+}
+
+#[derive(Debug, Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Cell {
+    cell_type: String,
+    #[serde(default)]
+    source: Text,
+    #[serde(default)]
+    execution_count: Option<u64>,
+    #[serde(default)]
+    outputs: Vec<Output>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+enum Output {
+    Stream {
+        text: Text,
+    },
+    ExecuteResult {
+        data: HashMap<String, serde_json::Value>,
+    },
+    DisplayData {
+        data: HashMap<String, serde_json::Value>,
+    },
+    Error {
+        traceback: Vec<String>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Text in a notebook, either a single string or a list of lines.
+#[derive(Debug, Default, Deserialize)]
+#[serde(untagged)]
+enum Text {
+    #[default]
+    Empty,
+    One(String),
+    Lines(Vec<String>),
+}
+
+impl Text {
+    fn joined(&self) -> String {
+        match self {
+            Self::Empty => String::new(),
+            Self::One(text) => text.clone(),
+            Self::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+/// A Jupyter notebook, its markdown cells rendered and code cells shown with their outputs.
+///
+/// Html outputs aren't shown, being from an untrusted source, falling back to their plain text.
+fn render_notebook(text: &str) -> anyhow::Result<String> {
+    let notebook: Notebook = serde_json::from_str(text)?;
+    let mut html = String::from("<div class=\"notebook\">");
+    for cell in &notebook.cells {
+        let source = cell.source.joined();
+        match cell.cell_type.as_str() {
+            "markdown" => html.push_str(&format!(
+                "<div class=\"cell markdown\">{}</div>",
+                render_markdown(&source)
+            )),
+            "code" => {
+                let count = cell
+                    .execution_count
+                    .map_or_else(|| " ".to_owned(), |count| count.to_string());
+                html.push_str(&format!(
+                    "<div class=\"cell code\"><pre class=\"input\">In [{count}]: {}</pre>",
+                    escape_html(&source)
+                ));
+                for output in &cell.outputs {
+                    html.push_str(&output_html(output));
+                }
+                html.push_str("</div>");
+            }
+            _ => html.push_str(&format!(
+                "<div class=\"cell\"><pre>{}</pre></div>",
+                escape_html(&source)
+            )),
+        }
+    }
+    html.push_str("</div>");
+    Ok(html)
+}
+
+fn output_html(output: &Output) -> String {
+    match output {
+        Output::Stream { text } => {
+            format!("<pre class=\"output\">{}</pre>", ansi_html(&text.joined()))
+        }
+        Output::ExecuteResult { data } | Output::DisplayData { data } => {
+            let text = |mime: &str| {
+                data.get(mime)
+                    .and_then(|value| Text::deserialize(value).ok())
+                    .map(|text| text.joined())
+            };
+            if let Some(image) = text("image/png") {
+                // only base64 is let into the attribute
+                let image = image
+                    .chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '='))
+                    .collect::<String>();
+                format!("<img class=\"output\" src=\"data:image/png;base64,{image}\">")
+            } else if let Some(text) = text("text/plain") {
+                format!("<pre class=\"output\">{}</pre>", escape_html(&text))
+            } else {
+                String::new()
+            }
+        }
+        // tracebacks are coloured with ansi escapes
+        Output::Error { traceback } => format!(
+            "<pre class=\"output error\">{}</pre>",
+            ansi_html(&traceback.join("\n"))
+        ),
+        Output::Other => String::new(),
+    }
+}
+
+fn ansi_html(text: &str) -> String {
+    let mut ansi = AnsiRenderer::default();
+    text.split('\n')
+        .map(|line| ansi.line_html(line).to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The syntax of the inline markup in a line.
+#[derive(Debug, Clone, Copy)]
+enum Syntax {
+    Rst,
+    Org,
+}
+
+impl Syntax {
+    /// Delimiters of inline markup and the tag each becomes, longest first.
+    fn markers(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Rst => &[("``", "code"), ("**", "strong"), ("*", "em")],
+            Self::Org => &[
+                ("*", "strong"),
+                ("/", "em"),
+                ("=", "code"),
+                ("~", "code"),
+                ("+", "del"),
+            ],
+        }
+    }
+
+    /// A link at the start of `text`, as its href, label and length.
+    fn link(self, text: &str) -> Option<(&str, &str, usize)> {
+        match self {
+            // `label <url>`_
+            Self::Rst => {
+                let inner = text.strip_prefix('`')?;
+                let end = inner.find(">`_")?;
+                // not spanning other backquoted markup, like ``code`` before the link
+                if inner[..end].contains('`') {
+                    return None;
+                }
+                let (label, url) = inner[..end].rsplit_once('<')?;
+                Some((url, label.trim_end(), end + 4))
+            }
+            // [[url][label]] or [[url]]
+            Self::Org => {
+                let inner = text.strip_prefix("[[")?;
+                let end = inner.find("]]")?;
+                let (url, label) = inner[..end]
+                    .split_once("][")
+                    .unwrap_or((&inner[..end], &inner[..end]));
+                Some((url, label, end + 4))
+            }
+        }
+    }
+}
+
+/// Escape `text`, turning its inline markup into html.
+fn inline(text: &str, syntax: Syntax) -> String {
+    let mut html = String::new();
+    let mut previous = ' ';
+    let mut i = 0;
+    'text: while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        if let Some((url, label, len)) = syntax.link(rest) {
+            // other schemes, like javascript:, aren't linked
+            if url.starts_with("http://") || url.starts_with("https://") {
+                html.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(url),
+                    escape_html(label)
+                ));
+            } else {
+                html.push_str(&escape_html(label));
+            }
+            i += len;
+            previous = ' ';
+            continue;
+        }
+        // markup only starts at the beginning of a word, like `*this*` but not `a*b*c`
+        if !previous.is_alphanumeric() {
+            for (marker, tag) in syntax.markers() {
+                if let Some(after) = rest.strip_prefix(marker)
+                    && !after.starts_with(char::is_whitespace)
+                    && let Some(end) = after.find(marker)
+                    && end > 0
+                    && !after[..end].ends_with(char::is_whitespace)
+                    && !after[end + marker.len()..].starts_with(char::is_alphanumeric)
+                {
+                    html.push_str(&format!("<{tag}>{}</{tag}>", escape_html(&after[..end])));
+                    i += marker.len() * 2 + end;
+                    previous = ' ';
+                    continue 'text;
+                }
+            }
+        }
+        html.push_str(&escape_html(&c.to_string()));
+        i += c.len_utf8();
+        previous = c;
+    }
+    html
+}
+
+/// A list item's marker, like `- `, `1. ` or `#) `, as whether it is ordered and the rest of the
+/// line.
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    let line = line.trim_start();
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some((false, rest));
+        }
+    }
+    let number = line.find(|c: char| !c.is_ascii_digit() && c != '#')?;
+    if number == 0 {
+        return None;
+    }
+    line[number..]
+        .strip_prefix(". ")
+        .or_else(|| line[number..].strip_prefix(") "))
+        .map(|rest| (true, rest))
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// The list starting at `lines[*i]`, each item continuing onto the lines indented below it.
+fn list(lines: &[&str], i: &mut usize, syntax: Syntax) -> String {
+    let Some((ordered, _)) = list_item(lines[*i]) else {
+        return String::new();
+    };
+    let mut items = Vec::new();
+    while *i < lines.len() {
+        let Some((_, first)) = list_item(lines[*i]) else {
+            break;
+        };
+        let marker_indent = indent(lines[*i]);
+        let mut item = vec![first.trim()];
+        *i += 1;
+        while *i < lines.len()
+            && !lines[*i].trim().is_empty()
+            && indent(lines[*i]) > marker_indent
+            && list_item(lines[*i]).is_none()
+        {
+            item.push(lines[*i].trim());
+            *i += 1;
+        }
+        items.push(format!("<li>{}</li>", inline(&item.join(" "), syntax)));
+        // items may be separated by blank lines
+        let next = (*i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+        match next {
+            Some(j) if list_item(lines[j]).is_some_and(|(o, _)| o == ordered) => *i = j,
+            _ => break,
+        }
+    }
+    let tag = if ordered { "ol" } else { "ul" };
+    format!("<{tag}>{}</{tag}>", items.concat())
+}
+
+/// The lines from `lines[*i]` up to a blank line, or one that `ends` says starts another block.
+fn paragraph<'a>(lines: &[&'a str], i: &mut usize, ends: impl Fn(&str) -> bool) -> Vec<&'a str> {
+    let mut paragraph = Vec::new();
+    while *i < lines.len()
+        && !lines[*i].trim().is_empty()
+        && (paragraph.is_empty() || !ends(lines[*i]))
+    {
+        paragraph.push(lines[*i].trim());
+        *i += 1;
+    }
+    paragraph
+}
+
+/// The block of blank or indented lines from `lines[*i]`, with the common indentation removed.
+fn indented(lines: &[&str], i: &mut usize) -> String {
+    let start = *i;
+    while *i < lines.len() && (lines[*i].trim().is_empty() || indent(lines[*i]) > 0) {
+        *i += 1;
+    }
+    let block = &lines[start..*i];
+    let common = block
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent(line))
+        .min()
+        .unwrap_or(0);
+    block
+        .iter()
+        .map(|line| line.get(common..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_owned()
+}
+
+/// Whether `line` is an rst section adornment, a run of one punctuation character.
+fn adornment(line: &str) -> Option<char> {
+    let line = line.trim_end();
+    let c = line.chars().next()?;
+    (line.len() >= 2 && c.is_ascii_punctuation() && line.chars().all(|d| d == c)).then_some(c)
+}
+
+/// How deep block quotes nest before the rest is shown literally, so a file quoting thousands of
+/// times over can't overflow the stack.
+const MAX_QUOTE_DEPTH: usize = 32;
+
+/// reStructuredText, covering sections, paragraphs, lists, literal blocks and quotes with inline
+/// markup. Other directives and comments are left out.
+fn render_rst(text: &str) -> anyhow::Result<String> {
+    Ok(rst_html(text, 0))
+}
+
+/// The html of `text` quoted `depth` times over.
+fn rst_html(text: &str, depth: usize) -> String {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut html = String::new();
+    // section levels go by the order their adornments are first seen in
+    let mut adornments = Vec::new();
+    let mut heading = |c: char, title: &str| {
+        let level = match adornments.iter().position(|&a| a == c) {
+            Some(level) => level,
+            None => {
+                adornments.push(c);
+                adornments.len() - 1
+            }
+        };
+        let level = (level + 1).min(6);
+        format!("<h{level}>{}</h{level}>", inline(title.trim(), Syntax::Rst))
+    };
+    let mut literal_next = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+        if indent(line) > 0 {
+            let block = indented(&lines, &mut i);
+            if literal_next || depth >= MAX_QUOTE_DEPTH {
+                html.push_str(&format!("<pre>{}</pre>", escape_html(&block)));
+            } else {
+                html.push_str(&format!(
+                    "<blockquote>{}</blockquote>",
+                    rst_html(&block, depth + 1)
+                ));
+            }
+            literal_next = false;
+            continue;
+        }
+        literal_next = false;
+        if let Some(directive) = line.strip_prefix(".. ") {
+            i += 1;
+            if directive.starts_with("code") || directive.starts_with("sourcecode") {
+                // skip options of the directive, like :linenos:
+                while i < lines.len() && lines[i].trim_start().starts_with(':') {
+                    i += 1;
+                }
+                literal_next = true;
+            } else {
+                indented(&lines, &mut i);
+            }
+            continue;
+        }
+        // a title with an overline and underline
+        if let Some(c) = adornment(line)
+            && i + 2 < lines.len()
+            && adornment(lines[i + 2]) == Some(c)
+            && !lines[i + 1].trim().is_empty()
+        {
+            html.push_str(&heading(c, lines[i + 1]));
+            i += 3;
+            continue;
+        }
+        // a title with an underline at least as long as it
+        if i + 1 < lines.len()
+            && let Some(c) = adornment(lines[i + 1])
+            && lines[i + 1].trim_end().len() >= line.trim_end().chars().count()
+        {
+            html.push_str(&heading(c, line));
+            i += 2;
+            continue;
+        }
+        if adornment(line).is_some() {
+            html.push_str("<hr>");
+            i += 1;
+            continue;
+        }
+        if list_item(line).is_some() {
+            html.push_str(&list(&lines, &mut i, Syntax::Rst));
+            continue;
+        }
+        let mut text = paragraph(&lines, &mut i, |line| indent(line) > 0).join(" ");
+        // a paragraph ending in `::` introduces a literal block, the `::` shown as `:` if it
+        // follows text
+        if let Some(rest) = text.strip_suffix("::") {
+            literal_next = true;
+            text = match rest.strip_suffix(' ') {
+                Some(rest) => rest.trim_end().to_owned(),
+                None if rest.is_empty() => rest.to_owned(),
+                None => format!("{rest}:"),
+            };
+        }
+        if !text.is_empty() {
+            html.push_str(&format!("<p>{}</p>", inline(&text, Syntax::Rst)));
+        }
+    }
+    html
+}
+
+/// An org heading, as its level and title.
+fn org_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.find(|c| c != '*')?;
+    let title = line[level..].strip_prefix(' ')?;
+    (level > 0).then_some((level, title))
+}
+
+/// An org mode document, covering headings, paragraphs, lists, tables and blocks with inline
+/// markup. Keywords other than the title, and comments, are left out.
+fn render_org(text: &str) -> anyhow::Result<String> {
+    Ok(org_html(text, 0))
+}
+
+/// The html of `text` quoted `depth` times over.
+fn org_html(text: &str, depth: usize) -> String {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut html = String::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        let upper = trimmed.to_ascii_uppercase();
+        if trimmed.is_empty() || trimmed == "#" || trimmed.starts_with("# ") {
+            i += 1;
+            continue;
+        }
+        if let Some((level, title)) = org_heading(line) {
+            let level = level.min(6);
+            html.push_str(&format!(
+                "<h{level}>{}</h{level}>",
+                inline(title.trim(), Syntax::Org)
+            ));
+            i += 1;
+            continue;
+        }
+        if let Some(block) = upper.strip_prefix("#+BEGIN_") {
+            let name = block.split_whitespace().next().unwrap_or_default();
+            let end = format!("#+END_{name}");
+            let start = i + 1;
+            i = start;
+            while i < lines.len() && !lines[i].trim().eq_ignore_ascii_case(&end) {
+                i += 1;
+            }
+            let body = lines[start..i].join("\n");
+            i += 1;
+            if name == "QUOTE" && depth < MAX_QUOTE_DEPTH {
+                html.push_str(&format!(
+                    "<blockquote>{}</blockquote>",
+                    org_html(&body, depth + 1)
+                ));
+            } else {
+                html.push_str(&format!("<pre>{}</pre>", escape_html(&body)));
+            }
+            continue;
+        }
+        if upper.starts_with("#+TITLE:") {
+            html.push_str(&format!(
+                "<h1>{}</h1>",
+                inline(trimmed["#+TITLE:".len()..].trim(), Syntax::Org)
+            ));
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with("#+") {
+            i += 1;
+            continue;
+        }
+        // fixed width lines
+        if trimmed == ":" || trimmed.starts_with(": ") {
+            let mut fixed = Vec::new();
+            while i < lines.len()
+                && let Some(rest) = lines[i].trim().strip_prefix(':')
+                && (rest.is_empty() || rest.starts_with(' '))
+            {
+                fixed.push(rest.strip_prefix(' ').unwrap_or(rest));
+                i += 1;
+            }
+            html.push_str(&format!("<pre>{}</pre>", escape_html(&fixed.join("\n"))));
+            continue;
+        }
+        if trimmed.starts_with('|') {
+            html.push_str("<table>");
+            while i < lines.len() && lines[i].trim().starts_with('|') {
+                let row = lines[i].trim();
+                i += 1;
+                // rules between rows
+                if row.starts_with("|-") {
+                    continue;
+                }
+                let cells = row
+                    .trim_matches('|')
+                    .split('|')
+                    .map(|cell| format!("<td>{}</td>", inline(cell.trim(), Syntax::Org)))
+                    .collect::<String>();
+                html.push_str(&format!("<tr>{cells}</tr>"));
+            }
+            html.push_str("</table>");
+            continue;
+        }
+        if list_item(line).is_some() {
+            html.push_str(&list(&lines, &mut i, Syntax::Org));
+            continue;
+        }
+        let text = paragraph(&lines, &mut i, |line| {
+            org_heading(line).is_some() || line.trim_start().starts_with("#+")
+        })
+        .join(" ");
+        html.push_str(&format!("<p>{}</p>", inline(&text, Syntax::Org)));
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rst_sections_and_inline_markup() {
+        let rst = "=====\nTitle\n=====\n\nIntro with *emphasis*, ``code`` and a \
+                   `link <https://example.com>`_.\n\nPart\n----\n\n- one\n- two\n";
+        assert_eq!(
+            render_rst(rst).unwrap(),
+            "<h1>Title</h1><p>Intro with <em>emphasis</em>, <code>code</code> and a \
+             <a href=\"https://example.com\">link</a>.</p><h2>Part</h2>\
+             <ul><li>one</li><li>two</li></ul>"
+        );
+    }
+
+    #[test]
+    fn rst_literal_blocks_and_quotes() {
+        let rst = "Run this::\n\n    a < b\n\nThen\n\n    quoted *text*\n";
+        assert_eq!(
+            render_rst(rst).unwrap(),
+            "<p>Run this:</p><pre>a &lt; b</pre><p>Then</p>\
+             <blockquote><p>quoted <em>text</em></p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn rst_unsafe_links_are_text() {
+        assert_eq!(
+            render_rst("`click <javascript:alert(1)>`_").unwrap(),
+            "<p>click</p>"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_rst_quotes_stop_nesting() {
+        let rst = (1..100)
+            .map(|depth| format!("{}x", " ".repeat(depth)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let html = render_rst(&rst).unwrap();
+        assert_eq!(html.matches("<blockquote>").count(), MAX_QUOTE_DEPTH);
+        assert!(html.contains("<pre>"));
+    }
+
+    #[test]
+    fn org_headings_blocks_and_tables() {
+        let org = "#+TITLE: Notes\n* Heading\nSome /it/ and =code=.\n\
+                   #+BEGIN_SRC rust\nlet a = 1 < 2;\n#+END_SRC\n| a | b |\n|---+---|\n| 1 | 2 |\n";
+        assert_eq!(
+            render_org(org).unwrap(),
+            "<h1>Notes</h1><h1>Heading</h1><p>Some <em>it</em> and <code>code</code>.</p>\
+             <pre>let a = 1 &lt; 2;</pre><table><tr><td>a</td><td>b</td></tr>\
+             <tr><td>1</td><td>2</td></tr></table>"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_org_quotes_stop_nesting() {
+        let org = format!(
+            "{}x\n{}",
+            "#+BEGIN_QUOTE\n".repeat(100),
+            "#+END_QUOTE\n".repeat(100)
+        );
+        let html = render_org(&org).unwrap();
+        assert_eq!(html.matches("<blockquote>").count(), MAX_QUOTE_DEPTH);
+    }
+}
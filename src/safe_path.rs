@@ -0,0 +1,69 @@
+use std::path::{Component, Path};
+
+use gix::traverse::tree::recorder;
+use tracing::warn;
+
+/// Whether the path of a tree entry can be written below a directory without escaping it, being
+/// made of plain names without `..`, `.`, empty or NUL containing components.
+///
+/// Git doesn't write such trees itself, but a crafted repo can hold them.
+pub(crate) fn is_safe_tree_path(path: &[u8]) -> bool {
+    !path.is_empty()
+        && path
+            .split(|&b| b == b'/')
+            .all(|name| !matches!(name, b"" | b"." | b"..") && !name.contains(&0))
+}
+
+/// Drop the entries of a tree that aren't safe to write out, with a warning for each.
+pub(crate) fn retain_safe_paths(records: &mut Vec<recorder::Entry>) {
+    records.retain(|entry| {
+        let safe = is_safe_tree_path(&entry.filepath);
+        if !safe {
+            warn!(path = %entry.filepath, "Skipping tree entry with an unsafe path");
+        }
+        safe
+    });
+}
+
+/// Check that `path` only has plain components, so joining it onto an output directory stays
+/// within it.
+pub(crate) fn check_relative_path(path: &Path) -> anyhow::Result<()> {
+    let mut components = path.components().peekable();
+    if components.peek().is_none()
+        || !components.all(|component| matches!(component, Component::Normal(_)))
+    {
+        anyhow::bail!("refusing to write to unsafe path {:?}", path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_tree_paths() {
+        for path in ["README", "src/lib.rs", "a/.hidden", "..."] {
+            assert!(is_safe_tree_path(path.as_bytes()), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn unsafe_tree_paths() {
+        for path in [
+            "", "..", "../x", "a/../b", ".", "a/./b", "/etc", "a//b", "a/", "a\0b",
+        ] {
+            assert!(!is_safe_tree_path(path.as_bytes()), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn relative_paths() {
+        for path in ["index.html", "commits/abc.html", "files/a b/c.html"] {
+            assert!(check_relative_path(Path::new(path)).is_ok(), "{path:?}");
+        }
+        for path in ["", "/etc/passwd", "../x", "a/../../b", "./a"] {
+            assert!(check_relative_path(Path::new(path)).is_err(), "{path:?}");
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use build_html::{Container, Html as _, HtmlContainer as _, HtmlElement, escape_html};
+use gix::bstr::ByteSlice as _;
+use gix::{ObjectId, Repository};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::Topology;
+use crate::html::Escaped;
+
+/// Name of the search index written at the root of a repo's output.
+pub(crate) const SEARCH_INDEX_FILE: &str = "search.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SearchKind {
+    File,
+    Commit,
+    Ref,
+}
+
+impl SearchKind {
+    fn heading(self) -> &'static str {
+        match self {
+            SearchKind::File => "Files",
+            SearchKind::Commit => "Commits",
+            SearchKind::Ref => "Refs",
+        }
+    }
+}
+
+/// A searchable page, with the url relative to the repo's output.
+#[derive(Debug, Serialize)]
+struct SearchEntry {
+    kind: SearchKind,
+    title: String,
+    url: String,
+}
+
+/// File paths, commit titles and refs of a repo, written to [`SEARCH_INDEX_FILE`] for client-side
+/// search and listed on `search.html`.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct SearchIndex {
+    entries: Vec<SearchEntry>,
+}
+
+impl SearchIndex {
+    /// Add the file at `path` in the tree, whose page is at `files/<path>.html`.
+    pub(crate) fn add_file(&mut self, path: &Path) {
+        self.entries.push(SearchEntry {
+            kind: SearchKind::File,
+            title: path.to_string_lossy().into_owned(),
+            url: format!("files/{}.html", path.display()),
+        });
+    }
+
+    /// Add every local branch and tag, linking to the commit page of those pointing at one of
+    /// `paged_tips` and to the refs page otherwise.
+    pub(crate) fn add_refs(
+        &mut self,
+        repo: &Repository,
+        paged_tips: &[ObjectId],
+    ) -> anyhow::Result<()> {
+        let refs = repo.references()?;
+        for reference in refs.local_branches()?.chain(refs.tags()?) {
+            let mut reference = reference.map_err(|e| anyhow::anyhow!(e))?;
+            let name = reference.name().shorten().to_string();
+            let url = match reference.peel_to_commit() {
+                Ok(commit) if paged_tips.contains(&commit.id) => {
+                    format!("commits/{}.html", commit.id)
+                }
+                _ => "refs.html".to_owned(),
+            };
+            self.entries.push(SearchEntry {
+                kind: SearchKind::Ref,
+                title: name,
+                url,
+            });
+        }
+        Ok(())
+    }
+
+    /// Add every commit with a page, walking the log of each of `tips` as the commit pages are.
+    pub(crate) fn add_commits(
+        &mut self,
+        repo: &Repository,
+        tips: &[ObjectId],
+        log_length: Option<usize>,
+        topology: Topology,
+    ) -> anyhow::Result<()> {
+        debug!(repo=?repo.path(), ?tips, "add commits to search index");
+        let mut seen = HashSet::new();
+        for &tip in tips {
+            for rev in topology
+                .walk(repo, tip)?
+                .take(log_length.unwrap_or(usize::MAX))
+            {
+                let rev = rev?;
+                if !seen.insert(rev.id) {
+                    continue;
+                }
+                let commit = rev.object()?;
+                self.entries.push(SearchEntry {
+                    kind: SearchKind::Commit,
+                    title: commit.message()?.title.trim().to_str_lossy().into_owned(),
+                    url: format!("commits/{}.html", rev.id),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// The search page, listing every entry so it works as a plain index without scripts, with a
+    /// small script filtering the list as a query is typed.
+    pub(crate) fn to_container(&self) -> Container {
+        let mut container = Container::new(build_html::ContainerType::Div)
+            .with_attributes([("id", "search")])
+            .with_raw(
+                "<input type=\"search\" id=\"query\" placeholder=\"Filter\" hidden>\
+                 <script>\
+                 const query = document.getElementById('query');\
+                 query.hidden = false;\
+                 query.addEventListener('input', () => {\
+                 const q = query.value.toLowerCase();\
+                 for (const li of document.querySelectorAll('#search li')) {\
+                 li.hidden = !li.textContent.toLowerCase().includes(q);\
+                 }\
+                 });\
+                 </script>",
+            );
+        for kind in [SearchKind::Ref, SearchKind::File, SearchKind::Commit] {
+            let items = self
+                .entries
+                .iter()
+                .filter(|e| e.kind == kind)
+                .map(|e| {
+                    let link = HtmlElement::new(build_html::HtmlTag::Link)
+                        .with_attribute("href", escape_html(&e.url))
+                        .with_raw(Escaped::new(&e.title))
+                        .to_html_string();
+                    format!("<li>{link}</li>")
+                })
+                .collect::<String>();
+            if items.is_empty() {
+                continue;
+            }
+            container.add_header(2, kind.heading());
+            container.add_raw(format!("<ul>{items}</ul>"));
+        }
+        container
+    }
+}
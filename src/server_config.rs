@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+
+use crate::Output;
+use crate::manifest::{Manifest, ManifestEntry, Source};
+
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const SHORT_TTL: &str = "public, max-age=300";
+
+/// Web server configuration to generate cache headers for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ServerConfig {
+    /// `nginx.conf` snippet of `location` blocks, to be included in a `server` block.
+    Nginx,
+    /// `Caddyfile` snippet of `header` directives, to be imported into a site block.
+    Caddy,
+    /// `_headers` file as used by Netlify and Cloudflare Pages.
+    Headers,
+}
+
+impl ServerConfig {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ServerConfig::Nginx => "nginx.conf",
+            ServerConfig::Caddy => "Caddyfile",
+            ServerConfig::Headers => "_headers",
+        }
+    }
+
+    /// Where the config is written to when not given explicitly, next to `output` rather than in
+    /// it so that the web server doesn't serve it.
+    pub fn default_path(self, output: &Output) -> PathBuf {
+        let (Output::Dir(path) | Output::Archive(path)) = output;
+        let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        path.with_file_name(format!("{name}.{}", self.file_name()))
+    }
+
+    /// Render the config with long lived caching for the diffs and patches of single commits,
+    /// which never change, and short lived caching for everything else.
+    ///
+    /// Html pages are never cached for long, as the header and navigation on them change with
+    /// the rest of the site.
+    pub fn render(self, manifest: &Manifest, base_url: Option<&str>) -> String {
+        let prefix = base_url.map(url_path).unwrap_or_default();
+        let rules = cache_rules(manifest);
+
+        let mut out = String::new();
+        for (pattern, immutable) in &rules {
+            let cache_control = if *immutable { IMMUTABLE } else { SHORT_TTL };
+            match self {
+                ServerConfig::Nginx => {
+                    let location = match pattern {
+                        Pattern::Dir(dir) => format!("^~ {prefix}/{dir}/"),
+                        Pattern::DirExtension(dir, extension) => format!(
+                            "~ ^{}/.*\\.{}$",
+                            regex_escape(&format!("{prefix}/{dir}")),
+                            regex_escape(extension)
+                        ),
+                        Pattern::File(file) => format!("= {prefix}/{file}"),
+                    };
+                    out.push_str(&format!(
+                        "location {location} {{\n    add_header Cache-Control \"{cache_control}\";\n}}\n"
+                    ));
+                }
+                ServerConfig::Caddy => {
+                    let matcher = pattern.glob(&prefix);
+                    out.push_str(&format!(
+                        "header {matcher} Cache-Control \"{cache_control}\"\n"
+                    ));
+                }
+                ServerConfig::Headers => {
+                    let matcher = pattern.glob(&prefix);
+                    out.push_str(&format!("{matcher}\n  Cache-Control: {cache_control}\n"));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Extensions of the files that are the same for as long as the commit they are of exists.
+const IMMUTABLE_EXTENSIONS: [&str; 2] = ["diff", "patch"];
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Pattern {
+    Dir(String),
+    /// The files with an extension in a directory, for directories with immutable files, so
+    /// that none of the patterns overlap.
+    DirExtension(String, String),
+    File(String),
+}
+
+impl Pattern {
+    /// The pattern as a path with `*` wildcards, as used by Caddy and `_headers`.
+    fn glob(&self, prefix: &str) -> String {
+        match self {
+            Pattern::Dir(dir) => format!("{prefix}/{dir}/*"),
+            Pattern::DirExtension(dir, extension) => format!("{prefix}/{dir}/*.{extension}"),
+            Pattern::File(file) => format!("{prefix}/{file}"),
+        }
+    }
+}
+
+/// Group the manifest by top level entry, a group being immutable only if every file in it is
+/// the diff or patch of a single commit.
+///
+/// Directories holding any such files are split up by extension, so their pages still get the
+/// short lived caching.
+fn cache_rules(manifest: &Manifest) -> BTreeMap<Pattern, bool> {
+    let split = |entry: &ManifestEntry| {
+        let mut components = entry.path.components().filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        });
+        let first = components.next()?;
+        Some((first, components.next().is_some()))
+    };
+    let immutable = |entry: &ManifestEntry| {
+        matches!(entry.source, Source::Commit(_))
+            && extension(&entry.path).is_some_and(|e| IMMUTABLE_EXTENSIONS.contains(&e))
+    };
+    let split_dirs = manifest
+        .files
+        .iter()
+        .filter(|entry| immutable(entry))
+        .filter_map(split)
+        .filter(|(_, in_dir)| *in_dir)
+        .map(|(dir, _)| dir)
+        .collect::<BTreeSet<_>>();
+
+    let mut rules = BTreeMap::new();
+    for entry in &manifest.files {
+        let Some((first, in_dir)) = split(entry) else {
+            continue;
+        };
+        let pattern = if !in_dir {
+            Pattern::File(first)
+        } else if split_dirs.contains(&first) {
+            match extension(&entry.path) {
+                Some(extension) => Pattern::DirExtension(first, extension.to_owned()),
+                // no glob can pick out a file without an extension from the rest
+                None => Pattern::File(entry.path.to_string_lossy().into_owned()),
+            }
+        } else {
+            Pattern::Dir(first)
+        };
+        let rule = rules.entry(pattern).or_insert(true);
+        *rule &= immutable(entry);
+    }
+    rules
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(|extension| extension.to_str())
+}
+
+fn regex_escape(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| {
+            let escape = !c.is_ascii_alphanumeric() && !matches!(c, '/' | '-' | '_');
+            escape.then_some('\\').into_iter().chain([c])
+        })
+        .collect()
+}
+
+/// Path component of `url`, without a trailing slash.
+fn url_path(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = without_scheme
+        .find('/')
+        .map_or("", |i| &without_scheme[i..]);
+    path.trim_end_matches('/').to_owned()
+}
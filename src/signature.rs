@@ -0,0 +1,454 @@
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use base64::Engine as _;
+use base64::prelude::BASE64_STANDARD;
+use gix::bstr::ByteSlice as _;
+use gix::{ObjectId, Repository};
+use tracing::{debug, warn};
+
+use crate::html::Escaped;
+
+/// The kind of a commit or tag signature, from its armor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignatureKind {
+    Pgp,
+    Ssh,
+    X509,
+}
+
+/// What could be told about a commit or tag signature.
+#[derive(Debug)]
+pub(crate) struct SignatureInfo {
+    kind: SignatureKind,
+    /// Fingerprint or key id of a PGP key, or the public key of an SSH signature.
+    key_id: Option<String>,
+    /// Whether the signature was verified against the allowed signers, if it was checked.
+    verified: Option<bool>,
+}
+
+impl SignatureInfo {
+    /// Inspect the armored `signature` over `payload`, verifying SSH signatures against
+    /// `allowed_signers` if given.
+    pub(crate) fn new(signature: &[u8], payload: &[u8], allowed_signers: Option<&Path>) -> Self {
+        let signature_str = String::from_utf8_lossy(signature);
+        let kind = if signature_str.contains("BEGIN SSH SIGNATURE") {
+            SignatureKind::Ssh
+        } else if signature_str.contains("BEGIN SIGNED MESSAGE") {
+            SignatureKind::X509
+        } else {
+            SignatureKind::Pgp
+        };
+        let data = dearmor(&signature_str);
+        let key_id = data.as_deref().and_then(|data| match kind {
+            SignatureKind::Pgp => pgp_issuer(data),
+            SignatureKind::Ssh => ssh_public_key(data),
+            SignatureKind::X509 => None,
+        });
+        let verified = match (kind, allowed_signers) {
+            (SignatureKind::Ssh, Some(allowed_signers)) => {
+                match verify_ssh(signature, payload, allowed_signers) {
+                    Ok(verified) => Some(verified),
+                    Err(error) => {
+                        warn!(%error, "Failed to verify ssh signature");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        Self {
+            kind,
+            key_id,
+            verified,
+        }
+    }
+
+    /// A short badge for the signature, with the key in its title.
+    pub(crate) fn to_html(&self) -> Escaped {
+        let kind = match self.kind {
+            SignatureKind::Pgp => "GPG",
+            SignatureKind::Ssh => "SSH",
+            SignatureKind::X509 => "X.509",
+        };
+        let (class, state) = match self.verified {
+            Some(true) => ("signed verified", "verified"),
+            Some(false) => ("signed unverified", "unverified"),
+            None => ("signed", "signed"),
+        };
+        let title = match &self.key_id {
+            Some(key_id) => format!("{kind} key {key_id}"),
+            None => format!("{kind} key"),
+        };
+        Escaped::markup(format!(
+            "<span class=\"{class}\" title=\"{}\">{state}</span>",
+            Escaped::new(title)
+        ))
+    }
+}
+
+/// The signature of `commit`, if it is signed.
+pub(crate) fn commit_signature(
+    commit: &gix::Commit<'_>,
+    allowed_signers: Option<&Path>,
+) -> anyhow::Result<Option<SignatureInfo>> {
+    let Some((signature, signed_data)) = commit.signature()? else {
+        return Ok(None);
+    };
+    Ok(Some(SignatureInfo::new(
+        &signature,
+        &signed_data.to_bstring(),
+        allowed_signers,
+    )))
+}
+
+/// The signature of the tag object `id`, if it is an annotated tag that is signed.
+pub(crate) fn tag_signature(
+    repo: &Repository,
+    id: ObjectId,
+    allowed_signers: Option<&Path>,
+) -> anyhow::Result<Option<SignatureInfo>> {
+    let object = repo.find_object(id)?;
+    if object.kind != gix::object::Kind::Tag {
+        return Ok(None);
+    }
+    let tag = object.into_tag();
+    // gix only splits pgp signatures off the message, so like git take the last line starting
+    // any kind of signature
+    let Some(start) = [
+        "-----BEGIN PGP SIGNATURE-----",
+        "-----BEGIN SSH SIGNATURE-----",
+        "-----BEGIN SIGNED MESSAGE-----",
+    ]
+    .iter()
+    .filter_map(|armor| Some(tag.data.rfind(format!("\n{armor}"))? + 1))
+    .max() else {
+        return Ok(None);
+    };
+    // the signature covers everything before it
+    Ok(Some(SignatureInfo::new(
+        &tag.data[start..],
+        &tag.data[..start],
+        allowed_signers,
+    )))
+}
+
+/// The base64 decoded body of an armored signature, skipping any headers and checksum.
+fn dearmor(armored: &str) -> Option<Vec<u8>> {
+    let mut lines = armored.lines().map(str::trim);
+    lines.find(|line| line.starts_with("-----BEGIN"))?;
+    let mut body = lines
+        .take_while(|line| !line.starts_with("-----END"))
+        .collect::<Vec<_>>();
+    // pgp armor headers end at the first empty line
+    if let Some(blank) = body.iter().position(|line| line.is_empty()) {
+        body.drain(..=blank);
+    }
+    let encoded = body
+        .into_iter()
+        .filter(|line| !line.starts_with('='))
+        .collect::<String>();
+    BASE64_STANDARD.decode(encoded).ok()
+}
+
+/// Read an SSH wire format string, returning it and the rest of `data`.
+fn ssh_string(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = data.split_at_checked(4)?;
+    let len = u32::from_be_bytes(len.try_into().ok()?) as usize;
+    rest.split_at_checked(len)
+}
+
+/// The public key of an `SSHSIG` blob, in the format of an `authorized_keys` entry.
+fn ssh_public_key(data: &[u8]) -> Option<String> {
+    let rest = data.strip_prefix(b"SSHSIG")?;
+    let (_version, rest) = rest.split_at_checked(4)?;
+    let (key, _) = ssh_string(rest)?;
+    let (key_type, _) = ssh_string(key)?;
+    Some(format!(
+        "{} {}",
+        String::from_utf8_lossy(key_type),
+        BASE64_STANDARD.encode(key)
+    ))
+}
+
+/// The issuer fingerprint, or else key id, of the first packet of a PGP signature.
+fn pgp_issuer(data: &[u8]) -> Option<String> {
+    let (&header, rest) = data.split_first()?;
+    let body = if header & 0x40 != 0 {
+        // new format packet length
+        let (&first, rest) = rest.split_first()?;
+        match first {
+            0..192 => rest.get(..usize::from(first))?,
+            192..224 => {
+                let (&second, rest) = rest.split_first()?;
+                let len = ((usize::from(first) - 192) << 8) + usize::from(second) + 192;
+                rest.get(..len)?
+            }
+            255 => {
+                let (len, rest) = rest.split_at_checked(4)?;
+                rest.get(..u32::from_be_bytes(len.try_into().ok()?) as usize)?
+            }
+            _ => return None,
+        }
+    } else {
+        // old format packet length
+        let len_bytes = match header & 0x03 {
+            0 => 1,
+            1 => 2,
+            2 => 4,
+            _ => return None,
+        };
+        let (len, rest) = rest.split_at_checked(len_bytes)?;
+        let len = len.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b));
+        rest.get(..len)?
+    };
+
+    match body.first()? {
+        3 => Some(hex(body.get(7..15)?)),
+        4 | 5 => {
+            let (hashed_len, rest) = body.get(4..)?.split_at_checked(2)?;
+            let hashed_len = usize::from(u16::from_be_bytes(hashed_len.try_into().ok()?));
+            let (hashed, rest) = rest.split_at_checked(hashed_len)?;
+            let (unhashed_len, rest) = rest.split_at_checked(2)?;
+            let unhashed_len = usize::from(u16::from_be_bytes(unhashed_len.try_into().ok()?));
+            let unhashed = rest.get(..unhashed_len)?;
+            let subpackets = pgp_subpackets(hashed)
+                .into_iter()
+                .chain(pgp_subpackets(unhashed))
+                .collect::<Vec<_>>();
+            // prefer the full fingerprint over the short key id
+            subpackets
+                .iter()
+                .find(|(kind, _)| *kind == 33)
+                .and_then(|(_, data)| data.get(1..))
+                .or_else(|| {
+                    subpackets
+                        .iter()
+                        .find(|(kind, _)| *kind == 16)
+                        .map(|(_, data)| *data)
+                })
+                .map(hex)
+        }
+        _ => None,
+    }
+}
+
+/// The type and body of each subpacket in a PGP signature subpacket area.
+fn pgp_subpackets(mut data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut subpackets = Vec::new();
+    while let Some((&first, rest)) = data.split_first() {
+        let (len, rest) = match first {
+            0..192 => (usize::from(first), rest),
+            192..255 => {
+                let Some((&second, rest)) = rest.split_first() else {
+                    break;
+                };
+                (
+                    ((usize::from(first) - 192) << 8) + usize::from(second) + 192,
+                    rest,
+                )
+            }
+            255 => {
+                let Some((len, rest)) = rest.split_at_checked(4) else {
+                    break;
+                };
+                (
+                    u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize,
+                    rest,
+                )
+            }
+        };
+        let Some((subpacket, rest)) = rest.split_at_checked(len) else {
+            break;
+        };
+        if let Some((&kind, body)) = subpacket.split_first() {
+            subpackets.push((kind & 0x7f, body));
+        }
+        data = rest;
+    }
+    subpackets
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+/// Verify an SSH signature with `ssh-keygen` as `git verify-commit` would, by any of the
+/// principals that `allowed_signers` gives its key.
+fn verify_ssh(signature: &[u8], payload: &[u8], allowed_signers: &Path) -> anyhow::Result<bool> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let signature_path = std::env::temp_dir().join(format!(
+        "stagix-signature-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    // only ever a new file that others can't read or swap out
+    let mut file_options = OpenOptions::new();
+    file_options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut file_options, 0o600);
+    file_options.open(&signature_path)?.write_all(signature)?;
+    let result = (|| {
+        let principals = Command::new("ssh-keygen")
+            .args(["-Y", "find-principals", "-f"])
+            .arg(allowed_signers)
+            .arg("-s")
+            .arg(&signature_path)
+            .stderr(Stdio::null())
+            .output()?;
+        // a key missing from the allowed signers has no principals
+        if !principals.status.success() {
+            return anyhow::Ok(false);
+        }
+        for principal in principals.stdout.lines().filter(|line| !line.is_empty()) {
+            let mut child = Command::new("ssh-keygen")
+                .args(["-Y", "verify", "-n", "git", "-f"])
+                .arg(allowed_signers)
+                .arg("-I")
+                .arg(principal.to_os_str()?)
+                .arg("-s")
+                .arg(&signature_path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            let written = child
+                .stdin
+                .take()
+                .map_or(Ok(()), |mut stdin| stdin.write_all(payload));
+            // waited on even if writing failed, so it isn't left a zombie
+            let status = child.wait()?;
+            written?;
+            if status.success() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    })();
+    if let Err(error) = std::fs::remove_file(&signature_path) {
+        warn!(?signature_path, %error, "Failed to remove signature file");
+    }
+    debug!(?result, "verified ssh signature");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A detached signature by an ed25519 key from `gpg --armor --detach-sign`, in an old format
+    /// packet with the issuer fingerprint in the hashed subpackets.
+    const PGP_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----
+
+iHUEABYIAB0WIQTDhnIAORsrfdFDaxMCcDcB4hSwvAUCatIq8wAKCRACcDcB4hSw
+vDYXAPkBrmn+mFKT2++Eu0GhWyJnQ8XPhhd7oxR0bUMfa6+/xAD/SbpOfEmsQaB3
+mu2KZvu6FVZTle5zuMARbV5v9M/angI=
+=u4UA
+-----END PGP SIGNATURE-----
+";
+    const PGP_FINGERPRINT: &str = "C3867200391B2B7DD1436B1302703701E214B0BC";
+
+    /// A signature from `ssh-keygen -Y sign -n git` by the key of [`SSH_PUBLIC_KEY`].
+    const SSH_SIGNATURE: &str = "-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAg1fTkPeo4rP2Xj9ztvoETxeRVhP
+Lln3ioSr4QTjMLO0kAAAADZ2l0AAAAAAAAAAZzaGE1MTIAAABTAAAAC3NzaC1lZDI1NTE5
+AAAAQJKx27Bo8FR5pWU8MPnS5iI/pyyq9eum1CdW9gxXyspEAeiw/Z9SBriYU+6bqdsD6F
+Nl+uCxO4xUm7HVeVMRxgY=
+-----END SSH SIGNATURE-----
+";
+    const SSH_PUBLIC_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAINX05D3qOKz9l4/c7b6BE8XkVYTy5Z94qEq+EE4zCztJ";
+
+    #[test]
+    fn pgp_fingerprint_of_old_format_packet() {
+        let data = dearmor(PGP_SIGNATURE).unwrap();
+        assert_eq!(data[0], 0x88, "old format signature packet");
+        assert_eq!(pgp_issuer(&data).as_deref(), Some(PGP_FINGERPRINT));
+
+        let info = SignatureInfo::new(PGP_SIGNATURE.as_bytes(), b"payload", None);
+        assert_eq!(info.kind, SignatureKind::Pgp);
+        assert_eq!(info.key_id.as_deref(), Some(PGP_FINGERPRINT));
+        assert_eq!(info.verified, None);
+    }
+
+    #[test]
+    fn pgp_fingerprint_of_new_format_packet() {
+        let data = dearmor(PGP_SIGNATURE).unwrap();
+        let body = &data[2..];
+        let mut packet = vec![0xc2, body.len() as u8];
+        packet.extend_from_slice(body);
+        assert_eq!(pgp_issuer(&packet).as_deref(), Some(PGP_FINGERPRINT));
+        // a five byte length
+        let mut packet = vec![0xc2, 255];
+        packet.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        packet.extend_from_slice(body);
+        assert_eq!(pgp_issuer(&packet).as_deref(), Some(PGP_FINGERPRINT));
+    }
+
+    #[test]
+    fn pgp_key_id_without_fingerprint() {
+        // a v4 signature with only an issuer key id, in the unhashed subpackets
+        let mut body = vec![4, 0, 22, 8, 0, 0, 0, 10, 9, 16];
+        body.extend_from_slice(&[0x02, 0x70, 0x37, 0x01, 0xe2, 0x14, 0xb0, 0xbc]);
+        let mut packet = vec![0x88, body.len() as u8];
+        packet.extend_from_slice(&body);
+        assert_eq!(pgp_issuer(&packet).as_deref(), Some("02703701E214B0BC"));
+    }
+
+    #[test]
+    fn ssh_public_key_of_sshsig() {
+        let data = dearmor(SSH_SIGNATURE).unwrap();
+        assert_eq!(ssh_public_key(&data).as_deref(), Some(SSH_PUBLIC_KEY));
+
+        let info = SignatureInfo::new(SSH_SIGNATURE.as_bytes(), b"payload", None);
+        assert_eq!(info.kind, SignatureKind::Ssh);
+        assert_eq!(info.key_id.as_deref(), Some(SSH_PUBLIC_KEY));
+        assert!(info.to_html().as_str().contains(SSH_PUBLIC_KEY));
+    }
+
+    #[test]
+    fn truncated_signatures_have_no_key() {
+        let data = dearmor(PGP_SIGNATURE).unwrap();
+        for len in 0..data.len() {
+            assert_eq!(pgp_issuer(&data[..len]), None, "{len} bytes");
+        }
+        let data = dearmor(SSH_SIGNATURE).unwrap();
+        // the version and the public key string that follow the magic
+        let key_end = 6 + 4 + 4 + 0x33;
+        for len in 0..key_end {
+            assert_eq!(ssh_public_key(&data[..len]), None, "{len} bytes");
+        }
+        for len in key_end..data.len() {
+            assert_eq!(
+                ssh_public_key(&data[..len]).as_deref(),
+                Some(SSH_PUBLIC_KEY)
+            );
+        }
+    }
+
+    #[test]
+    fn garbage_has_no_key() {
+        assert_eq!(dearmor("not a signature"), None);
+        assert_eq!(
+            dearmor("-----BEGIN PGP SIGNATURE-----\n\n!!!\n-----END PGP SIGNATURE-----"),
+            None
+        );
+        assert_eq!(pgp_issuer(&[0xff; 64]), None);
+        assert_eq!(pgp_issuer(&[0x88, 3, 4, 0, 22]), None);
+        // subpacket lengths running past the end of the area
+        assert_eq!(pgp_subpackets(&[255, 0xff, 0xff, 0xff, 0xff, 16]), []);
+        assert_eq!(pgp_subpackets(&[200]), []);
+        assert_eq!(ssh_public_key(b"SSHSIG\0\0\0\x01\xff\xff\xff\xff"), None);
+        assert_eq!(ssh_public_key(b"PGPSIG\0\0\0\x01\0\0\0\0"), None);
+
+        let info = SignatureInfo::new(b"-----BEGIN PGP SIGNATURE-----\ngarbage", b"", None);
+        assert_eq!(info.key_id, None);
+        assert_eq!(
+            info.to_html().as_str(),
+            "<span class=\"signed\" title=\"GPG key\">signed</span>"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::{create_dir_all, read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use crate::{
+    CommitRefs, IndexColumn, IndexOptions, IndexSort, Output, PageOptions, RepoOptions, Theme,
+    build_index_page, build_repo_pages, without_hidden,
+};
+
+/// A file describing several independent sites to build in one go.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SitesFile {
+    #[serde(rename = "site")]
+    pub sites: Vec<SiteConfig>,
+}
+
+/// A single site: an index page plus the pages of every repo in it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteConfig {
+    pub repos: Vec<PathBuf>,
+    pub out_dir: PathBuf,
+    pub base_url: Option<String>,
+    pub theme: Option<Theme>,
+    pub stylesheet: Option<PathBuf>,
+    pub logo: Option<PathBuf>,
+    pub favicon: Option<PathBuf>,
+    #[serde(default)]
+    pub clone_base_urls: Vec<String>,
+    pub log_length: Option<usize>,
+    pub pages_url: Option<String>,
+    /// Path to an html fragment inserted at the bottom of every page of the site.
+    pub footer_file: Option<PathBuf>,
+    /// Columns of the index table, in order.
+    #[serde(default)]
+    pub index_columns: Vec<IndexColumn>,
+    /// Order of the repos on the index page.
+    pub index_sort: Option<IndexSort>,
+}
+
+impl SitesFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = read_to_string(path).context("read sites file")?;
+        toml::from_str(&content).context("parse sites file")
+    }
+}
+
+/// The git repos directly under `dir`, bare or not, by name and leaving out hidden repos.
+pub(crate) fn discover_repos(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut repos = Vec::new();
+    for entry in read_dir(dir).with_context(|| format!("read repos dir {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() && gix::open(&path).is_ok() {
+            repos.push(path);
+        } else {
+            debug!(?path, "not a git repo, skipping");
+        }
+    }
+    repos.sort();
+    Ok(without_hidden(repos))
+}
+
+/// Build every site in the sites file at `path`, carrying on past sites that fail.
+pub fn build_sites(path: &Path) -> anyhow::Result<()> {
+    let sites = SitesFile::load(path)?;
+    info!(num_sites = sites.sites.len(), "building sites");
+    let mut failed = 0;
+    for site in &sites.sites {
+        if let Err(error) = build_site(site) {
+            warn!(out_dir=?site.out_dir, %error, "Failed to build site");
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} sites failed to build", sites.sites.len());
+    }
+    Ok(())
+}
+
+pub fn build_site(site: &SiteConfig) -> anyhow::Result<()> {
+    info!(out_dir=?site.out_dir, num_repos = site.repos.len(), "building site");
+    create_dir_all(&site.out_dir)?;
+    let out_dir = site.out_dir.canonicalize()?;
+    let footer_include = site
+        .footer_file
+        .as_ref()
+        .map(read_to_string)
+        .transpose()
+        .context("read footer file")?;
+
+    // hidden repos get no pages, not only no place on the index
+    let repos = without_hidden(site.repos.clone());
+    for repo_path in &repos {
+        let Some(repo_name) = repo_path.file_stem() else {
+            warn!(?repo_path, "no repo name found");
+            continue;
+        };
+        let repo_out_dir = out_dir.join(repo_name);
+        create_dir_all(&repo_out_dir)?;
+        let options = RepoOptions {
+            output: Output::Dir(repo_out_dir),
+            // staged beside the repo's pages, inside the site's out dir
+            atomic: true,
+            log_length: site.log_length,
+            log_archive: None,
+            log_page_size: None,
+            blame: false,
+            snapshots: false,
+            snapshot_head: false,
+            dumb_http: false,
+            branches: Vec::new(),
+            tag_trees: false,
+            compare_head: false,
+            commit_refs: CommitRefs::Head,
+            formats: Vec::new(),
+            default_branch: None,
+            clone_base_urls: site.clone_base_urls.clone(),
+            page: PageOptions {
+                base_url: site.base_url.as_ref().map(|u| {
+                    format!(
+                        "{}/{}",
+                        u.trim_end_matches('/'),
+                        repo_name.to_string_lossy()
+                    )
+                }),
+                footer_include: footer_include.clone(),
+                ..Default::default()
+            },
+            server_config: None,
+            server_config_path: None,
+        };
+        if let Err(error) = build_repo_pages(repo_path, &options) {
+            warn!(?repo_path, %error, "Failed to build repo pages");
+        }
+    }
+
+    build_index_page(
+        repos,
+        IndexOptions {
+            out_dir: Some(out_dir),
+            stylesheet: site.stylesheet.clone(),
+            logo: site.logo.clone(),
+            favicon: site.favicon.clone(),
+            repos_url: None,
+            pages_url: site.pages_url.clone(),
+            theme: site.theme,
+            page: PageOptions {
+                base_url: site.base_url.clone(),
+                footer_include,
+                ..Default::default()
+            },
+            jobs: 1,
+            columns: site.index_columns.clone(),
+            sort: site.index_sort.unwrap_or_default(),
+            reverse: false,
+            formats: Vec::new(),
+        },
+    )
+}
@@ -0,0 +1,88 @@
+use std::fs::read_dir;
+use std::path::Path;
+
+const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+/// A size in bytes for reading, such as `512 B` or `1.5 MiB`.
+pub(crate) fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Parse a size such as `512`, `100K` or `5MiB` into bytes, the units being powers of 1024.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number = number
+        .parse::<u64>()
+        .map_err(|e| format!("invalid size {s:?}: {e}"))?;
+    let shift = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 10,
+        "m" | "mb" | "mib" => 20,
+        "g" | "gb" | "gib" => 30,
+        _ => return Err(format!("unknown unit in size {s:?}")),
+    };
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("size {s:?} is too large"))
+}
+
+/// Total size of the files below `dir`, not following symlinks.
+pub(crate) fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        for entry in read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if metadata.is_file() {
+                size += metadata.len();
+            }
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sizes() {
+        for (s, bytes) in [
+            ("512", 512),
+            ("512B", 512),
+            ("100K", 100 * 1024),
+            ("5MiB", 5 * 1024 * 1024),
+            (" 1 gb ", 1024 * 1024 * 1024),
+        ] {
+            assert_eq!(parse_size(s), Ok(bytes), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_sizes() {
+        for s in ["", "MiB", "5x", "-1", "1.5M", "99999999999G"] {
+            assert!(parse_size(s).is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn format_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use anyhow::Context as _;
+use minijinja::{Environment, ErrorKind, Value};
+use tracing::debug;
+
+/// Names of the templates that can replace parts of the built-in page layout.
+const TEMPLATE_NAMES: [&str; 3] = ["header.html", "nav.html", "footer.html"];
+
+/// User supplied [minijinja](https://docs.rs/minijinja) templates for the header, nav and footer
+/// of every page, any not given keeping the built-in layout.
+///
+/// Each is rendered with the page `title`, the repo's `name`, `description`, `owner` and clone
+/// `urls`, the `logo` source, the `index` page href and the `nav` links as a list of `href` and
+/// `label` pairs. The header also gets the rendered nav as `nav_html`.
+#[derive(Debug, Clone)]
+pub struct Templates {
+    env: Environment<'static>,
+}
+
+impl Templates {
+    /// Load any of `header.html`, `nav.html` and `footer.html` from `dir`.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let mut env = Environment::new();
+        for name in TEMPLATE_NAMES {
+            let path = dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            debug!(?path, "loading template");
+            let source = read_to_string(&path).with_context(|| format!("read {name}"))?;
+            env.add_template_owned(name, source)
+                .with_context(|| format!("parse {name}"))?;
+        }
+        Ok(Self { env })
+    }
+
+    /// Sources of the templates that were given.
+    pub(crate) fn sources(&self) -> Vec<String> {
+        TEMPLATE_NAMES
+            .iter()
+            .filter_map(|name| self.env.get_template(name).ok())
+            .map(|template| template.source().to_owned())
+            .collect()
+    }
+
+    /// Render the template `name` with `context`, if it was given.
+    pub(crate) fn render(&self, name: &str, context: &Value) -> anyhow::Result<Option<String>> {
+        match self.env.get_template(name) {
+            Ok(template) => Ok(Some(
+                template
+                    .render(context)
+                    .with_context(|| format!("render {name}"))?,
+            )),
+            Err(error) if error.kind() == ErrorKind::TemplateNotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use base64::Engine as _;
+use base64::prelude::BASE64_STANDARD;
+use serde::Deserialize;
+
+use crate::InlineAssets;
+
+const LOGO: &[u8] = include_bytes!("../logo.png");
+const FAVICON: &[u8] = include_bytes!("../favicon.png");
+
+/// Built-in themes, each a complete set of assets for the site.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// The stylesheet shipped with stagix, light or dark following the browser's
+    /// `prefers-color-scheme`, used when no theme is given.
+    #[default]
+    Auto,
+    /// The shipped stylesheet, always light.
+    Light,
+    /// The shipped stylesheet, always dark.
+    Dark,
+    /// Monospace and minimal, matching stagit.
+    StagitClassic,
+    /// Sans-serif with more whitespace and rounded code blocks.
+    Modern,
+    /// Small text and tight spacing to fit as much as possible on screen.
+    Compact,
+}
+
+impl Theme {
+    pub fn stylesheet(self) -> &'static str {
+        match self {
+            Theme::Auto => concat!(
+                include_str!("../style.css"),
+                "\n@media (prefers-color-scheme: dark) {\n",
+                include_str!("../themes/dark.css"),
+                "}\n",
+            ),
+            Theme::Light => include_str!("../style.css"),
+            Theme::Dark => concat!(
+                include_str!("../style.css"),
+                "\n",
+                include_str!("../themes/dark.css"),
+            ),
+            Theme::StagitClassic => include_str!("../themes/stagit-classic.css"),
+            Theme::Modern => include_str!("../themes/modern.css"),
+            Theme::Compact => include_str!("../themes/compact.css"),
+        }
+    }
+
+    pub fn logo(self) -> &'static [u8] {
+        LOGO
+    }
+
+    pub fn favicon(self) -> &'static [u8] {
+        FAVICON
+    }
+
+    /// Every asset of the theme, with the file name it is linked to by pages.
+    pub fn assets(self) -> [(&'static str, &'static [u8]); 3] {
+        [
+            ("style.css", self.stylesheet().as_bytes()),
+            ("logo.png", self.logo()),
+            ("favicon.png", self.favicon()),
+        ]
+    }
+}
+
+impl InlineAssets {
+    /// Fill in any assets not already set from `theme`.
+    pub fn or_theme(mut self, theme: Theme) -> Self {
+        let png_uri =
+            |data: &[u8]| format!("data:image/png;base64,{}", BASE64_STANDARD.encode(data));
+        self.stylesheet
+            .get_or_insert_with(|| theme.stylesheet().to_owned());
+        self.logo.get_or_insert_with(|| png_uri(theme.logo()));
+        self.favicon.get_or_insert_with(|| png_uri(theme.favicon()));
+        self
+    }
+}
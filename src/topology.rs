@@ -0,0 +1,114 @@
+use std::cmp::Reverse;
+
+use gix::revision::walk::Info;
+use gix::{ObjectId, Repository};
+use serde::Deserialize;
+
+/// Which commits of the history get log entries and pages of their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Topology {
+    /// Only the first-parent history, leaving out commits merged in from other branches.
+    #[default]
+    FirstParent,
+    /// Every reachable commit, newest first by commit time, with the log showing the commit graph.
+    All,
+}
+
+impl Topology {
+    /// Walk the history from `tip`, the same walk deciding which commits have pages everywhere.
+    pub(crate) fn walk(
+        self,
+        repo: &Repository,
+        tip: ObjectId,
+    ) -> anyhow::Result<gix::revision::Walk<'_>> {
+        self.walk_hiding(repo, tip, None)
+    }
+
+    /// Walk the history from `tip` as [`Self::walk`] does, leaving out everything reachable from
+    /// `hidden`, like `git log hidden..tip`.
+    pub(crate) fn walk_hiding(
+        self,
+        repo: &Repository,
+        tip: ObjectId,
+        hidden: Option<ObjectId>,
+    ) -> anyhow::Result<gix::revision::Walk<'_>> {
+        let walk = repo.rev_walk([tip]).with_hidden(hidden);
+        let walk = match self {
+            Topology::FirstParent => walk.first_parent_only(),
+            Topology::All => walk.sorting(gix::revision::walk::Sorting::ByCommitTime(
+                Default::default(),
+            )),
+        };
+        Ok(walk.all()?)
+    }
+}
+
+/// Sort walked commits newest first by committer date, which isn't the order of the history when
+/// a rebase kept the original dates or a committer's clock was off.
+pub(crate) fn sort_by_committer_date(revs: &mut Vec<Info<'_>>) -> anyhow::Result<()> {
+    let mut timed = revs
+        .drain(..)
+        .map(|rev| Ok((rev.object()?.committer()?.time()?.seconds, rev)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    timed.sort_by_key(|(time, _)| Reverse(*time));
+    revs.extend(timed.into_iter().map(|(_, rev)| rev));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gix::actor::Signature;
+    use gix::date::Time;
+
+    use super::*;
+
+    fn commit(
+        repo: &Repository,
+        parent: Option<ObjectId>,
+        author: i64,
+        committer: i64,
+    ) -> ObjectId {
+        let signature = |seconds| Signature {
+            name: "A U Thor".into(),
+            email: "author@example.com".into(),
+            time: Time::new(seconds, 0),
+        };
+        let tree = repo
+            .write_object(gix::objs::Tree::empty())
+            .unwrap()
+            .detach();
+        repo.write_object(gix::objs::Commit {
+            tree,
+            parents: parent.into_iter().collect(),
+            author: signature(author),
+            committer: signature(committer),
+            encoding: None,
+            message: "commit".into(),
+            extra_headers: Vec::new(),
+        })
+        .unwrap()
+        .detach()
+    }
+
+    #[test]
+    fn rebased_history_in_committer_date_order() {
+        let dir = std::env::temp_dir().join(format!("stagix-topology-{}", std::process::id()));
+        let repo = gix::init_bare(&dir).unwrap();
+        let base = commit(&repo, None, 100, 100);
+        let upstream = commit(&repo, Some(base), 300, 300);
+        // rebased onto upstream with `--committer-date-is-author-date`
+        let rebased = commit(&repo, Some(upstream), 200, 200);
+
+        let mut revs = Topology::FirstParent
+            .walk(&repo, rebased)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let ids = |revs: &[Info<'_>]| revs.iter().map(|rev| rev.id).collect::<Vec<_>>();
+        assert_eq!(ids(&revs), [rebased, upstream, base]);
+        sort_by_committer_date(&mut revs).unwrap();
+        assert_eq!(ids(&revs), [upstream, rebased, base]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use build_html::Table;
+use gix::actor::SignatureRef;
+use gix::bstr::ByteSlice as _;
+use gix::objs::commit::message::body::Trailers;
+
+use crate::EmailDisplay;
+use crate::html::{Escaped, row};
+
+/// Split a trailer value such as `Jane Doe <jane@example.com>` into its name and email.
+fn parse_ident(value: &str) -> Option<(&str, &str)> {
+    let (name, email) = value.strip_suffix('>')?.rsplit_once('<')?;
+    Some((name.trim(), email.trim()))
+}
+
+/// The trailers of a commit message, like `Signed-off-by` and `Reviewed-by`, as a table with
+/// the emails of any people shown as `emails` says, `None` if there are none.
+pub(crate) fn trailers_table(trailers: Trailers<'_>, emails: EmailDisplay) -> Option<Table> {
+    let mut table = Table::new()
+        .with_attributes([("id", "trailers")])
+        .with_header_row(["Trailer", "Value"]);
+    let mut any = false;
+    for trailer in trailers {
+        any = true;
+        let value = trailer.value.to_str_lossy();
+        let value = match parse_ident(&value) {
+            Some((name, email)) => emails.html_ident(name, email),
+            None => Escaped::new(&value),
+        };
+        let token = Escaped::new(trailer.token.to_str_lossy());
+        table.add_custom_body_row(row([&token, &value]));
+    }
+    any.then_some(table)
+}
+
+/// The people credited by the `Co-authored-by` trailers, as signatures at the time of the
+/// commit `author`.
+pub(crate) fn co_authors<'a>(
+    trailers: Trailers<'a>,
+    author: SignatureRef<'a>,
+) -> Vec<SignatureRef<'a>> {
+    trailers
+        .filter(|trailer| trailer.token.eq_ignore_ascii_case(b"co-authored-by"))
+        .filter_map(|trailer| {
+            let value = trailer.value.to_str().ok()?;
+            let (name, email) = parse_ident(value)?;
+            Some(SignatureRef {
+                name: name.into(),
+                email: email.into(),
+                time: author.time,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use gix::objs::commit::message::BodyRef;
+
+    use super::*;
+
+    #[test]
+    fn parse_idents() {
+        assert_eq!(
+            parse_ident("Jane Doe <jane@example.com>"),
+            Some(("Jane Doe", "jane@example.com"))
+        );
+        assert_eq!(parse_ident("Jane Doe"), None);
+        assert_eq!(parse_ident("<jane@example.com"), None);
+    }
+
+    #[test]
+    fn co_authors_from_trailers() {
+        let body = BodyRef::from_bytes(
+            b"Some details.\n\n\
+              Co-authored-by: Jane Doe <jane@example.com>\n\
+              Signed-off-by: Joe Bloggs <joe@example.com>\n\
+              co-authored-by: no email\n\
+              CO-AUTHORED-BY: Ann <ann@example.com>\n",
+        );
+        let author = SignatureRef {
+            name: "Joe Bloggs".into(),
+            email: "joe@example.com".into(),
+            time: "1700000000 +0000",
+        };
+        let co_authors = co_authors(body.trailers(), author);
+        let names = co_authors
+            .iter()
+            .map(|signature| (signature.name.to_string(), signature.email.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("Jane Doe".to_owned(), "jane@example.com".to_owned()),
+                ("Ann".to_owned(), "ann@example.com".to_owned()),
+            ]
+        );
+        assert!(
+            co_authors
+                .iter()
+                .all(|signature| signature.time == author.time)
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+
+use tracing::warn;
+
+/// How strictly to treat problems found when validating generated pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Validation {
+    /// Log problems as warnings but keep building.
+    Warn,
+    /// Fail the build on the first page with problems.
+    Strict,
+}
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Check a generated page for well-formedness problems and report them according to
+/// `validation`.
+pub(crate) fn validate_page(name: &str, html: &str, validation: Validation) -> anyhow::Result<()> {
+    let problems = check_html(html);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    match validation {
+        Validation::Warn => {
+            for problem in &problems {
+                warn!(?name, %problem, "invalid html");
+            }
+            Ok(())
+        }
+        Validation::Strict => {
+            anyhow::bail!(
+                "page {:?} has {} html problems: {}",
+                name,
+                problems.len(),
+                problems.join("; ")
+            )
+        }
+    }
+}
+
+/// Find unescaped `<`, unbalanced tags and duplicate ids in `html`.
+pub(crate) fn check_html(html: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut open_tags: Vec<String> = Vec::new();
+    let mut ids = HashSet::new();
+    let mut rest = html;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find('<') {
+        let pos = offset + start;
+        let tag = &rest[start + 1..];
+        let advance = if tag.starts_with("!--") {
+            match tag.find("-->") {
+                Some(end) => end + 4,
+                None => {
+                    problems.push(format!("unterminated comment at byte {pos}"));
+                    break;
+                }
+            }
+        } else if tag.starts_with('!') || tag.starts_with('/') || starts_with_letter(tag) {
+            let Some(end) = find_tag_end(tag) else {
+                problems.push(format!("unterminated tag at byte {pos}"));
+                break;
+            };
+            let inner = &tag[..end];
+            if let Some(name) = inner.strip_prefix('/') {
+                let name = name.trim().to_ascii_lowercase();
+                match open_tags.iter().rposition(|t| *t == name) {
+                    Some(i) => {
+                        for unclosed in open_tags.drain(i..).skip(1) {
+                            problems.push(format!("unclosed <{unclosed}> before </{name}>"));
+                        }
+                    }
+                    None => problems.push(format!("stray </{name}> at byte {pos}")),
+                }
+            } else if !inner.starts_with('!') {
+                let name = inner
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if let Some(id) = attribute(inner, "id")
+                    && !ids.insert(id.to_owned())
+                {
+                    problems.push(format!("duplicate id {id:?}"));
+                }
+                if !inner.ends_with('/') && !VOID_ELEMENTS.contains(&name.as_str()) {
+                    if name == "script" || name == "style" {
+                        // raw text elements, skip straight to their closing tag
+                        let close = format!("</{name}");
+                        if let Some(body_end) = tag[end..].find(&close) {
+                            let skip = start + 1 + end + body_end;
+                            offset += skip;
+                            rest = &rest[skip..];
+                            open_tags.push(name);
+                            continue;
+                        }
+                    }
+                    open_tags.push(name);
+                }
+            }
+            end + 2
+        } else {
+            problems.push(format!("unescaped '<' at byte {pos}"));
+            1
+        };
+        let skip = (start + advance).min(rest.len());
+        offset += skip;
+        rest = &rest[skip..];
+    }
+
+    for unclosed in open_tags {
+        problems.push(format!("unclosed <{unclosed}> at end of page"));
+    }
+    problems
+}
+
+fn starts_with_letter(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+/// Find the `>` closing a tag, skipping over quoted attribute values.
+fn find_tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {name}=");
+    let start = tag.find(&needle)? + needle.len();
+    let value = &tag[start..];
+    let quote = value.chars().next()?;
+    if quote == '"' || quote == '\'' {
+        let end = value[1..].find(quote)?;
+        Some(&value[1..end + 1])
+    } else {
+        let end = value
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(value.len());
+        Some(&value[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn well_formed_page() {
+        let html = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>a &lt; b</title>\
+                    </head><body><!-- <b> --><p id=\"a\">text<br><img src=\"x\" alt=\"a > b\">\
+                    </p><script>if (a < b) {}</script></body></html>";
+        assert_eq!(check_html(html), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unescaped_less_than() {
+        assert_eq!(check_html("<p>a < b</p>"), ["unescaped '<' at byte 5"]);
+    }
+
+    #[test]
+    fn unbalanced_tags() {
+        assert_eq!(
+            check_html("<div><span></div>"),
+            ["unclosed <span> before </div>"]
+        );
+        assert_eq!(check_html("<p></b></p>"), ["stray </b> at byte 3"]);
+        assert_eq!(check_html("<p>"), ["unclosed <p> at end of page"]);
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        assert_eq!(
+            check_html("<a id=\"l1\"></a><a id='l1'></a>"),
+            ["duplicate id \"l1\""]
+        );
+    }
+
+    #[test]
+    fn unterminated_tags_and_comments() {
+        assert_eq!(check_html("<p"), ["unterminated tag at byte 0"]);
+        assert_eq!(check_html("<!-- x"), ["unterminated comment at byte 0"]);
+    }
+
+    #[test]
+    fn only_strict_validation_fails() {
+        assert!(validate_page("page", "<p>", Validation::Strict).is_err());
+        assert!(validate_page("page", "<p>", Validation::Warn).is_ok());
+    }
+}
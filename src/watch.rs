@@ -0,0 +1,81 @@
+use std::path::Path;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor};
+use tracing::{debug, info, warn};
+
+use crate::{RepoOptions, build_repo_pages};
+
+/// How long to let a burst of changes, such as from a push, settle before rebuilding.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Files directly in the git dir whose changes are worth a rebuild.
+const WATCHED_FILES: [&str; 2] = ["HEAD", "packed-refs"];
+
+/// Build the pages for a repo, then build them again whenever its refs change, only returning if
+/// the repo can no longer be watched.
+///
+/// Each build skips commit pages cached from the last one, so only new commits are rendered.
+pub fn watch_repo(repo_path: &Path, options: &RepoOptions) -> anyhow::Result<()> {
+    let git_dir = gix::open(repo_path).context("open repo")?.path().to_owned();
+    info!(?git_dir, "watching repo");
+    loop {
+        // watches are set up before building so changes made during the build aren't missed,
+        // and again each time to pick up new ref directories
+        let (inotify, git_dir_watch) = watch_git_dir(&git_dir).context("watch git dir")?;
+        if let Err(error) = build_repo_pages(repo_path, options) {
+            warn!(?repo_path, %error, "Failed to build repo pages");
+        }
+        wait_for_change(&inotify, git_dir_watch)?;
+        info!(?repo_path, "repo changed, rebuilding");
+    }
+}
+
+/// Watch the git dir itself, for `HEAD` and `packed-refs`, and every directory under `refs/`,
+/// returning the watch of the git dir.
+fn watch_git_dir(git_dir: &Path) -> anyhow::Result<(Inotify, WatchDescriptor)> {
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC)?;
+    let flags = AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_MOVED_TO
+        | AddWatchFlags::IN_CREATE
+        | AddWatchFlags::IN_DELETE;
+    let git_dir_watch = inotify.add_watch(git_dir, flags)?;
+    let mut dirs = vec![git_dir.join("refs")];
+    while let Some(dir) = dirs.pop() {
+        inotify.add_watch(&dir, flags)?;
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    Ok((inotify, git_dir_watch))
+}
+
+/// Block until a ref changes, ignoring the index, `FETCH_HEAD` and lock files that git writes
+/// along the way, then wait for it to settle.
+fn wait_for_change(inotify: &Inotify, git_dir_watch: WatchDescriptor) -> anyhow::Result<()> {
+    loop {
+        let events = inotify.read_events()?;
+        let changed = events.iter().any(|event| {
+            let Some(name) = event.name.as_ref().and_then(|name| name.to_str()) else {
+                return false;
+            };
+            if event.wd == git_dir_watch {
+                WATCHED_FILES.contains(&name)
+            } else {
+                !name.ends_with(".lock")
+            }
+        });
+        if changed {
+            debug!(events = events.len(), "refs changed");
+            break;
+        }
+    }
+    // changes from here on are picked up by the next build, which watches afresh
+    sleep(SETTLE_TIME);
+    Ok(())
+}